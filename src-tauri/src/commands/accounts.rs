@@ -0,0 +1,248 @@
+//! 多网址 / 多账号 Commands
+
+use crate::models::{EncryptableField, ItemType, PasswordAccount, PasswordSearchResult, PasswordUrl, UrlMatchRule};
+use crate::services::url_match;
+use crate::AppState;
+use serde_json::{json, Value};
+use tauri::State;
+
+/// 获取密码条目的附加网址
+#[tauri::command]
+pub async fn get_password_urls(
+    state: State<'_, AppState>,
+    password_id: i64,
+) -> Result<Vec<PasswordUrl>, String> {
+    state.ensure_unlocked()?;
+    let cipher = state.field_cipher()?;
+    let mut urls = state.db.get_password_urls(password_id)?;
+    for url in &mut urls {
+        url.url = cipher
+            .decrypt_value(EncryptableField::PasswordUrl, Some(std::mem::take(&mut url.url)))
+            .unwrap_or_default();
+    }
+    Ok(urls)
+}
+
+/// 以新列表替换密码条目的附加网址（顺序即排序）
+#[tauri::command]
+pub async fn set_password_urls(
+    state: State<'_, AppState>,
+    password_id: i64,
+    urls: Vec<PasswordUrl>,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("set_password_urls called: password_id={}, count={}", password_id, urls.len());
+    if state.db.get_password(password_id)?.is_none() {
+        return Err("Password not found".to_string());
+    }
+
+    let cipher = state.field_cipher()?;
+    let mut stored = Vec::with_capacity(urls.len());
+    for mut url in urls {
+        let trimmed = url.url.trim().to_string();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if url_match::host(&trimmed).is_none() {
+            return Err(format!("无效的网址: {}", trimmed));
+        }
+        url.url = cipher
+            .encrypt_value(EncryptableField::PasswordUrl, Some(&trimmed))?
+            .unwrap_or_default();
+        stored.push(url);
+    }
+    state.db.replace_password_urls(password_id, &stored)?;
+    super::search::refresh_search_index(&state, ItemType::Login, password_id);
+    Ok(json!({ "success": true }))
+}
+
+/// 获取密码条目的附加账号（密码已解密）
+#[tauri::command]
+pub async fn get_password_accounts(
+    state: State<'_, AppState>,
+    password_id: i64,
+) -> Result<Vec<PasswordAccount>, String> {
+    state.ensure_unlocked()?;
+    let cipher = state.field_cipher()?;
+    let encryption = state.encryption_service()?;
+    let mut accounts = state.db.get_password_accounts(password_id)?;
+    for account in &mut accounts {
+        decrypt_account(&cipher, &encryption, account);
+    }
+    Ok(accounts)
+}
+
+/// 添加附加账号
+#[tauri::command]
+pub async fn add_password_account(
+    state: State<'_, AppState>,
+    mut account: PasswordAccount,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("add_password_account called: password_id={}", account.password_id);
+    if state.db.get_password(account.password_id)?.is_none() {
+        return Err("Password not found".to_string());
+    }
+    let (cipher, encryption) = (state.field_cipher()?, state.encryption_service()?);
+    encrypt_account(&cipher, &encryption, &mut account)?;
+    let id = state.db.add_password_account(&account)?;
+    Ok(json!({ "success": true, "id": id }))
+}
+
+/// 更新附加账号（不可移动到其他条目）
+#[tauri::command]
+pub async fn update_password_account(
+    state: State<'_, AppState>,
+    id: i64,
+    mut account: PasswordAccount,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("update_password_account called: id={}", id);
+    let existing = state
+        .db
+        .get_password_account(id)?
+        .ok_or("Account not found")?;
+    account.id = Some(id);
+    account.password_id = existing.password_id;
+    let (cipher, encryption) = (state.field_cipher()?, state.encryption_service()?);
+    encrypt_account(&cipher, &encryption, &mut account)?;
+    state.db.update_password_account(&account)?;
+    Ok(json!({ "success": true }))
+}
+
+/// 删除附加账号
+#[tauri::command]
+pub async fn delete_password_account(
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    state.db.delete_password_account(id)?;
+    Ok(json!({ "success": true }))
+}
+
+/// 查找网址匹配的密码条目
+///
+/// 主网址按主机名匹配，附加网址按各自的匹配规则匹配。
+#[tauri::command]
+pub async fn find_passwords_by_url(
+    state: State<'_, AppState>,
+    url: String,
+) -> Result<Vec<PasswordSearchResult>, String> {
+    state.ensure_unlocked()?;
+    let cipher = state.field_cipher()?;
+
+    let matched_ids: std::collections::HashSet<i64> = state
+        .db
+        .get_all_password_urls()?
+        .into_iter()
+        .filter(|saved| {
+            cipher
+                .decrypt_value(EncryptableField::PasswordUrl, Some(saved.url.clone()))
+                .is_some_and(|saved_url| url_match::matches(saved.match_rule, &saved_url, &url))
+        })
+        .map(|saved| saved.password_id)
+        .collect();
+
+    let mut passwords = state.db.get_passwords(None)?;
+    passwords.iter_mut().for_each(|p| cipher.decrypt_password(p));
+    passwords.retain(|p| {
+        p.id.is_some_and(|id| matched_ids.contains(&id))
+            || p
+                .url
+                .as_deref()
+                .is_some_and(|primary| url_match::matches(UrlMatchRule::Host, primary, &url))
+    });
+    // 网址匹配不区分相关度
+    super::passwords::to_search_results(&state, passwords.into_iter().map(|p| (p, 1.0)).collect())
+}
+
+fn encrypt_account(
+    cipher: &crate::services::field_encryption::FieldCipher,
+    encryption: &crate::services::encryption::EncryptionService,
+    account: &mut PasswordAccount,
+) -> Result<(), String> {
+    account.username = cipher.encrypt_value(EncryptableField::PasswordUsername, account.username.as_deref())?;
+    if let Some(plain) = account.password.as_ref().filter(|p| !p.is_empty()) {
+        account.password = Some(encryption.encrypt(plain.expose())?.into());
+    }
+    Ok(())
+}
+
+fn decrypt_account(
+    cipher: &crate::services::field_encryption::FieldCipher,
+    encryption: &crate::services::encryption::EncryptionService,
+    account: &mut PasswordAccount,
+) {
+    account.username = cipher.decrypt_value(EncryptableField::PasswordUsername, account.username.take());
+    if let Some(stored) = account.password.as_ref().filter(|p| !p.is_empty()) {
+        match encryption.decrypt(stored.expose()) {
+            Ok(plain) => account.password = Some(plain),
+            Err(e) => log::warn!("解密附加账号密码失败 (id={:?}): {}", account.id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_account_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        let account: PasswordAccount = serde_json::from_value(json!({ "passwordId": 1 })).unwrap();
+        assert_vault_locked(get_password_urls(app.state(), 1).await);
+        assert_vault_locked(set_password_urls(app.state(), 1, Vec::new()).await);
+        assert_vault_locked(get_password_accounts(app.state(), 1).await);
+        assert_vault_locked(add_password_account(app.state(), account.clone()).await);
+        assert_vault_locked(update_password_account(app.state(), 1, account).await);
+        assert_vault_locked(delete_password_account(app.state(), 1).await);
+        assert_vault_locked(find_passwords_by_url(app.state(), "https://a.com".to_string()).await);
+    }
+
+    #[tokio::test]
+    async fn test_accounts_and_url_matching() {
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let password: crate::models::Password =
+            serde_json::from_value(json!({ "title": "Cloud", "url": "https://console.example.com" })).unwrap();
+        let id = state.db.add_password(&password).unwrap();
+
+        let urls: Vec<PasswordUrl> =
+            serde_json::from_value(json!([{ "url": "https://sso.example.net/login", "matchRule": "base_domain" }]))
+                .unwrap();
+        set_password_urls(app.state(), id, urls).await.unwrap();
+        let account: PasswordAccount = serde_json::from_value(json!({
+            "passwordId": id, "label": "Admin", "username": "root", "password": "s3cret"
+        }))
+        .unwrap();
+        add_password_account(app.state(), account).await.unwrap();
+
+        assert_ne!(
+            state.db.get_password_accounts(id).unwrap()[0].password.as_ref().map(|p| p.expose().to_string()),
+            Some("s3cret".to_string())
+        );
+        let accounts = get_password_accounts(app.state(), id).await.unwrap();
+        assert_eq!(accounts[0].password.as_ref().map(|p| p.expose()), Some("s3cret"));
+
+        let by_primary = find_passwords_by_url(app.state(), "https://console.example.com/home".to_string())
+            .await
+            .unwrap();
+        assert_eq!(by_primary.len(), 1);
+        let by_base_domain = find_passwords_by_url(app.state(), "https://id.example.net".to_string())
+            .await
+            .unwrap();
+        assert_eq!(by_base_domain.len(), 1);
+        assert!(find_passwords_by_url(app.state(), "https://example.org".to_string())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,39 @@
+//! 密码健康检查 Commands
+
+use crate::models::PasswordAuditReport;
+use crate::services::audit;
+use crate::AppState;
+use tauri::State;
+
+/// 检查全部密码条目的强度、重复使用、密码年龄与网址问题
+///
+/// `max_age_days` 为密码最长使用天数，未指定时为 365，0 表示不检查。
+#[tauri::command]
+pub async fn audit_passwords(
+    state: State<'_, AppState>,
+    max_age_days: Option<u32>,
+) -> Result<PasswordAuditReport, String> {
+    state.ensure_unlocked()?;
+    log::info!("audit_passwords called");
+    audit::run(
+        &state.db,
+        &*state.encryption_service()?,
+        &*state.field_cipher()?,
+        max_age_days.unwrap_or(audit::DEFAULT_MAX_AGE_DAYS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_audit_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(audit_passwords(app.state(), None).await);
+    }
+}
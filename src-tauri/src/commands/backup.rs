@@ -4,8 +4,22 @@
 //!   导入: 备份JSON(明文) -> encrypt -> DB(密文)
 //!   导出: DB(密文) -> decrypt -> 备份JSON(明文)
 //!   云端: DB(密文) -> decrypt -> encrypted_zip -> COS
+//!
+//! 附件仅随加密ZIP导出：每个附件解密后作为独立的 `attachments/<id>` 条目写入压缩包，
+//! backup.json 中只记录元数据与条目名；JSON 格式不包含附件。
 
+use crate::models::{
+    Attachment, CustomField, CustomFieldType, EncryptableField, ItemType, PasswordAccount, PasswordUrl, SecretBytes, SecretString,
+    UrlMatchRule, VaultItem,
+};
+use crate::services::attachments;
 use crate::services::encryption::EncryptionService;
+use crate::services::custom_fields;
+use crate::services::database::DatabaseService;
+use crate::services::field_encryption::{self, FieldCipher};
+use crate::services::items;
+use crate::services::totp;
+use crate::services::vault;
 use crate::AppState;
 use chrono::{Datelike, Local, Timelike, Utc};
 use hmac::{Hmac, Mac};
@@ -27,6 +41,8 @@ use tauri_plugin_dialog::DialogExt;
 type HmacSha256 = Hmac<Sha256>;
 
 const BACKUP_FILENAME_PREFIX: &str = "myloair-backup-";
+const BACKUP_JSON_ENTRY: &str = "backup.json";
+const ATTACHMENT_ENTRY_PREFIX: &str = "attachments/";
 const CLOUD_PROVIDER: &str = "cos";
 const AWS_SERVICE_NAME: &str = "s3";
 const BACKUP_FAILURE_NOTIFY_COOLDOWN_SECS: u64 = 300;
@@ -49,7 +65,7 @@ struct BackupConfig {
     auto_export_frequency: String,
     auto_export_directory: String,
     auto_export_format: String,
-    auto_export_password: Option<String>,
+    auto_export_password: Option<SecretString>,
     auto_export_time_of_day: String,
     auto_export_day_of_week: i64,
     auto_export_day_of_month: i64,
@@ -60,8 +76,8 @@ struct BackupConfig {
     cloud_bucket: String,
     cloud_region: String,
     cloud_path_prefix: String,
-    cloud_secret_id: Option<String>,
-    cloud_secret_key: Option<String>,
+    cloud_secret_id: Option<SecretString>,
+    cloud_secret_key: Option<SecretString>,
 }
 
 #[derive(Debug, Serialize)]
@@ -109,9 +125,9 @@ pub struct SaveBackupConfigInput {
     bucket: Option<String>,
     region: Option<String>,
     path_prefix: Option<String>,
-    secret_id: Option<String>,
-    secret_key: Option<String>,
-    export_default_password: Option<String>,
+    secret_id: Option<SecretString>,
+    secret_key: Option<SecretString>,
+    export_default_password: Option<SecretString>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -121,9 +137,9 @@ pub struct TestBackupCloudInput {
     bucket: String,
     region: String,
     path_prefix: Option<String>,
-    secret_id: Option<String>,
-    secret_key: Option<String>,
-    export_default_password: Option<String>,
+    secret_id: Option<SecretString>,
+    secret_key: Option<SecretString>,
+    export_default_password: Option<SecretString>,
 }
 
 #[derive(Debug, Serialize)]
@@ -141,8 +157,110 @@ struct CloudConfig {
     bucket: String,
     region: String,
     path_prefix: String,
-    secret_id: String,
-    secret_key: String,
+    secret_id: SecretString,
+    secret_key: SecretString,
+}
+
+/// 导出文件结构；明文字段使用 SecretString，序列化时不经过 `serde_json::Value` 中转
+#[derive(Serialize)]
+struct BackupDocument {
+    version: &'static str,
+    exported_at: String,
+    app_name: &'static str,
+    passwords: Vec<BackupPasswordEntry>,
+    groups: Vec<Value>,
+    note_groups: Vec<Value>,
+    notes: Vec<BackupNoteEntry>,
+    /// 结构化条目（银行卡、身份等）
+    items: Vec<BackupItemEntry>,
+    user_settings: Vec<Value>,
+    password_history: Vec<Value>,
+}
+
+#[derive(Serialize)]
+struct BackupPasswordEntry {
+    id: Option<i64>,
+    title: String,
+    username: Option<String>,
+    password: Option<SecretString>,
+    url: Option<String>,
+    notes: Option<String>,
+    /// 附加网址与附加账号；均为空时为 null
+    multi_accounts: Option<BackupMultiAccounts>,
+    /// TOTP 配置（otpauth URI）
+    totp: Option<SecretString>,
+    custom_fields: Vec<BackupCustomFieldEntry>,
+    attachments: Vec<BackupAttachmentEntry>,
+    group_id: Option<i64>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+struct BackupMultiAccounts {
+    urls: Vec<BackupUrlEntry>,
+    accounts: Vec<BackupAccountEntry>,
+}
+
+#[derive(Serialize)]
+struct BackupUrlEntry {
+    url: String,
+    match_rule: &'static str,
+}
+
+#[derive(Serialize)]
+struct BackupAccountEntry {
+    label: Option<String>,
+    username: Option<String>,
+    password: Option<SecretString>,
+}
+
+#[derive(Serialize)]
+struct BackupCustomFieldEntry {
+    field_type: &'static str,
+    label: String,
+    value: SecretString,
+    sort_order: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct BackupAttachmentEntry {
+    /// 压缩包中存放附件内容的条目名
+    entry: String,
+    file_name: String,
+    mime_type: Option<String>,
+    size: i64,
+}
+
+/// 随 backup.json 一同写入加密ZIP的附件内容
+struct BackupArchiveFile {
+    name: String,
+    data: SecretBytes,
+}
+
+#[derive(Serialize)]
+struct BackupNoteEntry {
+    id: Option<i64>,
+    title: String,
+    content_ciphertext: Option<SecretString>,
+    attachments: Vec<BackupAttachmentEntry>,
+    group_id: Option<i64>,
+    pinned: Option<i32>,
+    archived: Option<i32>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BackupItemEntry {
+    item_type: &'static str,
+    title: String,
+    group_id: Option<i64>,
+    notes: Option<String>,
+    favorite: Option<bool>,
+    fields: std::collections::BTreeMap<String, SecretString>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -171,6 +289,7 @@ pub async fn export_data(
     state: State<'_, AppState>,
     options: Value,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("export_data called");
 
     let format = options
@@ -179,16 +298,14 @@ pub async fn export_data(
         .unwrap_or("json");
     let archive_password = options.get("archivePassword").and_then(|v| v.as_str());
 
-    let json_bytes = build_backup_json_bytes(&state)?;
-
-    let output_bytes = if format == "encrypted_zip" {
+    let data: Vec<i32> = if format == "encrypted_zip" {
         let password = archive_password.ok_or("加密ZIP格式需要提供 archivePassword")?;
-        create_encrypted_zip(&json_bytes, password)?
+        let zip_bytes = build_encrypted_backup_bytes(&state, password)?;
+        zip_bytes.iter().map(|b| *b as i32).collect()
     } else {
-        json_bytes
+        let json_bytes = build_backup_json_bytes(&state)?;
+        json_bytes.expose().iter().map(|b| *b as i32).collect()
     };
-
-    let data: Vec<i32> = output_bytes.iter().map(|b| *b as i32).collect();
     Ok(json!({ "success": true, "data": data }))
 }
 
@@ -198,6 +315,7 @@ pub async fn export_data_to_file(
     state: State<'_, AppState>,
     options: Value,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("export_data_to_file called");
 
     let file_path = options
@@ -230,25 +348,26 @@ pub async fn import_data(
     data: Vec<u8>,
     options: Value,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("import_data called, data length: {}", data.len());
 
     let is_zip = data.len() >= 2 && data[0] == 0x50 && data[1] == 0x4B;
 
-    let json_bytes = if is_zip {
+    let (json_bytes, attachment_files) = if is_zip {
         let password = options
             .get("archivePassword")
             .and_then(|v| v.as_str())
             .ok_or("导入加密ZIP需要提供密码")?;
         read_encrypted_zip(&data, password)?
     } else {
-        data
+        (data, HashMap::new())
     };
 
     let backup: Value =
         serde_json::from_slice(&json_bytes).map_err(|e| format!("JSON 解析失败: {}", e))?;
 
     let db = &state.db;
-    let encryption = &state.encryption;
+    let encryption = &*state.encryption_service()?;
     let conn = db
         .get_connection()
         .map_err(|e| format!("数据库连接失败: {}", e))?;
@@ -256,11 +375,12 @@ pub async fn import_data(
     conn.execute_batch("BEGIN TRANSACTION;")
         .map_err(|e| e.to_string())?;
 
-    let result = do_import(&conn, &backup, encryption);
+    let result = do_import(&conn, &backup, encryption, &attachment_files);
 
     match result {
         Ok(stats) => {
             conn.execute_batch("COMMIT;").map_err(|e| e.to_string())?;
+            super::search::invalidate_search_index(&state);
             Ok(json!({
                 "success": true,
                 "data": {
@@ -279,6 +399,7 @@ pub async fn import_data(
 
 #[tauri::command]
 pub async fn get_backup_config(state: State<'_, AppState>) -> Result<BackupConfigResponse, String> {
+    state.ensure_unlocked()?;
     let config = load_backup_config(&state)?;
     Ok(BackupConfigResponse {
         target_mode: config.target_mode,
@@ -296,16 +417,16 @@ pub async fn get_backup_config(state: State<'_, AppState>) -> Result<BackupConfi
         bucket: config.cloud_bucket,
         region: config.cloud_region,
         path_prefix: config.cloud_path_prefix,
-        secret_id_masked: config.cloud_secret_id.as_deref().map(mask_secret_id),
+        secret_id_masked: config.cloud_secret_id.as_ref().map(|s| mask_secret_id(s.expose())),
         has_secret_key: config
             .cloud_secret_key
             .as_ref()
-            .map(|s| !s.trim().is_empty())
+            .map(|s| !s.expose().trim().is_empty())
             .unwrap_or(false),
         has_archive_password: config
             .auto_export_password
             .as_ref()
-            .map(|s| !s.trim().is_empty())
+            .map(|s| !s.expose().trim().is_empty())
             .unwrap_or(false),
         failure_notification_cooldown_minutes: BACKUP_FAILURE_NOTIFY_COOLDOWN_SECS / 60,
         last_manual_run: get_backup_run_status(&state, "last_manual"),
@@ -318,6 +439,7 @@ pub async fn save_backup_config(
     state: State<'_, AppState>,
     input: SaveBackupConfigInput,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     if let Some(target_mode) = input.target_mode {
         if target_mode != "local" && target_mode != "cos" {
             return Err("targetMode 仅支持 local 或 cos".to_string());
@@ -333,7 +455,7 @@ pub async fn save_backup_config(
     }
 
     if let Some(retention_count) = input.retention_count {
-        let value = retention_count.max(1).min(365).to_string();
+        let value = retention_count.clamp(1, 365).to_string();
         save_plain_setting(
             &state,
             "backup.retention_count",
@@ -395,7 +517,7 @@ pub async fn save_backup_config(
     )?;
 
     if let Some(secret_id) = input.secret_id {
-        let trimmed = secret_id.trim();
+        let trimmed = secret_id.expose().trim();
         if !trimmed.is_empty() {
             save_sensitive_setting(
                 &state,
@@ -409,7 +531,7 @@ pub async fn save_backup_config(
     }
 
     if let Some(secret_key) = input.secret_key {
-        let trimmed = secret_key.trim();
+        let trimmed = secret_key.expose().trim();
         if !trimmed.is_empty() {
             save_sensitive_setting(
                 &state,
@@ -423,7 +545,7 @@ pub async fn save_backup_config(
     }
 
     if let Some(password) = input.export_default_password {
-        let trimmed = password.trim();
+        let trimmed = password.expose().trim();
         if !trimmed.is_empty() {
             save_sensitive_setting(
                 &state,
@@ -444,6 +566,7 @@ pub async fn test_backup_cloud_connection(
     state: State<'_, AppState>,
     input: TestBackupCloudInput,
 ) -> Result<CloudTestResponse, String> {
+    state.ensure_unlocked()?;
     let client = build_http_client()?;
     let config = resolve_test_cloud_config(&state, &input).await?;
     let archive_password = resolve_test_archive_password(&state, &input)?;
@@ -452,7 +575,7 @@ pub async fn test_backup_cloud_connection(
         "generatedAt": Local::now().to_rfc3339(),
     })
     .to_string();
-    let test_zip = create_encrypted_zip(test_payload.as_bytes(), &archive_password)
+    let test_zip = create_encrypted_zip(test_payload.as_bytes(), &[], archive_password.expose())
         .map_err(|e| format!("生成加密测试备份失败: {}", e))?;
 
     let test_key = format!(
@@ -489,6 +612,7 @@ pub async fn trigger_manual_cloud_backup(
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     let result = execute_backup_run(&app, &state, "manual", "cos").await;
 
     match result {
@@ -592,9 +716,18 @@ struct BackupExecutionOutcome {
     file_path: Option<String>,
 }
 
-fn build_backup_json_bytes(state: &State<'_, AppState>) -> Result<Vec<u8>, String> {
+fn build_backup_json_bytes(state: &State<'_, AppState>) -> Result<SecretBytes, String> {
+    build_backup_document(state, false).map(|(json_bytes, _)| json_bytes)
+}
+
+/// 生成 backup.json；`include_attachments` 为真时同时解密全部附件作为压缩包条目返回
+fn build_backup_document(
+    state: &State<'_, AppState>,
+    include_attachments: bool,
+) -> Result<(SecretBytes, Vec<BackupArchiveFile>), String> {
     let db = &state.db;
-    let encryption = &state.encryption;
+    let encryption = &*state.encryption_service()?;
+    let cipher = FieldCipher::new(field_encryption::load_policy(db)?, encryption.clone());
     let conn = db
         .get_connection()
         .map_err(|e| format!("数据库连接失败: {}", e))?;
@@ -625,11 +758,85 @@ fn build_backup_json_bytes(state: &State<'_, AppState>) -> Result<Vec<u8>, Strin
         }
     }
 
-    let mut passwords_arr: Vec<Value> = Vec::new();
+    let mut totp_map: HashMap<i64, SecretString> = HashMap::new();
+    for mut config in db.get_all_password_totp()? {
+        match encryption.decrypt(config.secret.expose()) {
+            Ok(secret) => {
+                config.secret = secret;
+                totp_map.insert(config.password_id, totp::to_otpauth_uri(&config));
+            }
+            Err(e) => log::warn!("解密 TOTP 密钥失败 (password_id={}): {}", config.password_id, e),
+        }
+    }
+
+    let mut custom_field_map: HashMap<i64, Vec<BackupCustomFieldEntry>> = HashMap::new();
+    for mut field in db.get_all_custom_fields()? {
+        custom_fields::decrypt_field(encryption, &mut field);
+        custom_field_map
+            .entry(field.password_id)
+            .or_default()
+            .push(BackupCustomFieldEntry {
+                field_type: field.field_type.as_str(),
+                label: field.label,
+                value: field.value,
+                sort_order: field.sort_order,
+            });
+    }
+
+    let mut multi_accounts_map: HashMap<i64, BackupMultiAccounts> = HashMap::new();
+    for url in db.get_all_password_urls()? {
+        multi_accounts_map.entry(url.password_id).or_default().urls.push(BackupUrlEntry {
+            url: cipher
+                .decrypt_value(EncryptableField::PasswordUrl, Some(url.url))
+                .unwrap_or_default(),
+            match_rule: url.match_rule.as_str(),
+        });
+    }
+    for account in db.get_all_password_accounts()? {
+        let password = account.password.map(|p| p.expose().to_string());
+        multi_accounts_map
+            .entry(account.password_id)
+            .or_default()
+            .accounts
+            .push(BackupAccountEntry {
+                label: account.label,
+                username: cipher.decrypt_value(EncryptableField::PasswordUsername, account.username),
+                password: decrypt_field(encryption, &password),
+            });
+    }
+
+    let mut archive_files: Vec<BackupArchiveFile> = Vec::new();
+    let mut password_attachment_map: HashMap<i64, Vec<BackupAttachmentEntry>> = HashMap::new();
+    let mut note_attachment_map: HashMap<i64, Vec<BackupAttachmentEntry>> = HashMap::new();
+    if include_attachments {
+        for attachment in db.get_all_attachments()? {
+            let data = match attachments::read(db, encryption, &attachment) {
+                Ok(data) => data,
+                Err(e) => {
+                    log::warn!("解密附件失败，备份中将缺少该附件 (id={:?}): {}", attachment.id, e);
+                    continue;
+                }
+            };
+            let entry = BackupAttachmentEntry {
+                entry: format!("{}{}", ATTACHMENT_ENTRY_PREFIX, attachment.id.unwrap_or_default()),
+                file_name: attachment.file_name,
+                mime_type: attachment.mime_type,
+                size: attachment.size,
+            };
+            archive_files.push(BackupArchiveFile { name: entry.entry.clone(), data });
+            match (attachment.password_id, attachment.note_id) {
+                (Some(password_id), _) => password_attachment_map.entry(password_id).or_default().push(entry),
+                (None, Some(note_id)) => note_attachment_map.entry(note_id).or_default().push(entry),
+                (None, None) => {}
+            }
+        }
+    }
+
+    let mut passwords_arr: Vec<BackupPasswordEntry> = Vec::new();
     {
         let mut stmt = conn
             .prepare(
-                "SELECT id, title, username, password, url, notes, group_id, created_at, updated_at FROM passwords ORDER BY id",
+                "SELECT id, title, username, password, url, notes, group_id, created_at, updated_at FROM passwords WHERE deleted_at IS NULL ORDER BY id",
             )
             .map_err(|e| e.to_string())?;
         let rows = stmt
@@ -650,19 +857,25 @@ fn build_backup_json_bytes(state: &State<'_, AppState>) -> Result<Vec<u8>, Strin
         for row in rows {
             let (id, title, username, cipher_pwd, url, notes, group_id, created_at, updated_at) =
                 row.map_err(|e| e.to_string())?;
-            let plain_pwd = decrypt_field(encryption, &cipher_pwd);
-            passwords_arr.push(json!({
-                "id": id,
-                "title": title,
-                "username": username,
-                "password": plain_pwd,
-                "url": url,
-                "notes": notes,
-                "multi_accounts": Value::Null,
-                "group_id": group_id,
-                "created_at": created_at,
-                "updated_at": updated_at
-            }));
+            passwords_arr.push(BackupPasswordEntry {
+                id,
+                title,
+                username: cipher.decrypt_value(EncryptableField::PasswordUsername, username),
+                password: decrypt_field(encryption, &cipher_pwd),
+                url: cipher.decrypt_value(EncryptableField::PasswordUrl, url),
+                notes: cipher.decrypt_value(EncryptableField::PasswordNotes, notes),
+                multi_accounts: id.and_then(|id| multi_accounts_map.remove(&id)),
+                totp: id.and_then(|id| totp_map.remove(&id)),
+                custom_fields: id
+                    .and_then(|id| custom_field_map.remove(&id))
+                    .unwrap_or_default(),
+                attachments: id
+                    .and_then(|id| password_attachment_map.remove(&id))
+                    .unwrap_or_default(),
+                group_id,
+                created_at,
+                updated_at,
+            });
         }
     }
 
@@ -691,11 +904,11 @@ fn build_backup_json_bytes(state: &State<'_, AppState>) -> Result<Vec<u8>, Strin
         }
     }
 
-    let mut notes_arr: Vec<Value> = Vec::new();
+    let mut notes_arr: Vec<BackupNoteEntry> = Vec::new();
     {
         let mut stmt = conn
             .prepare(
-                "SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records ORDER BY id",
+                "SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records WHERE deleted_at IS NULL ORDER BY id",
             )
             .map_err(|e| e.to_string())?;
         let rows = stmt
@@ -715,20 +928,42 @@ fn build_backup_json_bytes(state: &State<'_, AppState>) -> Result<Vec<u8>, Strin
         for row in rows {
             let (id, title, cipher_content, group_id, pinned, archived, created_at, updated_at) =
                 row.map_err(|e| e.to_string())?;
-            let plain_content = decrypt_field(encryption, &cipher_content);
-            notes_arr.push(json!({
-                "id": id,
-                "title": title,
-                "content_ciphertext": plain_content,
-                "group_id": group_id,
-                "pinned": pinned,
-                "archived": archived,
-                "created_at": created_at,
-                "updated_at": updated_at
-            }));
+            notes_arr.push(BackupNoteEntry {
+                id,
+                title: cipher
+                    .decrypt_value(EncryptableField::NoteTitle, Some(title))
+                    .unwrap_or_default(),
+                content_ciphertext: decrypt_field(encryption, &cipher_content),
+                attachments: id
+                    .and_then(|id| note_attachment_map.remove(&id))
+                    .unwrap_or_default(),
+                group_id,
+                pinned,
+                archived,
+                created_at,
+                updated_at,
+            });
         }
     }
 
+    let items_arr: Vec<BackupItemEntry> = db
+        .get_vault_items(None)?
+        .into_iter()
+        .map(|mut item| {
+            cipher.decrypt_item(&mut item);
+            BackupItemEntry {
+                item_type: item.item_type.as_str(),
+                title: item.title,
+                group_id: item.group_id,
+                notes: item.notes,
+                favorite: item.favorite,
+                fields: item.fields,
+                created_at: item.created_at,
+                updated_at: item.updated_at,
+            }
+        })
+        .collect();
+
     let mut settings_arr: Vec<Value> = Vec::new();
     {
         let mut stmt = conn
@@ -754,6 +989,25 @@ fn build_backup_json_bytes(state: &State<'_, AppState>) -> Result<Vec<u8>, Strin
             settings_arr.push(row.map_err(|e| e.to_string())?);
         }
     }
+    // 敏感配置以本保险库的数据密钥加密，导出明文并标记，导入时用目标保险库的密钥重新加密
+    settings_arr.retain_mut(|setting| {
+        let key = setting["key"].as_str().unwrap_or_default();
+        if !vault::SENSITIVE_SETTING_KEYS.contains(&key) {
+            return true;
+        }
+        match setting["value"].as_str().filter(|v| !v.trim().is_empty()).map(|v| encryption.decrypt(v)) {
+            Some(Ok(plain)) => {
+                setting["value"] = json!(plain.expose());
+                setting["sensitive"] = json!(true);
+                true
+            }
+            Some(Err(e)) => {
+                log::warn!("Skipping sensitive setting {} in backup: {}", key, e);
+                false
+            }
+            None => false,
+        }
+    });
 
     let mut history_arr: Vec<Value> = Vec::new();
     {
@@ -778,31 +1032,39 @@ fn build_backup_json_bytes(state: &State<'_, AppState>) -> Result<Vec<u8>, Strin
         }
     }
 
-    let backup = json!({
-        "version": "1.0",
-        "exported_at": chrono_now_iso(),
-        "app_name": "Password Manager",
-        "passwords": passwords_arr,
-        "groups": groups_arr,
-        "note_groups": note_groups_arr,
-        "notes": notes_arr,
-        "user_settings": settings_arr,
-        "password_history": history_arr
-    });
+    let backup = BackupDocument {
+        version: "1.0",
+        exported_at: chrono_now_iso(),
+        app_name: "Password Manager",
+        passwords: passwords_arr,
+        groups: groups_arr,
+        note_groups: note_groups_arr,
+        notes: notes_arr,
+        items: items_arr,
+        user_settings: settings_arr,
+        password_history: history_arr,
+    };
 
-    serde_json::to_vec_pretty(&backup).map_err(|e| e.to_string())
+    let json_bytes = serde_json::to_vec_pretty(&backup)
+        .map(SecretBytes::from)
+        .map_err(|e| e.to_string())?;
+    Ok((json_bytes, archive_files))
 }
 
 fn build_encrypted_backup_bytes(
     state: &State<'_, AppState>,
     archive_password: &str,
 ) -> Result<Vec<u8>, String> {
-    let json_bytes = build_backup_json_bytes(state)?;
-    create_encrypted_zip(&json_bytes, archive_password)
+    let (json_bytes, archive_files) = build_backup_document(state, true)?;
+    create_encrypted_zip(json_bytes.expose(), &archive_files, archive_password)
 }
 
-/// 创建 AES-256 加密的 ZIP 文件，内含 backup.json
-fn create_encrypted_zip(json_bytes: &[u8], password: &str) -> Result<Vec<u8>, String> {
+/// 创建 AES-256 加密的 ZIP 文件，内含 backup.json 与各附件条目
+fn create_encrypted_zip(
+    json_bytes: &[u8],
+    files: &[BackupArchiveFile],
+    password: &str,
+) -> Result<Vec<u8>, String> {
     let buf = Cursor::new(Vec::new());
     let mut zip = zip::ZipWriter::new(buf);
 
@@ -810,41 +1072,68 @@ fn create_encrypted_zip(json_bytes: &[u8], password: &str) -> Result<Vec<u8>, St
         .compression_method(zip::CompressionMethod::Deflated)
         .with_aes_encryption(zip::AesMode::Aes256, password);
 
-    zip.start_file("backup.json", options)
+    zip.start_file(BACKUP_JSON_ENTRY, options)
         .map_err(|e| format!("创建ZIP条目失败: {}", e))?;
     zip.write_all(json_bytes)
         .map_err(|e| format!("写入ZIP数据失败: {}", e))?;
+    for file in files {
+        zip.start_file(file.name.as_str(), options)
+            .map_err(|e| format!("创建ZIP条目失败: {}", e))?;
+        zip.write_all(file.data.expose())
+            .map_err(|e| format!("写入ZIP数据失败: {}", e))?;
+    }
 
     let result = zip.finish().map_err(|e| format!("完成ZIP文件失败: {}", e))?;
     Ok(result.into_inner())
 }
 
-fn read_encrypted_zip(zip_bytes: &[u8], password: &str) -> Result<Vec<u8>, String> {
+/// 读取加密ZIP，返回 backup.json 内容与按条目名索引的附件内容
+fn read_encrypted_zip(
+    zip_bytes: &[u8],
+    password: &str,
+) -> Result<(Vec<u8>, HashMap<String, SecretBytes>), String> {
     let reader = Cursor::new(zip_bytes);
     let mut archive =
         zip::ZipArchive::new(reader).map_err(|e| format!("读取ZIP文件失败: {}", e))?;
 
-    let mut file = archive
-        .by_name_decrypt("backup.json", password.as_bytes())
-        .map_err(|e| format!("ZIP解密失败（密码错误或文件损坏）: {}", e))?;
-
     let mut contents = Vec::new();
-    file.read_to_end(&mut contents)
+    archive
+        .by_name_decrypt(BACKUP_JSON_ENTRY, password.as_bytes())
+        .map_err(|e| format!("ZIP解密失败（密码错误或文件损坏）: {}", e))?
+        .read_to_end(&mut contents)
         .map_err(|e| format!("读取ZIP内容失败: {}", e))?;
 
-    Ok(contents)
+    let names: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.starts_with(ATTACHMENT_ENTRY_PREFIX))
+        .map(str::to_string)
+        .collect();
+    let mut files = HashMap::new();
+    for name in names {
+        let mut data = Vec::new();
+        archive
+            .by_name_decrypt(&name, password.as_bytes())
+            .map_err(|e| format!("ZIP解密失败（密码错误或文件损坏）: {}", e))?
+            .read_to_end(&mut data)
+            .map_err(|e| format!("读取ZIP内容失败: {}", e))?;
+        files.insert(name, SecretBytes::from(data));
+    }
+
+    Ok((contents, files))
 }
 
 fn do_import(
     conn: &rusqlite::Connection,
     backup: &Value,
     encryption: &EncryptionService,
+    attachment_files: &HashMap<String, SecretBytes>,
 ) -> Result<ImportStats, String> {
     let mut stats = ImportStats {
         total_imported: 0,
         total_skipped: 0,
         errors: Vec::new(),
     };
+    let cipher = FieldCipher::new(field_encryption::load_policy_conn(conn)?, encryption.clone());
 
     let mut group_id_map: HashMap<i64, i64> = HashMap::new();
     if let Some(groups) = backup.get("groups").and_then(|v| v.as_array()) {
@@ -903,26 +1192,40 @@ fn do_import(
             let mapped_group_id = old_group_id.and_then(|gid| group_id_map.get(&gid).copied());
             let encrypted_pwd = encrypt_field(encryption, plain_password);
 
-            let existing: Option<i64> = conn
-                .query_row(
-                    "SELECT id FROM passwords WHERE title = ?1 AND (username IS ?2)",
-                    rusqlite::params![title, username],
-                    |row| row.get(0),
-                )
-                .ok();
+            let existing = find_existing_password(conn, &cipher, title, username)?;
+            let username = cipher.encrypt_value(EncryptableField::PasswordUsername, username)?;
+            let url = cipher.encrypt_value(EncryptableField::PasswordUrl, url)?;
+            let notes = cipher.encrypt_value(EncryptableField::PasswordNotes, notes)?;
 
-            if let Some(eid) = existing {
+            let password_id = if let Some(eid) = existing {
                 conn.execute(
                     "UPDATE passwords SET password = ?1, url = ?2, notes = ?3, group_id = ?4, updated_at = datetime('now') WHERE id = ?5",
                     rusqlite::params![encrypted_pwd, url, notes, mapped_group_id, eid],
                 )
                 .map_err(|e| e.to_string())?;
+                eid
             } else {
                 conn.execute(
                     "INSERT INTO passwords (title, username, password, url, notes, group_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'), datetime('now'))",
                     rusqlite::params![title, username, encrypted_pwd, url, notes, mapped_group_id],
                 )
                 .map_err(|e| e.to_string())?;
+                conn.last_insert_rowid()
+            };
+            if let Some(uri) = pwd.get("totp").and_then(|v| v.as_str()) {
+                if let Err(e) = import_totp(conn, encryption, password_id, uri) {
+                    stats.errors.push(format!("导入 '{}' 的 TOTP 失败: {}", title, e));
+                }
+            }
+            if let Some(fields) = pwd.get("custom_fields").and_then(|v| v.as_array()) {
+                import_custom_fields(conn, encryption, password_id, fields, &mut stats)?;
+            }
+            if let Some(multi_accounts) = pwd.get("multi_accounts").filter(|v| v.is_object()) {
+                import_multi_accounts(conn, &cipher, encryption, password_id, multi_accounts)?;
+            }
+            if let Some(entries) = pwd.get("attachments").and_then(|v| v.as_array()) {
+                let owner = (Some(password_id), None);
+                import_attachments(conn, encryption, owner, entries, attachment_files, &mut stats)?;
             }
             stats.total_imported += 1;
         }
@@ -987,26 +1290,41 @@ fn do_import(
             let archived = note.get("archived").and_then(|v| v.as_i64()).unwrap_or(0);
             let encrypted_content = encrypt_field(encryption, plain_content);
 
-            let existing: Option<i64> = conn
-                .query_row(
-                    "SELECT id FROM secure_records WHERE title = ?1 AND (group_id IS ?2)",
-                    rusqlite::params![title, mapped_group_id],
-                    |row| row.get(0),
-                )
-                .ok();
+            let existing = find_existing_note(conn, &cipher, title, mapped_group_id)?;
+            let title = cipher
+                .encrypt_value(EncryptableField::NoteTitle, Some(title))?
+                .unwrap_or_default();
 
-            if let Some(eid) = existing {
+            let note_id = if let Some(eid) = existing {
                 conn.execute(
                     "UPDATE secure_records SET content = ?1, pinned = ?2, archived = ?3, updated_at = datetime('now') WHERE id = ?4",
                     rusqlite::params![encrypted_content, pinned, archived, eid],
                 )
                 .map_err(|e| e.to_string())?;
+                eid
             } else {
                 conn.execute(
                     "INSERT INTO secure_records (title, content, group_id, pinned, archived, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'), datetime('now'))",
                     rusqlite::params![title, encrypted_content, mapped_group_id, pinned, archived],
                 )
                 .map_err(|e| e.to_string())?;
+                conn.last_insert_rowid()
+            };
+            if let Some(entries) = note.get("attachments").and_then(|v| v.as_array()) {
+                let owner = (None, Some(note_id));
+                import_attachments(conn, encryption, owner, entries, attachment_files, &mut stats)?;
+            }
+            stats.total_imported += 1;
+        }
+    }
+
+    if let Some(items) = backup.get("items").and_then(|v| v.as_array()) {
+        for entry in items {
+            if let Err(e) = import_item(conn, &cipher, entry, &group_id_map) {
+                let title = entry.get("title").and_then(|v| v.as_str()).unwrap_or_default();
+                stats.errors.push(format!("导入条目 '{}' 失败: {}", title, e));
+                stats.total_skipped += 1;
+                continue;
             }
             stats.total_imported += 1;
         }
@@ -1020,6 +1338,28 @@ fn do_import(
             let category = setting.get("category").and_then(|v| v.as_str());
             let description = setting.get("description").and_then(|v| v.as_str());
 
+            // 字段加密策略与密文格式标记描述的是本保险库现有数据的存储形式，不能随备份覆盖
+            if key == field_encryption::FIELD_POLICY_SETTING || key == vault::CIPHER_FORMAT_SETTING {
+                stats.total_skipped += 1;
+                continue;
+            }
+
+            // 敏感配置须以本保险库的数据密钥加密；旧版备份中的密文仅在能用本保险库密钥解密时保留
+            let value = if vault::SENSITIVE_SETTING_KEYS.contains(&key) {
+                let sensitive = setting.get("sensitive").and_then(|v| v.as_bool()).unwrap_or(false);
+                if sensitive {
+                    encryption.encrypt(value)?
+                } else if encryption.decrypt(value).is_ok() {
+                    value.to_string()
+                } else {
+                    stats.errors.push(format!("设置 '{}' 无法用当前保险库密钥解密，已跳过", key));
+                    stats.total_skipped += 1;
+                    continue;
+                }
+            } else {
+                value.to_string()
+            };
+
             let result = conn.execute(
                 "INSERT INTO user_settings (key, value, type, category, description, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'), datetime('now'))
                  ON CONFLICT(key) DO UPDATE SET value=excluded.value, type=excluded.type, category=excluded.category, description=excluded.description, updated_at=datetime('now')",
@@ -1047,13 +1387,290 @@ fn do_import(
     Ok(stats)
 }
 
-fn decrypt_field(encryption: &EncryptionService, cipher: &Option<String>) -> Option<String> {
+/// 校验并写入备份中的结构化条目；同类型同标题的条目被覆盖
+fn import_item(
+    conn: &rusqlite::Connection,
+    cipher: &FieldCipher,
+    entry: &Value,
+    group_id_map: &HashMap<i64, i64>,
+) -> Result<(), String> {
+    let item_type = entry
+        .get("item_type")
+        .and_then(|v| v.as_str())
+        .and_then(ItemType::parse)
+        .ok_or("未知的条目类型")?;
+    let mut item = VaultItem {
+        id: None,
+        item_type,
+        title: entry.get("title").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+        group_id: entry
+            .get("group_id")
+            .and_then(|v| v.as_i64())
+            .and_then(|gid| group_id_map.get(&gid).copied()),
+        notes: entry.get("notes").and_then(|v| v.as_str()).map(str::to_string),
+        favorite: entry.get("favorite").and_then(|v| v.as_bool()),
+        fields: entry
+            .get("fields")
+            .and_then(|v| v.as_object())
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter_map(|(name, value)| Some((name.clone(), SecretString::from(value.as_str()?))))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        created_at: None,
+        updated_at: None,
+    };
+    items::prepare(&mut item)?;
+    cipher.encrypt_item(&mut item)?;
+
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM vault_items WHERE item_type = ?1 AND title = ?2 AND deleted_at IS NULL",
+            (item_type.as_str(), &item.title),
+            |row| row.get(0),
+        )
+        .ok();
+    match existing {
+        Some(id) => {
+            conn.execute(
+                "UPDATE vault_items SET group_id = ?1, notes = ?2, favorite = ?3, updated_at = datetime('now') WHERE id = ?4",
+                rusqlite::params![item.group_id, item.notes, item.favorite.unwrap_or(false), id],
+            )
+            .map_err(|e| e.to_string())?;
+            DatabaseService::replace_vault_item_fields(conn, id, &item).map_err(|e| e.to_string())?;
+        }
+        None => {
+            DatabaseService::write_vault_item(conn, &item).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// 解析备份中的 otpauth URI，加密密钥后写入
+fn import_totp(
+    conn: &rusqlite::Connection,
+    encryption: &EncryptionService,
+    password_id: i64,
+    uri: &str,
+) -> Result<(), String> {
+    let mut config = totp::parse_otpauth_uri(uri)?;
+    config.password_id = password_id;
+    config.secret = encryption.encrypt(config.secret.expose())?.into();
+    DatabaseService::write_password_totp(conn, &config).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 写入备份压缩包中的附件；`owner` 为 (password_id, note_id)，同名附件被替换
+fn import_attachments(
+    conn: &rusqlite::Connection,
+    encryption: &EncryptionService,
+    owner: (Option<i64>, Option<i64>),
+    entries: &[Value],
+    attachment_files: &HashMap<String, SecretBytes>,
+    stats: &mut ImportStats,
+) -> Result<(), String> {
+    let (password_id, note_id) = owner;
+    for entry in entries {
+        let file_name = entry.get("file_name").and_then(|v| v.as_str()).unwrap_or_default();
+        let content = entry
+            .get("entry")
+            .and_then(|v| v.as_str())
+            .and_then(|name| attachment_files.get(name));
+        let (Some(content), Ok(file_name)) = (content, attachments::normalize_file_name(file_name)) else {
+            stats.errors.push(format!("附件 '{}' 缺少内容或文件名无效，已跳过", file_name));
+            stats.total_skipped += 1;
+            continue;
+        };
+
+        conn.execute(
+            "DELETE FROM attachment_chunks WHERE attachment_id IN
+             (SELECT id FROM attachments WHERE password_id IS ?1 AND note_id IS ?2 AND file_name = ?3)",
+            rusqlite::params![password_id, note_id, file_name],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute(
+            "DELETE FROM attachments WHERE password_id IS ?1 AND note_id IS ?2 AND file_name = ?3",
+            rusqlite::params![password_id, note_id, file_name],
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut attachment = Attachment {
+            password_id,
+            note_id,
+            file_name,
+            mime_type: entry.get("mime_type").and_then(|v| v.as_str()).map(str::to_string),
+            ..Default::default()
+        };
+        attachments::store(conn, encryption, &mut attachment, content.expose())?;
+        stats.total_imported += 1;
+    }
+    Ok(())
+}
+
+/// 以备份中的附加网址与附加账号替换条目现有的数据
+fn import_multi_accounts(
+    conn: &rusqlite::Connection,
+    cipher: &FieldCipher,
+    encryption: &EncryptionService,
+    password_id: i64,
+    multi_accounts: &Value,
+) -> Result<(), String> {
+    let mut urls = Vec::new();
+    for entry in multi_accounts.get("urls").and_then(|v| v.as_array()).into_iter().flatten() {
+        let Some(url) = entry.get("url").and_then(|v| v.as_str()).filter(|u| !u.trim().is_empty()) else {
+            continue;
+        };
+        urls.push(PasswordUrl {
+            id: None,
+            password_id,
+            url: cipher
+                .encrypt_value(EncryptableField::PasswordUrl, Some(url.trim()))?
+                .unwrap_or_default(),
+            match_rule: entry
+                .get("match_rule")
+                .and_then(|v| v.as_str())
+                .and_then(UrlMatchRule::parse)
+                .unwrap_or_default(),
+            sort_order: None,
+            created_at: None,
+        });
+    }
+    DatabaseService::replace_password_urls_conn(conn, password_id, &urls).map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM password_accounts WHERE password_id = ?1", [password_id])
+        .map_err(|e| e.to_string())?;
+    let accounts = multi_accounts.get("accounts").and_then(|v| v.as_array());
+    for (index, entry) in accounts.into_iter().flatten().enumerate() {
+        let username = entry.get("username").and_then(|v| v.as_str());
+        let password = entry.get("password").and_then(|v| v.as_str());
+        let account = PasswordAccount {
+            id: None,
+            password_id,
+            label: entry.get("label").and_then(|v| v.as_str()).map(str::to_string),
+            username: cipher.encrypt_value(EncryptableField::PasswordUsername, username)?,
+            password: encrypt_field(encryption, password).map(SecretString::from),
+            sort_order: Some(index as i32),
+            created_at: None,
+            updated_at: None,
+        };
+        DatabaseService::write_password_account(conn, &account).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 以备份中的自定义字段替换条目现有的自定义字段；无效字段跳过并记录错误
+fn import_custom_fields(
+    conn: &rusqlite::Connection,
+    encryption: &EncryptionService,
+    password_id: i64,
+    fields: &[Value],
+    stats: &mut ImportStats,
+) -> Result<(), String> {
+    conn.execute("DELETE FROM password_custom_fields WHERE password_id = ?1", [password_id])
+        .map_err(|e| e.to_string())?;
+    for (index, value) in fields.iter().enumerate() {
+        let field_type = value
+            .get("field_type")
+            .and_then(|v| v.as_str())
+            .and_then(CustomFieldType::parse)
+            .unwrap_or_default();
+        let mut field = CustomField {
+            id: None,
+            password_id,
+            field_type,
+            label: value.get("label").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+            value: value.get("value").and_then(|v| v.as_str()).unwrap_or("").into(),
+            sort_order: value
+                .get("sort_order")
+                .and_then(|v| v.as_i64())
+                .map(|v| v as i32)
+                .or(Some(index as i32)),
+            created_at: None,
+            updated_at: None,
+        };
+        if let Err(e) = custom_fields::validate(&field) {
+            stats.errors.push(format!("导入自定义字段 '{}' 失败: {}", field.label, e));
+            stats.total_skipped += 1;
+            continue;
+        }
+        custom_fields::encrypt_field(encryption, &mut field)?;
+        DatabaseService::write_custom_field(conn, &field).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 按标题与用户名查找已存在的密码条目；用户名加密存储时解密后比较
+fn find_existing_password(
+    conn: &rusqlite::Connection,
+    cipher: &FieldCipher,
+    title: &str,
+    username: Option<&str>,
+) -> Result<Option<i64>, String> {
+    if !cipher.policy.password_username {
+        return Ok(conn
+            .query_row(
+                "SELECT id FROM passwords WHERE title = ?1 AND (username IS ?2) AND deleted_at IS NULL",
+                rusqlite::params![title, username],
+                |row| row.get(0),
+            )
+            .ok());
+    }
+    let mut stmt = conn
+        .prepare("SELECT id, username FROM passwords WHERE title = ?1 AND deleted_at IS NULL ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([title], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?)))
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (id, stored) = row.map_err(|e| e.to_string())?;
+        let stored = cipher.decrypt_value(EncryptableField::PasswordUsername, stored);
+        if stored.as_deref() == username {
+            return Ok(Some(id));
+        }
+    }
+    Ok(None)
+}
+
+/// 按标题与分组查找已存在的笔记；标题加密存储时解密后比较
+fn find_existing_note(
+    conn: &rusqlite::Connection,
+    cipher: &FieldCipher,
+    title: &str,
+    group_id: Option<i64>,
+) -> Result<Option<i64>, String> {
+    if !cipher.policy.note_title {
+        return Ok(conn
+            .query_row(
+                "SELECT id FROM secure_records WHERE title = ?1 AND (group_id IS ?2) AND deleted_at IS NULL",
+                rusqlite::params![title, group_id],
+                |row| row.get(0),
+            )
+            .ok());
+    }
+    let mut stmt = conn
+        .prepare("SELECT id, title FROM secure_records WHERE group_id IS ?1 AND deleted_at IS NULL ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([group_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (id, stored) = row.map_err(|e| e.to_string())?;
+        if cipher.decrypt_value(EncryptableField::NoteTitle, Some(stored)).as_deref() == Some(title) {
+            return Ok(Some(id));
+        }
+    }
+    Ok(None)
+}
+
+fn decrypt_field(encryption: &EncryptionService, cipher: &Option<String>) -> Option<SecretString> {
     match cipher {
         Some(text) if !text.is_empty() => match encryption.decrypt(text) {
             Ok(plain) => Some(plain),
-            Err(_) => Some(text.clone()),
+            Err(_) => Some(SecretString::from(text.as_str())),
         },
-        other => other.clone(),
+        other => other.clone().map(SecretString::from),
     }
 }
 
@@ -1138,11 +1755,14 @@ fn get_plain_setting(
         .map(|opt| opt.map(|s| s.value))
 }
 
-fn get_sensitive_setting(state: &State<'_, AppState>, key: &str) -> Result<Option<String>, String> {
+fn get_sensitive_setting(
+    state: &State<'_, AppState>,
+    key: &str,
+) -> Result<Option<SecretString>, String> {
     let value = get_plain_setting(&state.db, key)?;
     match value {
         Some(cipher) if !cipher.trim().is_empty() => state
-            .encryption
+            .encryption_service()?
             .decrypt(&cipher)
             .map(Some)
             .map_err(|e| format!("解密配置失败({}): {}", key, e)),
@@ -1181,7 +1801,8 @@ fn save_sensitive_setting(
     category: &str,
     description: &str,
 ) -> Result<(), String> {
-    let cipher = state.encryption.encrypt(value)?;
+    let encryption = state.encryption_service()?;
+    let cipher = encryption.encrypt(value)?;
     save_plain_setting(state, key, cipher, type_, category, description)
 }
 
@@ -1304,10 +1925,10 @@ fn validate_cloud_config(config: &CloudConfig) -> Result<(), String> {
     if config.region.trim().is_empty() {
         return Err("请先配置 Region".to_string());
     }
-    if config.secret_id.trim().is_empty() {
+    if config.secret_id.expose().trim().is_empty() {
         return Err("请先配置 SecretId".to_string());
     }
-    if config.secret_key.trim().is_empty() {
+    if config.secret_key.expose().trim().is_empty() {
         return Err("请先配置 SecretKey".to_string());
     }
     Ok(())
@@ -1340,18 +1961,18 @@ async fn resolve_test_cloud_config(
         .unwrap_or_else(|| base.cloud_path_prefix.clone());
     let secret_id = input
         .secret_id
-        .as_deref()
-        .map(str::trim)
+        .as_ref()
+        .map(|s| s.expose().trim())
         .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
+        .map(SecretString::from)
         .or_else(|| base.cloud_secret_id.clone())
         .ok_or("请先配置 SecretId 或在输入框中填写")?;
     let secret_key = input
         .secret_key
-        .as_deref()
-        .map(str::trim)
+        .as_ref()
+        .map(|s| s.expose().trim())
         .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
+        .map(SecretString::from)
         .or_else(|| base.cloud_secret_key.clone())
         .ok_or("请先配置 SecretKey 或在输入框中填写")?;
 
@@ -1371,17 +1992,17 @@ async fn resolve_test_cloud_config(
 fn resolve_test_archive_password(
     state: &State<'_, AppState>,
     input: &TestBackupCloudInput,
-) -> Result<String, String> {
+) -> Result<SecretString, String> {
     let base = load_backup_config(state)?;
     let password = input
         .export_default_password
-        .as_deref()
-        .map(str::trim)
+        .as_ref()
+        .map(|s| s.expose().trim())
         .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
+        .map(SecretString::from)
         .or(base.auto_export_password)
         .ok_or("请先设置加密ZIP默认密码（至少4位）")?;
-    if password.trim().len() < 4 {
+    if password.expose().trim().len() < 4 {
         return Err("加密ZIP默认密码至少需要 4 位".to_string());
     }
     Ok(password)
@@ -1545,11 +2166,11 @@ async fn signed_request(
         credential_scope,
         sha256_hex(canonical_request.as_bytes())
     );
-    let signing_key = build_signing_key(&config.secret_key, short_date, &config.region, AWS_SERVICE_NAME);
+    let signing_key = build_signing_key(config.secret_key.expose(), short_date, &config.region, AWS_SERVICE_NAME);
     let signature = hex::encode(hmac_sign(&signing_key, &string_to_sign));
     let authorization = format!(
         "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
-        config.secret_id, credential_scope, signed_headers, signature
+        config.secret_id.expose(), credential_scope, signed_headers, signature
     );
     headers.insert(
         HeaderName::from_static("authorization"),
@@ -1807,14 +2428,14 @@ fn execute_local_backup(
             .auto_export_password
             .as_ref()
             .ok_or("请先设置加密ZIP默认密码，再执行本地备份")?;
-        if password.trim().len() < 4 {
+        if password.expose().trim().len() < 4 {
             return Err("加密ZIP默认密码至少需要 4 位".to_string());
         }
-        build_encrypted_backup_bytes(state, password)?
+        SecretBytes::from(build_encrypted_backup_bytes(state, password.expose())?)
     };
 
     let full_path = path.join(file_name);
-    std::fs::write(&full_path, bytes).map_err(|e| format!("写入本地备份失败: {}", e))?;
+    std::fs::write(&full_path, bytes.expose()).map_err(|e| format!("写入本地备份失败: {}", e))?;
     cleanup_local_backups(&path, config.retention_count)?;
 
     Ok(BackupExecutionOutcome {
@@ -1834,14 +2455,14 @@ async fn execute_cloud_backup(
         .auto_export_password
         .clone()
         .ok_or_else(|| CloudError::new("config_error", "请先设置加密ZIP默认密码，再执行云备份"))?;
-    if password.trim().len() < 4 {
+    if password.expose().trim().len() < 4 {
         return Err(CloudError::new(
             "config_error",
             "加密ZIP默认密码至少需要 4 位",
         ));
     }
 
-    let bytes = build_encrypted_backup_bytes(state, &password)
+    let bytes = build_encrypted_backup_bytes(state, password.expose())
         .map_err(|e| CloudError::new("backup_generation_failed", e))?;
     let client = build_http_client().map_err(|e| CloudError::new("network_failure", e))?;
     upload_backup_bytes(&client, &cloud, file_name, &bytes, config.retention_count).await?;
@@ -1884,7 +2505,7 @@ pub fn start_backup_scheduler(app: AppHandle) {
         loop {
             ticker.tick().await;
             if let Err(err) = maybe_run_scheduled_backup(&app).await {
-                if should_notify_backup_failure(&app, &err.category, &err.message) {
+                if should_notify_backup_failure(&app, err.category, &err.message) {
                     app.emit(
                         "auto-export-done",
                         json!({
@@ -1902,6 +2523,10 @@ pub fn start_backup_scheduler(app: AppHandle) {
 
 async fn maybe_run_scheduled_backup(app: &AppHandle) -> Result<(), CloudError> {
     let state = app.state::<AppState>();
+    // 保险库锁定时数据密钥不可用，等待解锁后再执行
+    if !state.is_vault_unlocked() {
+        return Ok(());
+    }
     let config = load_backup_config(&state).map_err(|e| CloudError::new("config_error", e))?;
     if !config.auto_export_enabled {
         return Ok(());
@@ -2088,8 +2713,8 @@ mod tests {
             bucket: "myloair-1318175726".to_string(),
             region: "ap-shanghai".to_string(),
             path_prefix: "backups/qjs/".to_string(),
-            secret_id: "ak".to_string(),
-            secret_key: "sk".to_string(),
+            secret_id: "ak".into(),
+            secret_key: "sk".into(),
         };
         let key = "backups/qjs/myloair-backup-2026-03-24-10-30-00.zip";
         assert_eq!(
@@ -2109,8 +2734,8 @@ mod tests {
             bucket: "myloair-1318175726".to_string(),
             region: "ap-shanghai".to_string(),
             path_prefix: "backups/qjs/".to_string(),
-            secret_id: "ak".to_string(),
-            secret_key: "sk".to_string(),
+            secret_id: "ak".into(),
+            secret_key: "sk".into(),
         };
         let key = "backups/qjs/myloair-backup-2026-03-24-10-30-00.zip";
         assert_eq!(
@@ -2224,7 +2849,7 @@ mod tests {
             auto_export_frequency: frequency.to_string(),
             auto_export_directory: String::new(),
             auto_export_format: "encrypted_zip".to_string(),
-            auto_export_password: Some("1234".to_string()),
+            auto_export_password: Some("1234".into()),
             auto_export_time_of_day: "00:00".to_string(),
             auto_export_day_of_week: 1,
             auto_export_day_of_month: 1,
@@ -2246,4 +2871,180 @@ mod tests {
             .single()
             .unwrap()
     }
+
+    #[tokio::test]
+    async fn test_backup_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+
+        let dir = tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(export_data(app.state(), json!({ "format": "json" })).await);
+        assert_vault_locked(import_data(app.state(), b"{}".to_vec(), json!({})).await);
+        assert_vault_locked(get_backup_config(app.state()).await);
+    }
+
+    #[tokio::test]
+    async fn test_attachments_survive_encrypted_zip_roundtrip() {
+        use crate::test_support::app_with_state;
+
+        let source_dir = tempdir().unwrap();
+        let source = app_with_state(&source_dir, true);
+        let state = source.state::<AppState>();
+        let note: crate::models::SecureRecord =
+            serde_json::from_value(json!({ "title": "Recovery codes" })).unwrap();
+        let note_id = state.db.add_note(&note).unwrap();
+        let mut attachment = Attachment {
+            note_id: Some(note_id),
+            file_name: "codes.pdf".to_string(),
+            mime_type: Some("application/pdf".to_string()),
+            ..Default::default()
+        };
+        let conn = state.db.get_connection().unwrap();
+        attachments::store(&conn, &state.encryption_service().unwrap(), &mut attachment, b"%PDF-1.7 codes").unwrap();
+
+        let (json_bytes, _) = build_backup_document(&state, false).unwrap();
+        assert!(!String::from_utf8_lossy(json_bytes.expose()).contains("codes.pdf"));
+
+        let zip_bytes = build_encrypted_backup_bytes(&state, "archive-pass").unwrap();
+        let (json_bytes, files) = read_encrypted_zip(&zip_bytes, "archive-pass").unwrap();
+        assert!(!String::from_utf8_lossy(&json_bytes).contains("%PDF"));
+        assert_eq!(files.len(), 1);
+
+        let target_dir = tempdir().unwrap();
+        let target = app_with_state(&target_dir, true);
+        import_data(target.state(), zip_bytes, json!({ "archivePassword": "archive-pass" }))
+            .await
+            .unwrap();
+        let target_state = target.state::<AppState>();
+        let imported = target_state.db.get_all_attachments().unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].file_name, "codes.pdf");
+        assert!(imported[0].note_id.is_some());
+        let content = attachments::read(&target_state.db, &target_state.encryption_service().unwrap(), &imported[0])
+            .unwrap();
+        assert_eq!(content.expose(), b"%PDF-1.7 codes");
+    }
+
+    #[tokio::test]
+    async fn test_sensitive_settings_are_reencrypted_on_import() {
+        use crate::test_support::app_with_state;
+
+        let source_dir = tempdir().unwrap();
+        let source = app_with_state(&source_dir, true);
+        let state = source.state::<AppState>();
+        save_sensitive_setting(&state, "backup.cloud.secret_key", "sk-source", "string", "backup", "SecretKey")
+            .unwrap();
+        let backup = build_backup_json_bytes(&state).unwrap();
+        assert!(String::from_utf8_lossy(backup.expose()).contains("sk-source"));
+
+        // 目标保险库使用另一把数据密钥
+        let target_dir = tempdir().unwrap();
+        let target = app_with_state(&target_dir, true);
+        let target_state = target.state::<AppState>();
+        assert_ne!(
+            target_state.encryption_service().unwrap().key_bytes(),
+            state.encryption_service().unwrap().key_bytes()
+        );
+        import_data(target.state(), backup.expose().to_vec(), json!({})).await.unwrap();
+        let secret = get_sensitive_setting(&target_state, "backup.cloud.secret_key").unwrap().unwrap();
+        assert_eq!(secret.expose(), "sk-source");
+
+        // 旧版备份中以源保险库密钥加密的密文无法在目标保险库解密，不导入
+        let foreign = state.encryption_service().unwrap().encrypt("sk-legacy").unwrap();
+        let legacy = json!({ "user_settings": [{ "key": "backup.auto_export_password", "value": foreign }] });
+        let result = import_data(target.state(), serde_json::to_vec(&legacy).unwrap(), json!({})).await.unwrap();
+        assert_eq!(result["data"]["skipped"], 1);
+        assert!(get_plain_setting(&target_state.db, "backup.auto_export_password").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_keeps_cipher_format_marker() {
+        use crate::test_support::app_with_state;
+
+        let dir = tempdir().unwrap();
+        let app = app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let backup = json!({ "user_settings": [{ "key": vault::CIPHER_FORMAT_SETTING, "value": "2" }] });
+        let result = import_data(app.state(), serde_json::to_vec(&backup).unwrap(), json!({})).await.unwrap();
+        assert_eq!(result["data"]["skipped"], 1);
+        assert!(get_plain_setting(&state.db, vault::CIPHER_FORMAT_SETTING).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_password_extras_survive_backup_roundtrip() {
+        use crate::test_support::app_with_state;
+
+        let source_dir = tempdir().unwrap();
+        let source = app_with_state(&source_dir, true);
+        let state = source.state::<AppState>();
+        let password: crate::models::Password =
+            serde_json::from_value(json!({ "title": "GitHub", "username": "alice" })).unwrap();
+        let id = state.db.add_password(&password).unwrap();
+        import_totp(
+            &state.db.get_connection().unwrap(),
+            &state.encryption_service().unwrap(),
+            id,
+            "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&digits=8",
+        )
+        .unwrap();
+        let mut pin: CustomField = serde_json::from_value(json!({
+            "passwordId": id, "fieldType": "hidden", "label": "PIN", "value": "4321"
+        }))
+        .unwrap();
+        custom_fields::encrypt_field(&state.encryption_service().unwrap(), &mut pin).unwrap();
+        state.db.add_custom_field(&pin).unwrap();
+        let urls: Vec<PasswordUrl> =
+            serde_json::from_value(json!([{ "url": "https://gist.github.com", "matchRule": "exact" }])).unwrap();
+        state.db.replace_password_urls(id, &urls).unwrap();
+        let account: PasswordAccount = serde_json::from_value(json!({
+            "passwordId": id, "label": "Bot", "username": "ci-bot",
+            "password": state.encryption_service().unwrap().encrypt("bot-token").unwrap()
+        }))
+        .unwrap();
+        state.db.add_password_account(&account).unwrap();
+        let mut wifi: VaultItem = serde_json::from_value(json!({
+            "itemType": "wifi", "title": "Office", "fields": { "ssid": "corp", "password": "wifi-pass-1" }
+        }))
+        .unwrap();
+        items::prepare(&mut wifi).unwrap();
+        state.field_cipher().unwrap().encrypt_item(&mut wifi).unwrap();
+        state.db.add_vault_item(&wifi).unwrap();
+        let backup = build_backup_json_bytes(&state).unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let target = app_with_state(&target_dir, true);
+        import_data(target.state(), backup.expose().to_vec(), json!({})).await.unwrap();
+        let target_state = target.state::<AppState>();
+        let imported = target_state.db.get_all_password_totp().unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].digits, 8);
+        let secret = target_state
+            .encryption_service()
+            .unwrap()
+            .decrypt(imported[0].secret.expose())
+            .unwrap();
+        assert_eq!(secret.expose(), "JBSWY3DPEHPK3PXP");
+
+        let fields = target_state.db.get_all_custom_fields().unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].field_type, CustomFieldType::Hidden);
+        assert_ne!(fields[0].value.expose(), "4321");
+
+        let urls = target_state.db.get_all_password_urls().unwrap();
+        assert_eq!(urls.len(), 1);
+        assert_eq!((urls[0].url.as_str(), urls[0].match_rule), ("https://gist.github.com", UrlMatchRule::Exact));
+        let accounts = target_state.db.get_all_password_accounts().unwrap();
+        assert_eq!(accounts[0].username.as_deref(), Some("ci-bot"));
+        let password = accounts[0].password.as_ref().unwrap().expose().to_string();
+        assert_eq!(
+            target_state.encryption_service().unwrap().decrypt(&password).unwrap().expose(),
+            "bot-token"
+        );
+
+        let mut imported_items = target_state.db.get_vault_items(Some(ItemType::Wifi)).unwrap();
+        assert_eq!(imported_items.len(), 1);
+        assert_ne!(imported_items[0].fields["password"].expose(), "wifi-pass-1");
+        target_state.field_cipher().unwrap().decrypt_item(&mut imported_items[0]);
+        assert_eq!(imported_items[0].fields["password"].expose(), "wifi-pass-1");
+    }
 }
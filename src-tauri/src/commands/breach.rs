@@ -0,0 +1,140 @@
+//! 离线泄露密码检查 Commands
+
+use crate::models::{BreachDatasetInfo, BreachImportReport, BreachedPassword, SecretString};
+use crate::services::breach;
+use crate::AppState;
+use serde_json::{json, Value};
+use std::path::Path;
+use tauri::State;
+
+/// 导入本地泄露哈希数据集（完整哈希文件或 k-匿名范围文件目录）
+#[tauri::command]
+pub async fn import_breach_dataset(state: State<'_, AppState>, path: String) -> Result<BreachImportReport, String> {
+    state.ensure_unlocked()?;
+    log::info!("import_breach_dataset called: path={}", path);
+    let report = breach::import(&state.db, Path::new(&path))?;
+    log::info!(
+        "Breach dataset imported: {} hashes, {} lines skipped",
+        report.imported,
+        report.skipped
+    );
+    Ok(report)
+}
+
+/// 获取已导入数据集的概况
+#[tauri::command]
+pub async fn get_breach_dataset_info(state: State<'_, AppState>) -> Result<BreachDatasetInfo, String> {
+    state.db.get_breach_dataset_info()
+}
+
+/// 清空已导入的数据集
+#[tauri::command]
+pub async fn clear_breach_dataset(state: State<'_, AppState>) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    state.db.clear_breach_hashes()?;
+    Ok(json!({ "success": true }))
+}
+
+/// 检查全部密码条目，返回密码出现在泄露数据中的条目，以及无法解密而未能检查的条目
+#[tauri::command]
+pub async fn check_breached_passwords(state: State<'_, AppState>) -> Result<Vec<BreachedPassword>, String> {
+    state.ensure_unlocked()?;
+    log::info!("check_breached_passwords called");
+    let encryption = state.encryption_service()?;
+    let mut entries = Vec::new();
+    let mut plaintexts = Vec::new();
+    let mut undecryptable = Vec::new();
+    for p in state.db.get_passwords(None)? {
+        let (Some(id), Some(ciphertext)) = (p.id, p.password.filter(|c| !c.is_empty())) else {
+            continue;
+        };
+        match encryption.decrypt(ciphertext.expose()) {
+            Ok(plain) => {
+                entries.push((id, p.title, p.group_id));
+                plaintexts.push(plain);
+            }
+            Err(e) => {
+                log::warn!("Cannot decrypt password {} in breach check: {}", id, e);
+                undecryptable.push(BreachedPassword {
+                    id,
+                    title: p.title,
+                    group_id: p.group_id,
+                    count: 0,
+                    undecryptable: true,
+                });
+            }
+        }
+    }
+    let passwords: Vec<&str> = plaintexts.iter().map(SecretString::expose).collect();
+    let counts = breach::check(&state.db, &passwords)?;
+    let mut breached: Vec<BreachedPassword> = entries
+        .into_iter()
+        .zip(counts)
+        .filter_map(|((id, title, group_id), count)| {
+            count.map(|count| BreachedPassword { id, title, group_id, count, undecryptable: false })
+        })
+        .collect();
+    breached.extend(undecryptable);
+    Ok(breached)
+}
+
+/// 检查单个密码（如新生成的密码）是否出现在泄露数据中
+#[tauri::command]
+pub async fn check_password_breached(state: State<'_, AppState>, password: SecretString) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    let count = breach::check(&state.db, &[password.expose()])?
+        .into_iter()
+        .next()
+        .flatten();
+    Ok(json!({ "success": true, "breached": count.is_some(), "count": count.unwrap_or(0) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_breach_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(import_breach_dataset(app.state(), "hashes.txt".to_string()).await);
+        assert_vault_locked(clear_breach_dataset(app.state()).await);
+        assert_vault_locked(check_breached_passwords(app.state()).await);
+        assert_vault_locked(check_password_breached(app.state(), "password".into()).await);
+    }
+
+    #[tokio::test]
+    async fn test_check_breached_passwords() {
+        use crate::commands::passwords::add_password;
+        use crate::models::Password;
+        use crate::services::encryption::EncryptionService;
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let dataset = dir.path().join("hashes.txt");
+        std::fs::write(&dataset, include_str!("../../tests/fixtures/breach/pwned-sha1-sample.txt")).unwrap();
+        import_breach_dataset(app.state(), dataset.to_string_lossy().to_string()).await.unwrap();
+
+        for (title, secret) in [("Weak", "password"), ("Strong", "k9$Vq!x2#Lm7@pR")] {
+            let password: Password =
+                serde_json::from_value(json!({ "title": title, "password": secret })).unwrap();
+            add_password(app.state(), password).await.unwrap();
+        }
+        let foreign = EncryptionService::new("other_key").encrypt("password").unwrap();
+        let state = app.state::<AppState>();
+        let conn = state.db.get_connection().unwrap();
+        conn.execute("INSERT INTO passwords (title, password) VALUES ('Broken', ?1)", [foreign]).unwrap();
+        drop(conn);
+        let breached = check_breached_passwords(app.state()).await.unwrap();
+        assert_eq!(breached.len(), 2);
+        assert_eq!((breached[0].title.as_str(), breached[0].count), ("Weak", 9_545_824));
+        assert_eq!((breached[1].title.as_str(), breached[1].undecryptable), ("Broken", true));
+
+        let result = check_password_breached(app.state(), "123456".into()).await.unwrap();
+        assert_eq!(result["breached"], true);
+    }
+}
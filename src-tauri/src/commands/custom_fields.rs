@@ -0,0 +1,124 @@
+//! 自定义字段 Commands
+
+use crate::models::CustomField;
+use crate::services::custom_fields;
+use crate::AppState;
+use serde_json::{json, Value};
+use tauri::State;
+
+/// 获取密码条目的自定义字段（机密字段已解密）
+#[tauri::command]
+pub async fn get_custom_fields(
+    state: State<'_, AppState>,
+    password_id: i64,
+) -> Result<Vec<CustomField>, String> {
+    state.ensure_unlocked()?;
+    let encryption = state.encryption_service()?;
+    let mut fields = state.db.get_custom_fields(password_id)?;
+    for field in &mut fields {
+        custom_fields::decrypt_field(&encryption, field);
+    }
+    Ok(fields)
+}
+
+/// 添加自定义字段
+#[tauri::command]
+pub async fn add_custom_field(
+    state: State<'_, AppState>,
+    mut field: CustomField,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("add_custom_field called: password_id={}", field.password_id);
+    if state.db.get_password(field.password_id)?.is_none() {
+        return Err("Password not found".to_string());
+    }
+    custom_fields::validate(&field)?;
+    let encryption = state.encryption_service()?;
+    custom_fields::encrypt_field(&encryption, &mut field)?;
+    let id = state.db.add_custom_field(&field)?;
+    Ok(json!({ "success": true, "id": id }))
+}
+
+/// 更新自定义字段（不可移动到其他条目）
+#[tauri::command]
+pub async fn update_custom_field(
+    state: State<'_, AppState>,
+    id: i64,
+    mut field: CustomField,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("update_custom_field called: id={}", id);
+    let existing = state
+        .db
+        .get_custom_field(id)?
+        .ok_or("Custom field not found")?;
+    field.id = Some(id);
+    field.password_id = existing.password_id;
+    custom_fields::validate(&field)?;
+    let encryption = state.encryption_service()?;
+    custom_fields::encrypt_field(&encryption, &mut field)?;
+    state.db.update_custom_field(&field)?;
+    Ok(json!({ "success": true }))
+}
+
+/// 删除自定义字段
+#[tauri::command]
+pub async fn delete_custom_field(
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    state.db.delete_custom_field(id)?;
+    Ok(json!({ "success": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_field(password_id: i64) -> CustomField {
+        serde_json::from_value(json!({
+            "passwordId": password_id,
+            "fieldType": "hidden",
+            "label": "PIN",
+            "value": "4321"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_custom_field_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(get_custom_fields(app.state(), 1).await);
+        assert_vault_locked(add_custom_field(app.state(), sample_field(1)).await);
+        assert_vault_locked(update_custom_field(app.state(), 1, sample_field(1)).await);
+        assert_vault_locked(delete_custom_field(app.state(), 1).await);
+    }
+
+    #[tokio::test]
+    async fn test_hidden_field_is_encrypted_at_rest() {
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let password: crate::models::Password =
+            serde_json::from_value(json!({ "title": "Bank" })).unwrap();
+        let password_id = state.db.add_password(&password).unwrap();
+
+        add_custom_field(app.state(), sample_field(password_id)).await.unwrap();
+        let stored = state.db.get_custom_fields(password_id).unwrap();
+        assert_ne!(stored[0].value.expose(), "4321");
+
+        let fields = get_custom_fields(app.state(), password_id).await.unwrap();
+        assert_eq!(fields[0].value.expose(), "4321");
+
+        let mut invalid = sample_field(password_id);
+        invalid.field_type = crate::models::CustomFieldType::Email;
+        assert!(add_custom_field(app.state(), invalid).await.is_err());
+    }
+}
@@ -1,6 +1,7 @@
 //! 分组管理 Commands
 
 use crate::models::{Group, GroupWithChildren};
+use crate::services::fuzzy;
 use crate::AppState;
 use serde::Deserialize;
 use serde_json::Value;
@@ -15,11 +16,22 @@ pub struct ReorderGroupInput {
     pub insert_index: usize,
 }
 
-/// 获取所有分组
+/// 获取所有分组；提供关键字时只返回名称匹配（含模糊与拼音匹配）的分组，并按相关度排序
 #[tauri::command]
-pub async fn get_groups(state: State<'_, AppState>) -> Result<Vec<Group>, String> {
-    log::info!("get_groups called");
-    state.db.get_groups().map_err(|e| e.to_string())
+pub async fn get_groups(state: State<'_, AppState>, keyword: Option<String>) -> Result<Vec<Group>, String> {
+    state.ensure_unlocked()?;
+    log::info!("get_groups called with keyword: {:?}", keyword);
+    let groups = state.db.get_groups().map_err(|e| e.to_string())?;
+    let matcher = match keyword.as_deref().map(fuzzy::Matcher::new) {
+        Some(matcher) if !matcher.is_empty() => matcher,
+        _ => return Ok(groups),
+    };
+    let mut scored: Vec<(Group, f64)> = groups
+        .into_iter()
+        .filter_map(|g| matcher.score(&g.name).map(|score| (g, score)))
+        .collect();
+    fuzzy::rank(&mut scored, |g| &g.name);
+    Ok(scored.into_iter().map(|(g, _)| g).collect())
 }
 
 /// 获取分组树
@@ -28,6 +40,7 @@ pub async fn get_group_tree(
     state: State<'_, AppState>,
     parent_id: Option<i64>,
 ) -> Result<Vec<GroupWithChildren>, String> {
+    state.ensure_unlocked()?;
     log::info!("get_group_tree called with parent_id: {:?}", parent_id);
 
     // 1. Get all groups
@@ -44,7 +57,7 @@ fn build_group_tree(groups: Vec<Group>, root_parent_id: Option<i64>) -> Vec<Grou
     for g in groups {
         children_map
             .entry(g.parent_id)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(g);
     }
 
@@ -78,6 +91,7 @@ fn build_tree_recursive(
 /// 添加分组
 #[tauri::command]
 pub async fn add_group(state: State<'_, AppState>, group: Group) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("add_group called: {:?}", group.name);
     let id = state.db.add_group(&group).map_err(|e| e.to_string())?;
     Ok(serde_json::json!({
@@ -93,6 +107,7 @@ pub async fn update_group(
     id: i64,
     group: serde_json::Value,  // 先接收为 JSON Value 查看原始数据
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("[update_group] ========== 开始 ==========");
     log::info!("[update_group] 接收到的 id={}", id);
     log::info!("[update_group] 接收到的原始 JSON: {}", group);
@@ -139,8 +154,11 @@ pub async fn update_group(
 /// 删除分组
 #[tauri::command]
 pub async fn delete_group(state: State<'_, AppState>, id: i64) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("delete_group called: id={}", id);
     state.db.delete_group(id).map_err(|e| e.to_string())?;
+    // 分组内的条目已移入回收站
+    super::search::invalidate_search_index(&state);
     Ok(serde_json::json!({
         "success": true
     }))
@@ -152,6 +170,7 @@ pub async fn reorder_group(
     state: State<'_, AppState>,
     input: ReorderGroupInput,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     state
         .db
         .reorder_group(input.drag_id, input.new_parent_id, input.insert_index)
@@ -160,3 +179,40 @@ pub async fn reorder_group(
         "success": true
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_group_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        let group: Group = serde_json::from_value(serde_json::json!({ "name": "Work" })).unwrap();
+        assert_vault_locked(get_groups(app.state(), None).await);
+        assert_vault_locked(get_group_tree(app.state(), None).await);
+        assert_vault_locked(add_group(app.state(), group).await);
+        assert_vault_locked(delete_group(app.state(), 1).await);
+    }
+
+    #[tokio::test]
+    async fn test_get_groups_filters_by_keyword() {
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        for name in ["重庆办公室", "工作", "Infrastructure"] {
+            let group: Group = serde_json::from_value(serde_json::json!({ "name": name })).unwrap();
+            add_group(app.state(), group).await.unwrap();
+        }
+
+        let names = |groups: Vec<Group>| groups.into_iter().map(|g| g.name).collect::<Vec<_>>();
+        assert_eq!(names(get_groups(app.state(), Some("cq".to_string())).await.unwrap()), vec!["重庆办公室"]);
+        assert_eq!(names(get_groups(app.state(), Some("gongzuo".to_string())).await.unwrap()), vec!["工作"]);
+        assert_eq!(names(get_groups(app.state(), Some("infra".to_string())).await.unwrap()), vec!["Infrastructure"]);
+        assert_eq!(get_groups(app.state(), Some(" ".to_string())).await.unwrap().len(), 3);
+    }
+}
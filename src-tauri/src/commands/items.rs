@@ -0,0 +1,168 @@
+//! 条目类型 Commands
+
+use crate::models::{EncryptableField, ItemFieldSpec, ItemSummary, ItemType, VaultItem};
+use crate::services::items;
+use crate::AppState;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::State;
+
+/// 条目类型及其字段定义
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemTypeSchema {
+    pub item_type: ItemType,
+    pub fields: &'static [ItemFieldSpec],
+}
+
+/// 获取结构化条目类型的字段定义
+#[tauri::command]
+pub async fn get_item_type_schemas() -> Result<Vec<ItemTypeSchema>, String> {
+    Ok(ItemType::ALL
+        .into_iter()
+        .filter(|item_type| item_type.is_structured())
+        .map(|item_type| ItemTypeSchema { item_type, fields: item_type.fields() })
+        .collect())
+}
+
+/// 获取全部类型条目的摘要（登录、安全笔记与结构化条目）
+#[tauri::command]
+pub async fn list_all_items(state: State<'_, AppState>) -> Result<Vec<ItemSummary>, String> {
+    state.ensure_unlocked()?;
+    let cipher = state.field_cipher()?;
+    let mut summaries = state.db.get_item_summaries()?;
+    for summary in &mut summaries {
+        if summary.item_type == ItemType::SecureNote {
+            let title = std::mem::take(&mut summary.title);
+            summary.title = cipher
+                .decrypt_value(EncryptableField::NoteTitle, Some(title))
+                .unwrap_or_default();
+        }
+    }
+    Ok(summaries)
+}
+
+/// 获取结构化条目（可选按类型筛选，机密字段已解密）
+#[tauri::command]
+pub async fn get_items(
+    state: State<'_, AppState>,
+    item_type: Option<ItemType>,
+) -> Result<Vec<VaultItem>, String> {
+    state.ensure_unlocked()?;
+    if item_type.is_some_and(|t| !t.is_structured()) {
+        return Err("登录与安全笔记请使用对应的接口".to_string());
+    }
+    let cipher = state.field_cipher()?;
+    let mut items = state.db.get_vault_items(item_type)?;
+    items.iter_mut().for_each(|item| cipher.decrypt_item(item));
+    Ok(items)
+}
+
+/// 获取单个结构化条目（机密字段已解密）
+#[tauri::command]
+pub async fn get_item(state: State<'_, AppState>, id: i64) -> Result<Option<VaultItem>, String> {
+    state.ensure_unlocked()?;
+    let cipher = state.field_cipher()?;
+    let mut item = state.db.get_vault_item(id)?;
+    if let Some(item) = item.as_mut() {
+        cipher.decrypt_item(item);
+    }
+    Ok(item)
+}
+
+/// 添加结构化条目
+#[tauri::command]
+pub async fn add_item(state: State<'_, AppState>, mut item: VaultItem) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("add_item called: type={}", item.item_type.as_str());
+    items::prepare(&mut item)?;
+    let cipher = state.field_cipher()?;
+    cipher.encrypt_item(&mut item)?;
+    let id = state.db.add_vault_item(&item)?;
+    Ok(json!({ "success": true, "id": id }))
+}
+
+/// 更新结构化条目（类型不可更改，字段整体替换）
+#[tauri::command]
+pub async fn update_item(
+    state: State<'_, AppState>,
+    id: i64,
+    mut item: VaultItem,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("update_item called: id={}", id);
+    let existing = state.db.get_vault_item(id)?.ok_or("Item not found")?;
+    if existing.item_type != item.item_type {
+        return Err("不能更改条目类型".to_string());
+    }
+    item.id = Some(id);
+    items::prepare(&mut item)?;
+    let cipher = state.field_cipher()?;
+    cipher.encrypt_item(&mut item)?;
+    state.db.update_vault_item(&item)?;
+    Ok(json!({ "success": true }))
+}
+
+/// 删除结构化条目
+#[tauri::command]
+pub async fn delete_item(state: State<'_, AppState>, id: i64) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("delete_item called: id={}", id);
+    state.db.delete_vault_item(id)?;
+    Ok(json!({ "success": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_card() -> VaultItem {
+        serde_json::from_value(json!({
+            "itemType": "card",
+            "title": "Visa",
+            "fields": { "number": "4111 1111 1111 1111", "cvv": "123", "cardholder": "Alice" }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_item_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(list_all_items(app.state()).await);
+        assert_vault_locked(get_items(app.state(), None).await);
+        assert_vault_locked(get_item(app.state(), 1).await);
+        assert_vault_locked(add_item(app.state(), sample_card()).await);
+        assert_vault_locked(update_item(app.state(), 1, sample_card()).await);
+        assert_vault_locked(delete_item(app.state(), 1).await);
+    }
+
+    #[tokio::test]
+    async fn test_card_secrets_encrypted_at_rest() {
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+
+        let id = add_item(app.state(), sample_card()).await.unwrap()["id"].as_i64().unwrap();
+        let stored = state.db.get_vault_item(id).unwrap().unwrap();
+        assert_ne!(stored.fields["number"].expose(), "4111111111111111");
+        assert_ne!(stored.fields["cvv"].expose(), "123");
+        assert_eq!(stored.fields["cardholder"].expose(), "Alice");
+
+        let item = get_item(app.state(), id).await.unwrap().unwrap();
+        assert_eq!(item.fields["number"].expose(), "4111111111111111");
+
+        let mut wifi = sample_card();
+        wifi.item_type = ItemType::Wifi;
+        assert!(update_item(app.state(), id, wifi).await.is_err());
+
+        let summaries = list_all_items(app.state()).await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].item_type, ItemType::Card);
+    }
+}
@@ -3,6 +3,16 @@
 pub mod groups;
 pub mod notes;
 pub mod passwords;
+pub mod totp;
+pub mod custom_fields;
+pub mod accounts;
+pub mod attachments;
+pub mod items;
+pub mod trash;
+pub mod revisions;
+pub mod audit;
+pub mod breach;
+pub mod search;
 pub mod security;
 pub mod settings;
 pub mod backup;
@@ -1,6 +1,8 @@
 //! 笔记管理 Commands
 
-use crate::models::{SecureRecord, SecureRecordGroup};
+use crate::models::{ItemType, SecureRecord, SecureRecordGroup};
+use crate::services::field_encryption::like_contains;
+use crate::services::{fuzzy, revisions};
 use crate::AppState;
 use tauri::State;
 use serde::Deserialize;
@@ -26,45 +28,11 @@ pub struct ReorderNoteGroupInput {
     pub insert_index: usize,
 }
 
-/// 辅助函数：解密笔记内容
-fn decrypt_note_content(state: &State<'_, AppState>, note: &mut SecureRecord) {
-    log::info!("[decrypt_note_content] 开始解密，note.id={:?}", note.id);
-    if let Some(cipher) = &note.content {
-        if !cipher.is_empty() {
-            log::info!("[decrypt_note_content] 密文长度: {}", cipher.len());
-            match state.encryption.decrypt(cipher) {
-                Ok(plain) => {
-                    log::info!("[decrypt_note_content] 解密成功，明文长度: {}", plain.len());
-                    note.content = Some(plain);
-                }
-                Err(e) => {
-                    log::error!("[decrypt_note_content] 解密失败: {}", e);
-                    // 解密失败时保留原密文，让前端可以看到原始数据
-                }
-            }
-        } else {
-            log::info!("[decrypt_note_content] 密文为空");
-        }
-    } else {
-        log::info!("[decrypt_note_content] content 为 None");
-    }
-}
-
-/// 辅助函数：加密笔记内容
-fn encrypt_note_content(state: &State<'_, AppState>, note: &mut SecureRecord) -> Result<(), String> {
-    if let Some(plain) = &note.content {
-        if !plain.is_empty() {
-            let cipher = state.encryption.encrypt(plain)?;
-            note.content = Some(cipher);
-        }
-    }
-    Ok(())
-}
-
 // --- Note Groups ---
 
 #[tauri::command]
 pub async fn get_note_groups(state: State<'_, AppState>) -> Result<Vec<SecureRecordGroup>, String> {
+    state.ensure_unlocked()?;
     state.db.get_note_groups().map_err(|e| e.to_string())
 }
 
@@ -73,6 +41,7 @@ pub async fn get_note_group_tree(
     state: State<'_, AppState>,
     parent_id: Option<i64>,
 ) -> Result<Vec<SecureRecordGroupWithChildren>, String> {
+    state.ensure_unlocked()?;
     let groups = state.db.get_note_groups().map_err(|e| e.to_string())?;
     Ok(build_note_group_tree(groups, parent_id))
 }
@@ -85,7 +54,7 @@ fn build_note_group_tree(
     for g in groups {
         children_map
             .entry(g.parent_id)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(g);
     }
     build_note_tree_recursive(&children_map, root_parent_id)
@@ -116,17 +85,20 @@ fn build_note_tree_recursive(
 
 #[tauri::command]
 pub async fn get_note_group(state: State<'_, AppState>, id: i64) -> Result<Option<SecureRecordGroup>, String> {
+    state.ensure_unlocked()?;
     state.db.get_note_group(id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn add_note_group(state: State<'_, AppState>, group: SecureRecordGroup) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     let id = state.db.add_note_group(&group).map_err(|e| e.to_string())?;
     Ok(json!({ "success": true, "id": id }))
 }
 
 #[tauri::command]
 pub async fn update_note_group(state: State<'_, AppState>, id: i64, mut group: SecureRecordGroup) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("[update_note_group] 开始更新分组, id={}, group={:?}", id, group);
     group.id = Some(id);
     match state.db.update_note_group(&group) {
@@ -143,7 +115,10 @@ pub async fn update_note_group(state: State<'_, AppState>, id: i64, mut group: S
 
 #[tauri::command]
 pub async fn delete_note_group(state: State<'_, AppState>, id: i64) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     state.db.delete_note_group(id).map_err(|e| e.to_string())?;
+    // 分组内的笔记已移入回收站
+    super::search::invalidate_search_index(&state);
     Ok(json!({ "success": true }))
 }
 
@@ -152,6 +127,7 @@ pub async fn reorder_note_group(
     state: State<'_, AppState>,
     input: ReorderNoteGroupInput,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     state
         .db
         .reorder_note_group(input.drag_id, input.new_parent_id, input.insert_index)
@@ -163,21 +139,21 @@ pub async fn reorder_note_group(
 
 #[tauri::command]
 pub async fn get_notes(state: State<'_, AppState>, group_id: Option<i64>) -> Result<Vec<SecureRecord>, String> {
+    state.ensure_unlocked()?;
     log::info!("[get_notes] 开始获取笔记列表, group_id={:?}", group_id);
-    let mut notes = state.db.get_notes(group_id).map_err(|e| e.to_string())?;
-    log::info!("[get_notes] 从数据库获取到 {} 条笔记", notes.len());
-    for note in &mut notes {
-        log::info!("[get_notes] 处理笔记 id={:?}, title={:?}", note.id, note.title);
-        decrypt_note_content(&state, note);
-    }
+    let notes = state
+        .db
+        .get_notes(group_id, Some(&*state.field_cipher()?))
+        .map_err(|e| e.to_string())?;
     log::info!("[get_notes] 完成，返回 {} 条笔记", notes.len());
     Ok(notes)
 }
 
 #[tauri::command]
 pub async fn get_note(state: State<'_, AppState>, id: i64) -> Result<Option<SecureRecord>, String> {
+    state.ensure_unlocked()?;
     if let Some(mut note) = state.db.get_note(id).map_err(|e| e.to_string())? {
-        decrypt_note_content(&state, &mut note);
+        state.field_cipher()?.decrypt_note(&mut note);
         Ok(Some(note))
     } else {
         Ok(None)
@@ -186,55 +162,105 @@ pub async fn get_note(state: State<'_, AppState>, id: i64) -> Result<Option<Secu
 
 #[tauri::command]
 pub async fn add_note(state: State<'_, AppState>, mut note: SecureRecord) -> Result<Value, String> {
-    encrypt_note_content(&state, &mut note)?;
+    state.ensure_unlocked()?;
+    let cipher = state.field_cipher()?;
+    cipher.encrypt_note(&mut note)?;
     let id = state.db.add_note(&note).map_err(|e| e.to_string())?;
+    super::search::refresh_search_index(&state, ItemType::SecureNote, id);
     Ok(json!({ "success": true, "id": id }))
 }
 
 #[tauri::command]
-pub async fn update_note(state: State<'_, AppState>, id: i64, mut note: SecureRecord) -> Result<Value, String> {
+pub async fn update_note(state: State<'_, AppState>, id: i64, note: SecureRecord) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    save_note(&state, id, note)?;
+    Ok(json!({ "success": true }))
+}
+
+/// 以明文笔记更新：按明文比较后记录修订，再加密保存
+pub(crate) fn save_note(state: &AppState, id: i64, mut note: SecureRecord) -> Result<(), String> {
+    let mut previous = state.db.get_note(id).map_err(|e| e.to_string())?.ok_or("Note not found")?;
+    let cipher = state.field_cipher()?;
+    cipher.decrypt_note(&mut previous);
     note.id = Some(id);
-    encrypt_note_content(&state, &mut note)?;
+    revisions::record(
+        &state.db,
+        &*state.encryption_service()?,
+        ItemType::SecureNote,
+        id,
+        &revisions::note_snapshot(&previous),
+        &revisions::note_snapshot(&note),
+    )?;
+    cipher.encrypt_note(&mut note)?;
     state.db.update_note(&note).map_err(|e| e.to_string())?;
-    Ok(json!({ "success": true }))
+    super::search::refresh_search_index(state, ItemType::SecureNote, id);
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn delete_note(state: State<'_, AppState>, id: i64) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     state.db.delete_note(id).map_err(|e| e.to_string())?;
+    super::search::refresh_search_index(&state, ItemType::SecureNote, id);
     Ok(json!({ "success": true }))
 }
 
 #[tauri::command]
 pub async fn search_notes_title(state: State<'_, AppState>, keyword: String) -> Result<Vec<SecureRecord>, String> {
-    // Note: This searches database. 
-    // If content is encrypted, searching content in DB will not yield correct results for plaintext keywords.
-    // Title search works.
-    let notes = state.db.search_notes(&keyword).map_err(|e| e.to_string())?;
-    // We don't decrypt results for search list usually, or we do?
-    // If UI shows snippet, we might need to decrypt.
-    // Let's decrypt to be safe/consistent.
-    /*
-    let mut decrypted_notes = notes;
-    for note in &mut decrypted_notes {
-        decrypt_note_content(&state, note);
+    state.ensure_unlocked()?;
+    // 笔记内容与（按策略）标题均为密文，解密后在内存中匹配；标题按模糊与拼音匹配打分，正文只做包含匹配
+    let matcher = fuzzy::Matcher::new(&keyword);
+    let mut scored: Vec<(SecureRecord, f64)> = state
+        .db
+        .get_notes(None, Some(&*state.field_cipher()?))?
+        .into_iter()
+        .filter_map(|note| {
+            let score = matcher.score(&note.title).or_else(|| {
+                note.content
+                    .as_ref()
+                    .is_some_and(|content| like_contains(content.expose(), &keyword))
+                    .then_some(fuzzy::CONTENT_SCORE)
+            })?;
+            Some((note, score))
+        })
+        .collect();
+    fuzzy::rank(&mut scored, |note| &note.title);
+    Ok(scored.into_iter().map(|(note, _)| note).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_note_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        let note: SecureRecord =
+            serde_json::from_value(json!({ "title": "Note", "content": "body" })).unwrap();
+        assert_vault_locked(get_notes(app.state(), None).await);
+        assert_vault_locked(get_note(app.state(), 1).await);
+        assert_vault_locked(add_note(app.state(), note.clone()).await);
+        assert_vault_locked(update_note(app.state(), 1, note).await);
+        assert_vault_locked(delete_note(app.state(), 1).await);
+        assert_vault_locked(search_notes_title(app.state(), "Note".to_string()).await);
+        assert_vault_locked(get_note_groups(app.state()).await);
+        assert_vault_locked(get_note_group_tree(app.state(), None).await);
     }
-    Ok(decrypted_notes)
-    */
-    // Wait, searching returns many results. Decrypting all might be slow if list is huge.
-    // But for local app it's fine.
-    
-    // However, since we matched query against *Ciphertext* or *Title*, 
-    // matched content notes might be weird (matching base64 string).
-    // Better to filter by Title Only in DB if we want to avoid confusion?
-    // But `search_notes` in DB does `OR content LIKE`.
-    // Let's just return what DB gave, but decrypted.
-    
-    // Actually, Rust iterators consumption.
-    let mut notes = notes;
-    for note in &mut notes {
-        decrypt_note_content(&state, note);
+
+    #[tokio::test]
+    async fn test_note_commands_allowed_when_unlocked() {
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let note: SecureRecord =
+            serde_json::from_value(json!({ "title": "Note", "content": "body" })).unwrap();
+        add_note(app.state(), note).await.unwrap();
+        let notes = get_notes(app.state(), None).await.unwrap();
+        assert_eq!(notes[0].content.as_ref().map(crate::models::SecretString::expose), Some("body"));
     }
-    
-    Ok(notes)
 }
@@ -1,44 +1,29 @@
 //! 密码管理 Commands
 
-use crate::models::{Password, PasswordSearchResult, PasswordHistory};
+use crate::models::{
+    GeneratedPassword, ItemType, Password, PasswordGeneratorOptions, PasswordHistoryRetention, PasswordPolicy,
+    PasswordSearchResult,
+};
+use crate::services::field_encryption::like_contains;
+use crate::services::{fuzzy, generator, password_history, revisions};
 use serde_json::Value;
 use tauri::State;
 use crate::AppState;
 
-/// 辅助函数：解密密码字段
-fn decrypt_password_field(state: &State<'_, AppState>, p: &mut Password) {
-    if let Some(cipher) = &p.password {
-        if !cipher.is_empty() {
-            if let Ok(plain) = state.encryption.decrypt(cipher) {
-                p.password = Some(plain);
-            }
-        }
-    }
-}
-
-/// 辅助函数：加密密码字段
-fn encrypt_password_field(state: &State<'_, AppState>, p: &mut Password) -> Result<(), String> {
-    if let Some(plain) = &p.password {
-        if !plain.is_empty() {
-            let cipher = state.encryption.encrypt(plain)?;
-            p.password = Some(cipher);
-        }
-    }
-    Ok(())
-}
-
 /// 获取密码列表
 #[tauri::command]
 pub async fn get_passwords(
     state: State<'_, AppState>,
     group_id: Option<i64>,
 ) -> Result<Vec<Password>, String> {
+    state.ensure_unlocked()?;
     log::info!("get_passwords called with group_id: {:?}", group_id);
     let mut passwords = state.db.get_passwords(group_id).map_err(|e| e.to_string())?;
     
-    // Decrypt passwords
+    // 按字段加密策略解密
+    let cipher = state.field_cipher()?;
     for p in &mut passwords {
-        decrypt_password_field(&state, p);
+        cipher.decrypt_password(p);
     }
     
     Ok(passwords)
@@ -50,9 +35,10 @@ pub async fn get_password(
     state: State<'_, AppState>,
     id: i64,
 ) -> Result<Option<Password>, String> {
+    state.ensure_unlocked()?;
     log::info!("get_password called with id: {}", id);
     if let Some(mut p) = state.db.get_password(id).map_err(|e| e.to_string())? {
-        decrypt_password_field(&state, &mut p);
+        state.field_cipher()?.decrypt_password(&mut p);
         Ok(Some(p))
     } else {
         Ok(None)
@@ -65,9 +51,11 @@ pub async fn add_password(
     state: State<'_, AppState>,
     mut password: Password,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("add_password called: {:?}", password.title);
     
-    encrypt_password_field(&state, &mut password)?;
+    let cipher = state.field_cipher()?;
+    cipher.encrypt_password(&mut password)?;
     
     let id = state.db.add_password(&password).map_err(|e| {
         log::error!("Failed to add password to database: {}", e);
@@ -75,6 +63,7 @@ pub async fn add_password(
     })?;
     
     log::info!("Password added successfully with id: {}", id);
+    super::search::refresh_search_index(&state, ItemType::Login, id);
     
     Ok(serde_json::json!({
         "success": true,
@@ -87,36 +76,49 @@ pub async fn add_password(
 pub async fn update_password(
     state: State<'_, AppState>,
     id: i64,
-    mut password: Password,
+    password: Password,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("update_password called: id={}", id);
-    
-    // 获取旧密码用于历史记录
-    let old_password_encrypted = if let Some(old_pwd) = state.db.get_password(id).map_err(|e| e.to_string())? {
-        old_pwd.password
-    } else {
-        return Err("Password not found".to_string());
-    };
-    
+    save_password(&state, id, password, "密码更新")?;
+    Ok(serde_json::json!({
+        "success": true
+    }))
+}
+
+/// 以明文条目更新密码：按明文比较后记录修订与密码历史，再加密保存
+///
+/// 密码变化时以 `change_reason` 记录历史，并按保留策略清理该条目的历史记录。
+pub(crate) fn save_password(
+    state: &AppState,
+    id: i64,
+    mut password: Password,
+    change_reason: &str,
+) -> Result<(), String> {
+    let stored = state.db.get_password(id).map_err(|e| e.to_string())?.ok_or("Password not found")?;
+    let cipher = state.field_cipher()?;
+    let mut previous = stored.clone();
+    cipher.decrypt_password(&mut previous);
+
     // 确保 ID 一致
     password.id = Some(id);
-    
-    // 加密新密码
-    encrypt_password_field(&state, &mut password)?;
-    
-    // 如果密码发生变化，保存历史记录
-    if let (Some(old_pwd), Some(new_pwd)) = (&old_password_encrypted, &password.password) {
-        if old_pwd != new_pwd {
-            state.db.add_password_history(id, old_pwd, Some("密码更新")).map_err(|e| e.to_string())?;
+
+    let before = revisions::password_snapshot(&previous);
+    let after = revisions::password_snapshot(&password);
+    revisions::record(&state.db, &*state.encryption_service()?, ItemType::Login, id, &before, &after)?;
+
+    // 密码明文发生变化时保存历史记录
+    if let Some(old_pwd) = stored.password.as_ref().filter(|p| !p.is_empty()) {
+        if before.get("password") != after.get("password") {
+            state.db.add_password_history(id, old_pwd.expose(), Some(change_reason)).map_err(|e| e.to_string())?;
+            password_history::enforce(&state.db, id)?;
         }
     }
-    
-    // 更新密码
+
+    cipher.encrypt_password(&mut password)?;
     state.db.update_password(&password).map_err(|e| e.to_string())?;
-    
-    Ok(serde_json::json!({
-        "success": true
-    }))
+    super::search::refresh_search_index(state, ItemType::Login, id);
+    Ok(())
 }
 
 /// 删除密码
@@ -125,9 +127,11 @@ pub async fn delete_password(
     state: State<'_, AppState>,
     id: i64,
 ) -> Result<Value, String> {
+    state.ensure_unlocked()?;
     log::info!("delete_password called: id={}", id);
     
     state.db.delete_password(id).map_err(|e| e.to_string())?;
+    super::search::refresh_search_index(&state, ItemType::Login, id);
     
     Ok(serde_json::json!({
         "success": true
@@ -140,89 +144,127 @@ pub async fn search_passwords(
     state: State<'_, AppState>,
     keyword: String,
 ) -> Result<Vec<PasswordSearchResult>, String> {
+    state.ensure_unlocked()?;
     log::info!("search_passwords called: keyword={}", keyword);
+    let cipher = state.field_cipher()?;
+
+    // 包含匹配覆盖备注、自定义字段与附加网址；标题、用户名与主网址另按模糊与拼音匹配打分
+    let related_ids = state.db.search_related_password_ids(&keyword, &cipher)?;
+    let matcher = fuzzy::Matcher::new(&keyword);
+    let mut scored = Vec::new();
+    for mut p in state.db.get_passwords(None)? {
+        cipher.decrypt_password(&mut p);
+        let related = || {
+            p.id.is_some_and(|id| related_ids.contains(&id))
+                || [Some(&p.title), p.username.as_ref(), p.url.as_ref(), p.notes.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .any(|value| like_contains(value, &keyword))
+        };
+        let score = [(Some(&p.title), 1.0), (p.username.as_ref(), 0.9), (p.url.as_ref(), 0.8)]
+            .into_iter()
+            .filter_map(|(value, weight)| value.and_then(|v| matcher.score(v)).map(|score| score * weight))
+            .reduce(f64::max)
+            .or_else(|| related().then_some(fuzzy::CONTENT_SCORE));
+        if let Some(score) = score {
+            scored.push((p, score));
+        }
+    }
+    fuzzy::rank(&mut scored, |p| &p.title);
+
+    let results = to_search_results(&state, scored)?;
     
-    let passwords = state.db.search_passwords(&keyword).map_err(|e| e.to_string())?;
+    log::info!("Search results count: {}", results.len());
     
+    Ok(results)
+}
+
+/// 转换为 PasswordSearchResult 并填充 group_name
+pub(crate) fn to_search_results(
+    state: &AppState,
+    passwords: Vec<(Password, f64)>,
+) -> Result<Vec<PasswordSearchResult>, String> {
     // 获取所有分组用于查找分组名称
     let groups = state.db.get_groups().map_err(|e| e.to_string())?;
     let group_map: std::collections::HashMap<i64, String> = groups
         .into_iter()
         .filter_map(|g| g.id.map(|id| (id, g.name)))
         .collect();
-    
-    log::info!("Group map: {:?}", group_map);
-    
-    // 转换为 PasswordSearchResult 并填充 group_name
-    let results: Vec<PasswordSearchResult> = passwords.into_iter().map(|p| {
-        let group_name = p.group_id.and_then(|gid| group_map.get(&gid).cloned());
-        log::info!("Password: id={}, title={}, group_id={:?}, group_name={:?}", 
-                   p.id.unwrap_or(0), p.title, p.group_id, group_name);
-        PasswordSearchResult {
-            id: p.id.unwrap_or(0),
-            title: p.title,
-            username: p.username,
-            url: p.url,
-            group_id: p.group_id,
-            group_name,
-        }
-    }).collect();
-    
-    log::info!("Search results count: {}", results.len());
-    
-    Ok(results)
+
+    Ok(passwords
+        .into_iter()
+        .map(|(p, score)| {
+            let group_name = p.group_id.and_then(|gid| group_map.get(&gid).cloned());
+            PasswordSearchResult {
+                id: p.id.unwrap_or(0),
+                title: p.title,
+                username: p.username,
+                url: p.url,
+                group_id: p.group_id,
+                group_name,
+                score,
+            }
+        })
+        .collect())
 }
 
-/// 密码生成器选项
-#[derive(serde::Deserialize)]
-pub struct PasswordGeneratorOptions {
-    length: Option<usize>,
-    #[serde(rename = "includeUppercase")]
-    include_uppercase: Option<bool>,
-    #[serde(rename = "includeLowercase")]
-    include_lowercase: Option<bool>,
-    #[serde(rename = "includeNumbers")]
-    include_numbers: Option<bool>,
-    #[serde(rename = "includeSymbols")]
-    include_symbols: Option<bool>,
+/// 生成随机密码或单词口令，并返回熵（位）
+#[tauri::command]
+pub async fn generate_password(options: PasswordGeneratorOptions) -> Result<GeneratedPassword, String> {
+    generator::generate(&options)
 }
 
-/// 生成随机密码
+/// 按命名规则生成密码；未指定规则名时使用分组或上级分组关联的规则，都没有时使用默认选项
 #[tauri::command]
-pub async fn generate_password(options: PasswordGeneratorOptions) -> Result<String, String> {
-    use rand::Rng;
-    
-    let length = options.length.unwrap_or(16).max(4).min(128);
-    let include_uppercase = options.include_uppercase.unwrap_or(true);
-    let include_lowercase = options.include_lowercase.unwrap_or(true);
-    let include_numbers = options.include_numbers.unwrap_or(true);
-    let include_symbols = options.include_symbols.unwrap_or(true);
-
-    let mut charset = String::new();
-    if include_uppercase {
-        charset.push_str("ABCDEFGHIJKLMNOPQRSTUVWXYZ");
-    }
-    if include_lowercase {
-        charset.push_str("abcdefghijklmnopqrstuvwxyz");
-    }
-    if include_numbers {
-        charset.push_str("0123456789");
-    }
-    if include_symbols {
-        charset.push_str("!@#$%^&*()_+-=[]{}|;:,.<>?");
-    }
+pub async fn generate_password_with_policy(
+    state: State<'_, AppState>,
+    policy_name: Option<String>,
+    group_id: Option<i64>,
+) -> Result<GeneratedPassword, String> {
+    let options = generator::resolve_policy(&state.db, policy_name.as_deref(), group_id)?
+        .map(|policy| policy.options)
+        .unwrap_or_default();
+    generator::generate(&options)
+}
 
-    if charset.is_empty() {
-        return Err("至少需要选择一种字符类型".to_string());
-    }
+/// 获取全部密码生成规则
+#[tauri::command]
+pub async fn get_password_policies(state: State<'_, AppState>) -> Result<Value, String> {
+    Ok(serde_json::json!({
+        "success": true,
+        "policies": generator::load_policies(&state.db)?,
+        "groupPolicies": generator::load_group_policies(&state.db)?,
+    }))
+}
 
-    let charset_chars: Vec<char> = charset.chars().collect();
-    let mut rng = rand::thread_rng();
-    let password: String = (0..length)
-        .map(|_| charset_chars[rng.gen_range(0..charset_chars.len())])
-        .collect();
+/// 新增或替换同名密码生成规则
+#[tauri::command]
+pub async fn save_password_policy(state: State<'_, AppState>, policy: PasswordPolicy) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("save_password_policy called: name={}", policy.name);
+    generator::save_policy(&state.db, policy)?;
+    Ok(serde_json::json!({ "success": true }))
+}
 
-    Ok(password)
+/// 删除密码生成规则
+#[tauri::command]
+pub async fn delete_password_policy(state: State<'_, AppState>, name: String) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("delete_password_policy called: name={}", name);
+    generator::delete_policy(&state.db, &name)?;
+    Ok(serde_json::json!({ "success": true }))
+}
+
+/// 为分组关联密码生成规则；`policy_name` 为空时解除关联
+#[tauri::command]
+pub async fn set_group_password_policy(
+    state: State<'_, AppState>,
+    group_id: i64,
+    policy_name: Option<String>,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    generator::set_group_policy(&state.db, group_id, policy_name.as_deref())?;
+    Ok(serde_json::json!({ "success": true }))
 }
 
 #[cfg(test)]
@@ -237,13 +279,23 @@ mod tests {
             include_lowercase: Some(true),
             include_numbers: Some(true),
             include_symbols: Some(true),
+            ..Default::default()
         };
         
         let result = generate_password(options).await;
         assert!(result.is_ok());
         
-        let password = result.unwrap();
-        assert_eq!(password.len(), 16);
+        let generated = result.unwrap();
+        assert_eq!(generated.password.len(), 16);
+        assert!((generated.entropy_bits - 16.0 * 88f64.log2()).abs() < 1e-9);
+
+        // 启用的字符类型每次都至少出现一个
+        for _ in 0..100 {
+            let options = PasswordGeneratorOptions { length: Some(16), ..Default::default() };
+            let password = generate_password(options).await.unwrap().password;
+            assert!(password.expose().chars().any(|c| c.is_ascii_digit()));
+            assert!(password.expose().chars().any(|c| !c.is_ascii_alphanumeric()));
+        }
     }
 
     #[tokio::test]
@@ -255,11 +307,12 @@ mod tests {
             include_uppercase: None,
             include_numbers: None,
             include_symbols: None,
+            ..Default::default()
         };
         
         let result = generate_password(options).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap().len(), 4);
+        assert_eq!(result.unwrap().password.len(), 4);
 
         // 测试最大长度
         let options = PasswordGeneratorOptions {
@@ -268,11 +321,12 @@ mod tests {
             include_uppercase: None,
             include_numbers: None,
             include_symbols: None,
+            ..Default::default()
         };
         
         let result = generate_password(options).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap().len(), 128);
+        assert_eq!(result.unwrap().password.len(), 128);
     }
 
     #[tokio::test]
@@ -283,6 +337,7 @@ mod tests {
             include_lowercase: Some(false),
             include_numbers: Some(false),
             include_symbols: Some(false),
+            ..Default::default()
         };
         
         let result = generate_password(options).await;
@@ -298,14 +353,127 @@ mod tests {
             include_lowercase: Some(false),
             include_numbers: Some(false),
             include_symbols: Some(false),
+            ..Default::default()
         };
         
         let result = generate_password(options).await;
         assert!(result.is_ok());
         
-        let password = result.unwrap();
+        let password = result.unwrap().password;
         assert_eq!(password.len(), 10);
-        assert!(password.chars().all(|c| c.is_uppercase()));
+        assert!(password.expose().chars().all(|c| c.is_uppercase()));
+    }
+
+    fn sample_password() -> Password {
+        serde_json::from_value(serde_json::json!({ "title": "GitHub", "password": "s3cret" })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_password_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(get_passwords(app.state(), None).await);
+        assert_vault_locked(get_password(app.state(), 1).await);
+        assert_vault_locked(add_password(app.state(), sample_password()).await);
+        assert_vault_locked(update_password(app.state(), 1, sample_password()).await);
+        assert_vault_locked(delete_password(app.state(), 1).await);
+        assert_vault_locked(search_passwords(app.state(), "Git".to_string()).await);
+        assert_vault_locked(get_password_history(app.state(), 1).await);
+        assert_vault_locked(restore_password_from_history(app.state(), 1).await);
+        assert_vault_locked(delete_password_history_entry(app.state(), 1).await);
+        assert_vault_locked(set_password_history_retention(app.state(), Default::default()).await);
+        let policy = PasswordPolicy { name: "PIN".to_string(), options: Default::default() };
+        assert_vault_locked(save_password_policy(app.state(), policy).await);
+        assert_vault_locked(delete_password_policy(app.state(), "PIN".to_string()).await);
+        assert_vault_locked(set_group_password_policy(app.state(), 1, None).await);
+    }
+
+    #[tokio::test]
+    async fn test_restore_password_from_history_swaps_secret() {
+        use crate::models::{PasswordHistoryRetention, SecretString};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let id = add_password(app.state(), sample_password()).await.unwrap()["id"].as_i64().unwrap();
+        for next in ["second", "third"] {
+            let mut edited = sample_password();
+            edited.password = Some(next.into());
+            update_password(app.state(), id, edited).await.unwrap();
+        }
+
+        let history = state.db.get_password_history(id).unwrap();
+        assert_eq!(history.len(), 2);
+        let oldest = history.last().unwrap().id.unwrap();
+        restore_password_from_history(app.state(), oldest).await.unwrap();
+        let current = get_password(app.state(), id).await.unwrap().unwrap();
+        assert_eq!(current.password.as_ref().map(SecretString::expose), Some("s3cret"));
+        let history = get_password_history(app.state(), id).await.unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0]["old_password"], "third");
+        assert_eq!(history[0]["change_reason"], "从历史记录恢复");
+
+        delete_password_history_entry(app.state(), oldest).await.unwrap();
+        assert_eq!(state.db.get_password_history(id).unwrap().len(), 2);
+
+        let retention = PasswordHistoryRetention { max_entries: 1, max_age_days: 0 };
+        set_password_history_retention(app.state(), retention).await.unwrap();
+        let mut edited = sample_password();
+        edited.password = Some("fourth".into());
+        update_password(app.state(), id, edited).await.unwrap();
+        let history = state.db.get_password_history(id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(get_password_history_retention(app.state()).await.unwrap(), retention);
+    }
+
+    #[tokio::test]
+    async fn test_password_commands_allowed_when_unlocked() {
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        add_password(app.state(), sample_password()).await.unwrap();
+        let passwords = get_passwords(app.state(), None).await.unwrap();
+        assert_eq!(passwords[0].password.as_ref().map(crate::models::SecretString::expose), Some("s3cret"));
+    }
+
+    #[tokio::test]
+    async fn test_search_passwords_ranks_fuzzy_and_pinyin_matches() {
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        for (title, notes) in [
+            ("招商银行", None),
+            ("工商银行", None),
+            ("GitHub", None),
+            ("Gitea", None),
+            ("VPN", Some("招商 zsyh 专线")),
+        ] {
+            let password: Password =
+                serde_json::from_value(serde_json::json!({ "title": title, "notes": notes })).unwrap();
+            add_password(app.state(), password).await.unwrap();
+        }
+
+        // 首字母命中标题的条目排在只有备注包含关键字的条目之前
+        let results = search_passwords(app.state(), "zsyh".to_string()).await.unwrap();
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["招商银行", "VPN"]);
+        assert!(results[0].score > results[1].score);
+
+        // 拼写错误
+        let results = search_passwords(app.state(), "gihtub".to_string()).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "GitHub");
+
+        let results = search_passwords(app.state(), "git".to_string()).await.unwrap();
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["GitHub", "Gitea"]);
+        assert_eq!(search_passwords(app.state(), String::new()).await.unwrap().len(), 5);
     }
 }
 
@@ -315,6 +483,8 @@ pub async fn get_password_history(
     state: State<'_, AppState>,
     password_id: i64,
 ) -> Result<Vec<serde_json::Value>, String> {
+    state.ensure_unlocked()?;
+    let encryption = state.encryption_service()?;
     let history = state.db.get_password_history(password_id).map_err(|e| e.to_string())?;
     
     // 获取当前密码作为 new_password
@@ -325,7 +495,7 @@ pub async fn get_password_history(
     
     // 解密当前密码
     let current_password_decrypted = if !current_password.is_empty() {
-        state.encryption.decrypt(&current_password).unwrap_or(current_password.clone())
+        encryption.decrypt(current_password.expose()).unwrap_or(current_password.clone())
     } else {
         current_password
     };
@@ -333,7 +503,7 @@ pub async fn get_password_history(
     // 构建返回结果，解密旧密码并添加 new_password 字段
     let results: Vec<serde_json::Value> = history.into_iter().map(|h| {
         // 解密旧密码
-        let old_password_decrypted = state.encryption.decrypt(&h.old_password)
+        let old_password_decrypted = encryption.decrypt(h.old_password.expose())
             .unwrap_or(h.old_password.clone());
         
         serde_json::json!({
@@ -348,3 +518,64 @@ pub async fn get_password_history(
     
     Ok(results)
 }
+
+/// 恢复历史密码：当前密码记入历史，历史密码成为当前密码
+#[tauri::command]
+pub async fn restore_password_from_history(
+    state: State<'_, AppState>,
+    history_id: i64,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("restore_password_from_history called: history_id={}", history_id);
+    let entry = state
+        .db
+        .get_password_history_entry(history_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("History entry not found")?;
+    let mut password = state
+        .db
+        .get_password(entry.password_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("Password not found")?;
+    state.field_cipher()?.decrypt_password(&mut password);
+    password.password = Some(state.encryption_service()?.decrypt(entry.old_password.expose())?);
+    save_password(&state, entry.password_id, password, "从历史记录恢复")?;
+    Ok(serde_json::json!({
+        "success": true
+    }))
+}
+
+/// 删除单条密码历史记录
+#[tauri::command]
+pub async fn delete_password_history_entry(
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("delete_password_history_entry called: id={}", id);
+    state.db.delete_password_history_entry(id).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "success": true
+    }))
+}
+
+/// 获取密码历史保留策略
+#[tauri::command]
+pub async fn get_password_history_retention(
+    state: State<'_, AppState>,
+) -> Result<PasswordHistoryRetention, String> {
+    password_history::load_retention(&state.db)
+}
+
+/// 设置密码历史保留策略；之后每次更新密码时按新策略清理
+#[tauri::command]
+pub async fn set_password_history_retention(
+    state: State<'_, AppState>,
+    retention: PasswordHistoryRetention,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    password_history::save_retention(&state.db, &retention)?;
+    Ok(serde_json::json!({
+        "success": true
+    }))
+}
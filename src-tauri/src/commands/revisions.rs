@@ -0,0 +1,160 @@
+//! 修订历史 Commands
+
+use crate::models::{ItemType, Revision, RevisionFieldChange, RevisionSnapshot};
+use crate::services::revisions;
+use crate::AppState;
+use serde_json::{json, Value};
+use tauri::State;
+
+/// 列出密码条目或笔记的修订记录（新的在前）
+#[tauri::command]
+pub async fn get_revisions(
+    state: State<'_, AppState>,
+    item_type: ItemType,
+    item_id: i64,
+) -> Result<Vec<Revision>, String> {
+    state.ensure_unlocked()?;
+    revisions::ensure_supported(item_type)?;
+    state.db.get_revisions(item_type, item_id)
+}
+
+/// 比较修订与另一条修订或当前版本（`compare_to` 为空时）的差异
+#[tauri::command]
+pub async fn diff_revision(
+    state: State<'_, AppState>,
+    id: i64,
+    compare_to: Option<i64>,
+) -> Result<Vec<RevisionFieldChange>, String> {
+    state.ensure_unlocked()?;
+    let revision = state.db.get_revision(id)?.ok_or("Revision not found")?;
+    let encryption = state.encryption_service()?;
+    let before = revisions::open(&encryption, &revision)?;
+    let after = match compare_to {
+        Some(other_id) => {
+            let other = state.db.get_revision(other_id)?.ok_or("Revision not found")?;
+            if (other.item_type, other.item_id) != (revision.item_type, revision.item_id) {
+                return Err("只能比较同一条目的修订".to_string());
+            }
+            revisions::open(&encryption, &other)?
+        }
+        None => current_snapshot(&state, revision.item_type, revision.item_id)?,
+    };
+    Ok(revisions::diff(&before, &after))
+}
+
+/// 将条目恢复为修订中的版本；恢复前的版本会记录为新的修订
+#[tauri::command]
+pub async fn restore_revision(state: State<'_, AppState>, id: i64) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("restore_revision called: id={}", id);
+    let revision = state.db.get_revision(id)?.ok_or("Revision not found")?;
+    let snapshot = revisions::open(&*state.encryption_service()?, &revision)?;
+    let cipher = state.field_cipher()?;
+    match revision.item_type {
+        ItemType::Login => {
+            let mut password = state.db.get_password(revision.item_id)?.ok_or("Password not found")?;
+            cipher.decrypt_password(&mut password);
+            let current_group = password.group_id;
+            revisions::apply_password_snapshot(&mut password, &snapshot)?;
+            // 原分组已删除时保留当前分组
+            if let Some(group_id) = password.group_id {
+                if state.db.get_group(group_id)?.is_none() {
+                    password.group_id = current_group;
+                }
+            }
+            super::passwords::save_password(&state, revision.item_id, password, "恢复修订")?;
+        }
+        ItemType::SecureNote => {
+            let mut note = state.db.get_note(revision.item_id)?.ok_or("Note not found")?;
+            cipher.decrypt_note(&mut note);
+            let current_group = note.group_id;
+            revisions::apply_note_snapshot(&mut note, &snapshot)?;
+            if let Some(group_id) = note.group_id {
+                if state.db.get_note_group(group_id)?.is_none() {
+                    note.group_id = current_group;
+                }
+            }
+            super::notes::save_note(&state, revision.item_id, note)?;
+        }
+        other => revisions::ensure_supported(other)?,
+    }
+    Ok(json!({ "success": true }))
+}
+
+/// 条目当前版本的明文快照
+fn current_snapshot(state: &AppState, item_type: ItemType, item_id: i64) -> Result<RevisionSnapshot, String> {
+    let cipher = state.field_cipher()?;
+    match item_type {
+        ItemType::Login => {
+            let mut password = state.db.get_password(item_id)?.ok_or("Password not found")?;
+            cipher.decrypt_password(&mut password);
+            Ok(revisions::password_snapshot(&password))
+        }
+        ItemType::SecureNote => {
+            let mut note = state.db.get_note(item_id)?.ok_or("Note not found")?;
+            cipher.decrypt_note(&mut note);
+            Ok(revisions::note_snapshot(&note))
+        }
+        other => revisions::ensure_supported(other).map(|()| RevisionSnapshot::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Password, SecretString};
+
+    #[tokio::test]
+    async fn test_revision_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(get_revisions(app.state(), ItemType::Login, 1).await);
+        assert_vault_locked(diff_revision(app.state(), 1, None).await);
+        assert_vault_locked(restore_revision(app.state(), 1).await);
+    }
+
+    #[tokio::test]
+    async fn test_password_revisions_diff_and_restore() {
+        use crate::commands::passwords::{add_password, get_password, update_password};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let original: Password = serde_json::from_value(json!({
+            "title": "GitHub",
+            "username": "alice",
+            "password": "s3cret"
+        }))
+        .unwrap();
+        let id = add_password(app.state(), original.clone()).await.unwrap()["id"].as_i64().unwrap();
+
+        // 明文未变化（仅重新加密）不产生修订，也不产生密码历史
+        update_password(app.state(), id, original.clone()).await.unwrap();
+        assert!(get_revisions(app.state(), ItemType::Login, id).await.unwrap().is_empty());
+        assert!(state.db.get_password_history(id).unwrap().is_empty());
+
+        let mut edited = original.clone();
+        edited.password = Some("n3w-secret".into());
+        edited.notes = Some("rotated".to_string());
+        update_password(app.state(), id, edited).await.unwrap();
+        let listed = get_revisions(app.state(), ItemType::Login, id).await.unwrap();
+        assert_eq!(listed[0].changed_fields, vec!["notes".to_string(), "password".to_string()]);
+        assert_eq!(state.db.get_password_history(id).unwrap().len(), 1);
+
+        let revision_id = listed[0].id.unwrap();
+        let changes = diff_revision(app.state(), revision_id, None).await.unwrap();
+        assert_eq!(changes[1].before.as_ref().map(SecretString::expose), Some("s3cret"));
+        assert_eq!(changes[1].after.as_ref().map(SecretString::expose), Some("n3w-secret"));
+
+        restore_revision(app.state(), revision_id).await.unwrap();
+        let restored = get_password(app.state(), id).await.unwrap().unwrap();
+        assert_eq!(restored.password.as_ref().map(SecretString::expose), Some("s3cret"));
+        assert_eq!(restored.notes, None);
+        assert_eq!(get_revisions(app.state(), ItemType::Login, id).await.unwrap().len(), 2);
+        assert!(diff_revision(app.state(), revision_id, None).await.unwrap().is_empty());
+    }
+}
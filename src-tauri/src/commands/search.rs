@@ -0,0 +1,169 @@
+//! 全文搜索与结构化查询 Commands
+
+use crate::models::{ItemType, QueryResult, SearchHit};
+use crate::services::query;
+use crate::services::search_index::{self, SearchIndex};
+use crate::AppState;
+use tauri::State;
+
+/// 默认返回结果数
+const DEFAULT_LIMIT: usize = 50;
+/// 最多返回结果数
+const MAX_LIMIT: usize = 500;
+
+/// 在索引上执行查询；索引尚未建立（如启动后首次搜索）时先建立
+fn with_index<T>(state: &AppState, f: impl FnOnce(&SearchIndex) -> T) -> Result<T, String> {
+    let mut index = state.search_index.lock().map_err(|e| e.to_string())?;
+    if !index.is_built() {
+        *index = search_index::build(&state.db, &*state.field_cipher()?)?;
+        log::info!("Search index built with {} items", index.len());
+    }
+    Ok(f(&index))
+}
+
+/// 从数据库重新建立索引
+pub(crate) fn rebuild_search_index(state: &AppState) -> Result<(), String> {
+    let index = search_index::build(&state.db, &*state.field_cipher()?)?;
+    log::info!("Search index built with {} items", index.len());
+    *state.search_index.lock().map_err(|e| e.to_string())? = index;
+    Ok(())
+}
+
+/// 清空索引，下次搜索时重新建立；用于导入、删除分组等批量修改之后
+pub(crate) fn invalidate_search_index(state: &AppState) {
+    if let Ok(mut index) = state.search_index.lock() {
+        index.clear();
+    }
+}
+
+/// 条目新增、修改或删除后更新索引；更新失败时清空索引，下次搜索时重建
+pub(crate) fn refresh_search_index(state: &AppState, item_type: ItemType, id: i64) {
+    let result = (|| -> Result<(), String> {
+        let mut index = state.search_index.lock().map_err(|e| e.to_string())?;
+        if !index.is_built() {
+            return Ok(());
+        }
+        match search_index::load_document(&state.db, &*state.field_cipher()?, item_type, id)? {
+            Some(document) => index.upsert(document),
+            None => index.remove(item_type, id),
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        log::warn!("Failed to update search index for {} {}: {}", item_type.as_str(), id, e);
+        invalidate_search_index(state);
+    }
+}
+
+/// 在密码条目与笔记的解密内容中全文搜索，按相关度排序并返回高亮摘要
+#[tauri::command]
+pub async fn search_vault(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    state.ensure_unlocked()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    with_index(&state, |index| index.search(&query, limit))
+}
+
+/// 按结构化查询（如 `tag:work group:"Infra" is:favorite updated:<30d`）统一搜索密码条目与笔记
+#[tauri::command]
+pub async fn query_vault(state: State<'_, AppState>, query: String) -> Result<Vec<QueryResult>, String> {
+    state.ensure_unlocked()?;
+    let parsed = query::parse(&query)?;
+    query::run(&state.db, &*state.field_cipher()?, &parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_search_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(search_vault(app.state(), "github".to_string(), None).await);
+        assert_vault_locked(query_vault(app.state(), "is:favorite".to_string()).await);
+    }
+
+    #[tokio::test]
+    async fn test_search_index_follows_note_changes() {
+        use crate::commands::notes::{add_note, delete_note, update_note};
+        use crate::models::SecureRecord;
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let note: SecureRecord =
+            serde_json::from_value(json!({ "title": "网银", "content": "U盾密码在抽屉里" })).unwrap();
+        let id = add_note(app.state(), note.clone()).await.unwrap()["id"].as_i64().unwrap();
+
+        // 内容以密文存储，索引按明文匹配
+        let hits = search_vault(app.state(), "抽屉".to_string(), None).await.unwrap();
+        assert_eq!((hits[0].item_type, hits[0].id), (ItemType::SecureNote, id));
+
+        let mut edited = note.clone();
+        edited.content = Some("已挪到保险柜".into());
+        update_note(app.state(), id, edited).await.unwrap();
+        assert!(search_vault(app.state(), "抽屉".to_string(), None).await.unwrap().is_empty());
+        assert_eq!(search_vault(app.state(), "保险柜".to_string(), None).await.unwrap().len(), 1);
+
+        delete_note(app.state(), id).await.unwrap();
+        assert!(search_vault(app.state(), "保险柜".to_string(), None).await.unwrap().is_empty());
+        crate::commands::trash::restore_trash_item(app.state(), ItemType::SecureNote, id).await.unwrap();
+        assert_eq!(search_vault(app.state(), "保险柜".to_string(), None).await.unwrap().len(), 1);
+
+        app.state::<AppState>().set_encryption(None);
+        assert!(!app.state::<AppState>().search_index.lock().unwrap().is_built());
+    }
+
+    #[tokio::test]
+    async fn test_query_vault_returns_passwords_and_notes_with_group_paths() {
+        use crate::models::{Group, Password, SecureRecord};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let group = |name: &str, parent_id: Option<i64>| Group {
+            id: None,
+            name: name.to_string(),
+            parent_id,
+            icon: None,
+            color: None,
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let infra = state.db.add_group(&group("Infra", None)).unwrap();
+        let ci = state.db.add_group(&group("CI", Some(infra))).unwrap();
+        let password: Password = serde_json::from_value(json!({
+            "title": "GitHub",
+            "url": "https://github.com",
+            "tags": "work,ops",
+            "favorite": true,
+            "group_id": ci
+        }))
+        .unwrap();
+        crate::commands::passwords::add_password(app.state(), password).await.unwrap();
+        let note: SecureRecord =
+            serde_json::from_value(json!({ "title": "Runbook", "content": "github 部署步骤" })).unwrap();
+        crate::commands::notes::add_note(app.state(), note).await.unwrap();
+
+        let results = query_vault(app.state(), r#"tag:work group:"Infra" url:github.com is:favorite updated:<30d"#.to_string())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].group_path, vec!["Infra".to_string(), "CI".to_string()]);
+
+        let results = query_vault(app.state(), "github".to_string()).await.unwrap();
+        let types: Vec<ItemType> = results.iter().map(|r| r.item_type).collect();
+        assert_eq!(types, vec![ItemType::Login, ItemType::SecureNote]);
+        assert!(query_vault(app.state(), "is:unknown".to_string()).await.is_err());
+    }
+}
@@ -2,24 +2,102 @@
 //!
 //! 处理主密码验证、登录、锁定及会话管理
 
-use crate::models::UserSetting;
-use crate::{AppState, UnlockThrottleState};
+use crate::models::{FieldEncryptionPolicy, SecretString, UnlockThrottleState, UserSetting};
+use crate::services::field_encryption;
+use crate::services::password_hashing::{self, HashParams, Verification};
+use crate::services::trash;
+use crate::services::vault::{self, KdfParams};
+use crate::AppState;
 use serde_json::{json, Value};
-use sha2::{Digest, Sha256};
-use std::time::{Duration, Instant};
-use tauri::State;
+use std::sync::atomic::{AtomicBool, Ordering};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 const UNLOCK_FAILURE_LIMIT: u32 = 5;
 const UNLOCK_COOLDOWN_SECONDS: u64 = 30;
+const UNLOCK_COOLDOWN_MAX_SECONDS: u64 = 60 * 60;
+const DEFAULT_UNLOCK_FAILURE_LIST: u32 = 20;
+const UNLOCK_METHOD_MASTER_PASSWORD: &str = "master_password";
+const UNLOCK_METHOD_RECOVERY_KEY: &str = "recovery_key";
 const DEFAULT_AUTO_LOCK_MINUTES: u64 = 5;
 const MIN_MASTER_PASSWORD_LEN: usize = 6;
+/// 轮换数据密钥或改写密钥包装前等待其他命令归还数据密钥的最长时间
+const KEY_ROTATION_WAIT: Duration = Duration::from_secs(10);
 
-/// 计算密码哈希
-fn hash_password(password: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(password.as_bytes());
-    let result = hasher.finalize();
-    hex::encode(result)
+const AUTO_LOCK_CHECK_INTERVAL_SECS: u64 = 15;
+
+static CIPHER_UPGRADE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 后台将旧版 CBC 密文升级为 AEAD 格式
+///
+/// 保险库解锁后调用；同一时间只运行一个任务，已完成升级时直接返回。
+pub fn start_cipher_upgrade(app: AppHandle) {
+    if CIPHER_UPGRADE_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        // 逐条比较原密文后才写入，不会覆盖轮换后的数据，无需持有密钥租约（升级耗时较长，持有会阻塞修改主密码）
+        let data_key = state.encryption.lock().ok().and_then(|guard| guard.clone());
+        if let Some(data_key) = data_key {
+            match vault::upgrade_ciphertexts(&state.db, &data_key) {
+                Ok(report) => {
+                    if report.upgraded > 0 || report.failed > 0 {
+                        log::info!(
+                            "Cipher upgrade finished: {} upgraded, {} failed",
+                            report.upgraded,
+                            report.failed
+                        );
+                    }
+                    if report.failed == 0 {
+                        // 仅当密钥未在期间被替换时收紧为只接受 AEAD 格式
+                        if let Ok(mut guard) = state.encryption.lock() {
+                            if guard.as_ref().map(|k| k.key_bytes()) == Some(data_key.key_bytes()) {
+                                *guard = Some(data_key.without_legacy());
+                            }
+                        }
+                    }
+                }
+                Err(e) => log::error!("Cipher upgrade failed: {}", e),
+            }
+        }
+        CIPHER_UPGRADE_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// 以指定成本计算主密码哈希，并用同样成本的 KDF 重新包装数据密钥
+fn hash_and_wrap(
+    password: &str,
+    data_key: &crate::services::encryption::EncryptionService,
+    params: &HashParams,
+) -> Result<(String, crate::models::VaultKey), String> {
+    let hash = password_hashing::hash(password, params)?;
+    let master_key = vault::wrap_with_password(data_key, password, &KdfParams::argon2id(params))?;
+    Ok((hash, master_key))
+}
+
+/// 以新成本重写主密码哈希与主密码包装，保留提示与锁屏设置
+fn rehash_master_password(
+    state: &State<'_, AppState>,
+    password: &str,
+    data_key: &crate::services::encryption::EncryptionService,
+    params: &HashParams,
+) -> Result<(), String> {
+    let (_has_master, hint, require_password) = state.db.get_master_password_config()?;
+    let (hash, master_key) = hash_and_wrap(password, data_key, params)?;
+    state
+        .db
+        .set_master_password_with_key(&hash, hint.as_deref(), require_password, &[master_key])
+}
+
+/// 生成新的恢复密钥，并返回它对数据密钥的包装
+fn new_recovery_key(
+    data_key: &crate::services::encryption::EncryptionService,
+) -> Result<(SecretString, crate::models::VaultKey), String> {
+    let recovery_key = vault::generate_recovery_key();
+    let wrapped = vault::wrap_with_recovery_key(data_key, recovery_key.expose())?;
+    Ok((recovery_key, wrapped))
 }
 
 fn validate_master_password(password: &str) -> Result<(), String> {
@@ -38,7 +116,7 @@ fn read_auto_lock_minutes(state: &State<'_, AppState>) -> Result<u64, String> {
 
     let maybe_seconds = timeout_setting.and_then(|setting| setting.value.parse::<u64>().ok());
     let minutes = maybe_seconds
-        .map(|seconds| ((seconds + 59) / 60).max(1))
+        .map(|seconds| seconds.div_ceil(60).max(1))
         .unwrap_or(DEFAULT_AUTO_LOCK_MINUTES);
     Ok(minutes)
 }
@@ -55,6 +133,7 @@ fn read_last_unlock_at(state: &State<'_, AppState>) -> Result<Option<String>, St
 }
 
 fn touch_last_unlock_at(state: &State<'_, AppState>) -> Result<(), String> {
+    state.touch_activity();
     let now = chrono::Utc::now().to_rfc3339();
     let setting = UserSetting {
         id: None,
@@ -69,25 +148,91 @@ fn touch_last_unlock_at(state: &State<'_, AppState>) -> Result<(), String> {
     state.db.set_user_setting(&setting).map_err(|e| e.to_string())
 }
 
-fn active_cooldown_seconds(throttle: &mut UnlockThrottleState, now: Instant) -> Option<u64> {
+fn active_cooldown_seconds(throttle: &UnlockThrottleState, now: DateTime<Utc>) -> Option<u64> {
     let until = throttle.cooldown_until?;
     if now >= until {
-        throttle.failed_attempts = 0;
-        throttle.cooldown_until = None;
         return None;
     }
-    Some(until.duration_since(now).as_secs().max(1))
+    Some((until - now).num_seconds().max(1) as u64)
+}
+
+/// 第 `failed_attempts` 次连续失败对应的冷却时长：达到上限后每次翻倍，封顶一小时
+fn cooldown_for_failures(failed_attempts: u32) -> Option<u64> {
+    let over = failed_attempts.checked_sub(UNLOCK_FAILURE_LIMIT)?;
+    let factor = 1u64.checked_shl(over.min(32)).unwrap_or(u64::MAX);
+    Some(UNLOCK_COOLDOWN_SECONDS.saturating_mul(factor).min(UNLOCK_COOLDOWN_MAX_SECONDS))
 }
 
-fn register_unlock_failure(throttle: &mut UnlockThrottleState, now: Instant) -> Option<u64> {
+fn register_unlock_failure(throttle: &mut UnlockThrottleState, now: DateTime<Utc>) -> Option<u64> {
     throttle.failed_attempts += 1;
-    if throttle.failed_attempts < UNLOCK_FAILURE_LIMIT {
-        return None;
+    let seconds = cooldown_for_failures(throttle.failed_attempts)?;
+    throttle.cooldown_until = Some(now + chrono::Duration::seconds(seconds as i64));
+    Some(seconds)
+}
+
+/// 修改节流状态并写回数据库
+fn update_unlock_throttle<T>(
+    state: &AppState,
+    f: impl FnOnce(&mut UnlockThrottleState) -> T,
+) -> Result<T, String> {
+    let mut throttle = state
+        .unlock_throttle
+        .lock()
+        .map_err(|_| "Failed to lock throttle state".to_string())?;
+    let result = f(&mut throttle);
+    state.db.save_unlock_throttle(&throttle)?;
+    Ok(result)
+}
+
+/// 是否应因无操作而自动锁定
+fn should_auto_lock(require_password: bool, ui_locked: bool, idle: Duration, auto_lock_minutes: u64) -> bool {
+    require_password && !ui_locked && idle >= Duration::from_secs(auto_lock_minutes * 60)
+}
+
+/// 锁定保险库：锁定 UI，并在数据密钥只能由主密码解包时丢弃内存中的密钥
+fn lock_vault(state: &AppState) -> Result<(), String> {
+    {
+        let mut ui_locked = state.ui_locked.lock().map_err(|_| "Failed to lock state".to_string())?;
+        *ui_locked = true;
+    }
+    // 无设备级包装时数据密钥只能由主密码解包，锁定即丢弃（释放时清零）
+    if state.db.get_vault_key(vault::KEY_KIND_DEVICE)?.is_none() {
+        state.set_encryption(None);
     }
+    Ok(())
+}
 
-    let cooldown = Duration::from_secs(UNLOCK_COOLDOWN_SECONDS);
-    throttle.cooldown_until = Some(now + cooldown);
-    Some(cooldown.as_secs())
+/// 启动自动锁定计时任务
+///
+/// 周期检查最近一次活动时间，超过自动锁定时长后锁定保险库并发出 `vault-locked` 事件。
+pub fn start_auto_lock_timer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(AUTO_LOCK_CHECK_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            let state = app.state::<AppState>();
+            let (_has_master, _hint, require_password) = match state.db.get_master_password_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    log::warn!("Auto-lock check failed: {}", e);
+                    continue;
+                }
+            };
+            let ui_locked = state.ui_locked.lock().map(|l| *l).unwrap_or(true);
+            let idle = state.last_activity.lock().map(|t| t.elapsed()).unwrap_or_default();
+            let minutes = read_auto_lock_minutes(&state).unwrap_or(DEFAULT_AUTO_LOCK_MINUTES);
+            if !should_auto_lock(require_password, ui_locked, idle, minutes) {
+                continue;
+            }
+            match lock_vault(&state) {
+                Ok(()) => {
+                    log::info!("Vault auto-locked after {} minutes of inactivity", minutes);
+                    app.emit("vault-locked", json!({ "reason": "inactivity" })).ok();
+                }
+                Err(e) => log::error!("Auto-lock failed: {}", e),
+            }
+        }
+    });
 }
 
 fn reset_unlock_throttle(throttle: &mut UnlockThrottleState) {
@@ -97,11 +242,10 @@ fn reset_unlock_throttle(throttle: &mut UnlockThrottleState) {
 
 fn verify_current_password(stored_hash_opt: Option<String>, input_password: &str) -> Result<(), &'static str> {
     if let Some(stored_hash) = stored_hash_opt {
-        let input_hash = hash_password(input_password);
-        if stored_hash == input_hash {
-            Ok(())
-        } else {
-            Err("wrong_password")
+        let target = password_hashing::target_params(Some(&stored_hash));
+        match password_hashing::verify(&stored_hash, input_password, &target) {
+            Verification::Valid { .. } => Ok(()),
+            Verification::Invalid => Err("wrong_password"),
         }
     } else {
         Err("not_set")
@@ -121,13 +265,19 @@ pub async fn security_get_state(state: State<'_, AppState>) -> Result<Value, Str
 
     let auto_lock = read_auto_lock_minutes(&state)?;
     let last_unlock_at = read_last_unlock_at(&state)?;
+    let hash_params = state
+        .db
+        .get_master_password_hash()?
+        .and_then(|stored| password_hashing::params_of(&stored));
 
     let payload = json!({
         "hasMasterPassword": has_master,
         "requireMasterPassword": require_password,
         "hint": hint,
         "autoLockMinutes": auto_lock,
-        "lastUnlockAt": last_unlock_at
+        "lastUnlockAt": last_unlock_at,
+        "passwordHashParams": hash_params,
+        "hasRecoveryKey": state.db.get_vault_key(vault::KEY_KIND_RECOVERY)?.is_some()
     });
 
     Ok(payload)
@@ -137,16 +287,22 @@ pub async fn security_get_state(state: State<'_, AppState>) -> Result<Value, Str
 #[tauri::command]
 pub async fn security_set_master_password(
     state: State<'_, AppState>,
-    password: String,
+    password: SecretString,
     hint: Option<String>,
 ) -> Result<Value, String> {
     if state.db.has_master_password().map_err(|e| e.to_string())? {
         return Ok(json!({ "success": false, "error": "已经设置了主密码" }));
     }
 
-    validate_master_password(&password)?;
-    let hash = hash_password(&password);
-    state.db.set_master_password(&hash, hint.as_deref()).map_err(|e| e.to_string())?;
+    validate_master_password(password.expose())?;
+    let data_key = state.encryption_service()?;
+    let (hash, master_key) = hash_and_wrap(password.expose(), &data_key, &HashParams::default())?;
+    let (recovery_key, recovery_wrap) = new_recovery_key(&data_key)?;
+    state
+        .db
+        .set_master_password_with_key(&hash, hint.as_deref(), true, &[master_key, recovery_wrap])
+        .map_err(|e| e.to_string())?;
+    drop(data_key);
 
     // 自动解锁 UI（不再创建 session）
     {
@@ -155,77 +311,157 @@ pub async fn security_set_master_password(
     }
     touch_last_unlock_at(&state)?;
 
-    {
-        let mut throttle = state
-            .unlock_throttle
-            .lock()
-            .map_err(|_| "Failed to lock throttle state".to_string())?;
-        reset_unlock_throttle(&mut throttle);
-    }
+    update_unlock_throttle(&state, reset_unlock_throttle)?;
 
-    // Return new state
+    // Return new state；恢复密钥只在此返回一次
     let new_state = security_get_state(state).await?;
-    Ok(json!({ "success": true, "state": new_state }))
+    Ok(json!({ "success": true, "state": new_state, "recoveryKey": recovery_key }))
 }
 
-/// 验证主密码 (登录)
-#[tauri::command]
-pub async fn security_verify_master_password(
-    state: State<'_, AppState>,
-    password: String,
-) -> Result<Value, String> {
-    let now = Instant::now();
-    {
-        let mut throttle = state
-            .unlock_throttle
-            .lock()
-            .map_err(|_| "Failed to lock throttle state".to_string())?;
-        if let Some(remaining) = active_cooldown_seconds(&mut throttle, now) {
-            return Ok(json!({
+/// 预占一次解锁尝试：处于冷却期时返回拒绝响应，否则先按失败计数
+///
+/// 冷却检查与计数在同一把锁内完成，并发请求无法在慢速校验前一起通过检查；
+/// 解锁成功时由 [`finish_unlock`] 重置计数。
+fn reserve_unlock_attempt(state: &AppState, now: DateTime<Utc>) -> Result<Option<Value>, String> {
+    update_unlock_throttle(state, |throttle| {
+        if let Some(remaining) = active_cooldown_seconds(throttle, now) {
+            return Some(json!({
                 "success": false,
                 "error": format!("尝试次数过多，请 {} 秒后重试", remaining),
                 "errorCode": "UNLOCK_COOLDOWN",
                 "cooldownSeconds": remaining
             }));
         }
+        register_unlock_failure(throttle, now);
+        None
+    })
+}
+
+/// 记录一次解锁失败（计数已在预占时增加），返回失败响应（达到阈值时附带冷却时间）
+fn unlock_failure_response(
+    state: &State<'_, AppState>,
+    method: &str,
+    error: &str,
+    now: DateTime<Utc>,
+) -> Result<Value, String> {
+    state.db.record_unlock_attempt(false, method)?;
+    let cooldown_seconds = {
+        let throttle = state
+            .unlock_throttle
+            .lock()
+            .map_err(|_| "Failed to lock throttle state".to_string())?;
+        active_cooldown_seconds(&throttle, now)
+    };
+    Ok(match cooldown_seconds {
+        Some(remaining) => json!({
+            "success": false,
+            "error": format!("{}，已冷却 {} 秒", error, remaining),
+            "errorCode": "UNLOCK_COOLDOWN",
+            "cooldownSeconds": remaining
+        }),
+        None => json!({ "success": false, "error": error }),
+    })
+}
+
+/// 校验当前主密码（修改主密码、撤销恢复密钥等操作前）
+///
+/// 与解锁共用节流：冷却期内直接拒绝，失败计入解锁失败记录，成功时重置计数。
+/// 返回 `Some(response)` 表示未通过，调用方应直接返回该响应。
+fn check_current_password(
+    state: &State<'_, AppState>,
+    password: &str,
+    wrong_error: &str,
+) -> Result<Option<Value>, String> {
+    let now = Utc::now();
+    let Some(stored_hash) = state.db.get_master_password_hash()? else {
+        return Ok(Some(json!({ "success": false, "error": "尚未设置主密码" })));
+    };
+    if let Some(response) = reserve_unlock_attempt(state, now)? {
+        return Ok(Some(response));
+    }
+    match verify_current_password(Some(stored_hash), password) {
+        Ok(()) => {
+            update_unlock_throttle(state, reset_unlock_throttle)?;
+            Ok(None)
+        }
+        Err(_) => unlock_failure_response(state, UNLOCK_METHOD_MASTER_PASSWORD, wrong_error, now).map(Some),
+    }
+}
+
+/// 解锁成功后的共同处理：安装数据密钥、解除 UI 锁定并重置节流
+fn finish_unlock(
+    app: AppHandle,
+    state: &State<'_, AppState>,
+    data_key: crate::services::encryption::EncryptionService,
+    method: &str,
+) -> Result<(), String> {
+    state.set_encryption(Some(data_key));
+    start_cipher_upgrade(app);
+    if let Err(e) = trash::purge_expired(&state.db) {
+        log::warn!("清理回收站过期条目失败: {}", e);
     }
+    if let Err(e) = super::search::rebuild_search_index(state) {
+        log::warn!("建立搜索索引失败: {}", e);
+    }
+
+    // 只更新 UI 锁定状态，不再创建 session
+    {
+        let mut ui_locked = state.ui_locked.lock().map_err(|_| "Failed to lock state".to_string())?;
+        *ui_locked = false;
+    }
+    touch_last_unlock_at(state)?;
+    update_unlock_throttle(state, reset_unlock_throttle)?;
+    state.db.record_unlock_attempt(true, method)
+}
 
+/// 验证主密码 (登录)
+#[tauri::command]
+pub async fn security_verify_master_password(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    password: SecretString,
+) -> Result<Value, String> {
+    let now = Utc::now();
     let db_hash_opt = state.db.get_master_password_hash().map_err(|e| e.to_string())?;
 
     if let Some(stored_hash) = db_hash_opt {
-        let input_hash = hash_password(&password);
-        if stored_hash == input_hash {
-            // 只更新 UI 锁定状态，不再创建 session
-            {
-                let mut ui_locked = state.ui_locked.lock().map_err(|_| "Failed to lock state".to_string())?;
-                *ui_locked = false;
-            }
-            touch_last_unlock_at(&state)?;
-            {
-                let mut throttle = state
-                    .unlock_throttle
-                    .lock()
-                    .map_err(|_| "Failed to lock throttle state".to_string())?;
-                reset_unlock_throttle(&mut throttle);
+        if let Some(response) = reserve_unlock_attempt(&state, now)? {
+            return Ok(response);
+        }
+        let target = password_hashing::target_params(Some(&stored_hash));
+        if let Verification::Valid { needs_upgrade } =
+            password_hashing::verify(&stored_hash, password.expose(), &target)
+        {
+            // 主密码正确：解包数据密钥
+            let kdf_params = KdfParams::argon2id(&target);
+            let data_key = match vault::unlock_with_password(&state.db, password.expose(), &kdf_params) {
+                Ok(key) => key,
+                Err(e) => {
+                    log::error!("Failed to unlock vault: {}", e);
+                    return Ok(json!({ "success": false, "error": format!("解锁保险库失败: {}", e) }));
+                }
+            };
+
+            // 旧版 SHA-256 或低成本哈希：自动升级（失败不影响本次解锁）
+            if needs_upgrade {
+                match rehash_master_password(&state, password.expose(), &data_key, &target) {
+                    Ok(()) => log::info!("Master password hash upgraded to Argon2id"),
+                    Err(e) => log::warn!("Failed to upgrade master password hash: {}", e),
+                }
             }
+            // 上次修改主密码被中断：继续完成数据密钥轮换（失败时保持旧状态，下次解锁重试）
+            let data_key = match vault::resume_rekey(&state.db, &data_key) {
+                Ok(new_key) => new_key.unwrap_or(data_key),
+                Err(e) => {
+                    log::error!("Failed to resume vault rekey: {}", e);
+                    data_key
+                }
+            };
+            finish_unlock(app, &state, data_key, UNLOCK_METHOD_MASTER_PASSWORD)?;
             let current_state = security_get_state(state).await?;
             Ok(json!({ "success": true, "state": current_state }))
         } else {
-            let mut throttle = state
-                .unlock_throttle
-                .lock()
-                .map_err(|_| "Failed to lock throttle state".to_string())?;
-            let cooldown_seconds = register_unlock_failure(&mut throttle, now);
-            if let Some(remaining) = cooldown_seconds {
-                Ok(json!({
-                    "success": false,
-                    "error": format!("密码错误，已冷却 {} 秒", remaining),
-                    "errorCode": "UNLOCK_COOLDOWN",
-                    "cooldownSeconds": remaining
-                }))
-            } else {
-                Ok(json!({ "success": false, "error": "密码错误" }))
-            }
+            unlock_failure_response(&state, UNLOCK_METHOD_MASTER_PASSWORD, "密码错误", now)
         }
     } else {
         Ok(json!({ "success": false, "error": "尚未设置主密码" }))
@@ -235,41 +471,45 @@ pub async fn security_verify_master_password(
 #[tauri::command]
 pub async fn security_update_master_password(
     state: State<'_, AppState>,
-    current_password: String,
-    new_password: String,
+    current_password: SecretString,
+    new_password: SecretString,
     hint: Option<String>,
 ) -> Result<Value, String> {
     // 1. 验证当前密码
-    let db_hash_opt = state.db.get_master_password_hash().map_err(|e| e.to_string())?;
-    match verify_current_password(db_hash_opt, &current_password) {
-        Ok(()) => {}
-        Err("wrong_password") => {
-            return Ok(json!({
-                "success": false,
-                "error": "当前密码错误"
-            }))
-        }
-        Err(_) => {
-            return Ok(json!({
-                "success": false,
-                "error": "主密码未设置"
-            }))
-        }
+    if let Some(response) = check_current_password(&state, current_password.expose(), "当前密码错误")? {
+        return Ok(response);
     }
 
-    validate_master_password(&new_password)?;
-
-    let (_has_master, _old_hint, require_password) = state
-        .db
-        .get_master_password_config()
-        .map_err(|e| e.to_string())?;
+    validate_master_password(new_password.expose())?;
 
-    // 2. 更新密码
-    let new_hash = hash_password(&new_password);
-    state
+    // 2. 轮换数据密钥：重新加密全部数据，并与新密钥包装、密码哈希在同一事务中提交
+    let params = password_hashing::target_params(state.db.get_master_password_hash()?.as_deref());
+    let kdf_params = KdfParams::argon2id(&params);
+    let data_key = vault::unlock_with_password(&state.db, current_password.expose(), &kdf_params)?;
+    let new_hash = password_hashing::hash(new_password.expose(), &params)?;
+    // 旧恢复密钥随数据密钥失效；原先设置过恢复密钥时签发新的，由前端在提示成功前展示
+    let recovery_key = state
         .db
-        .set_master_password_with_require(&new_hash, hint.as_deref(), require_password)
-        .map_err(|e| e.to_string())?;
+        .get_vault_key(vault::KEY_KIND_RECOVERY)?
+        .map(|_| vault::generate_recovery_key());
+    {
+        // 等待已取出数据密钥的命令写完，并在轮换期间拒绝新的取用，避免用旧密钥写入
+        let _rotation = state.key_leases.begin_rotation(KEY_ROTATION_WAIT)?;
+        let mut encryption = state
+            .encryption
+            .lock()
+            .map_err(|_| "Failed to lock encryption state".to_string())?;
+        let new_key = vault::rekey_vault(
+            &state.db,
+            &data_key,
+            new_password.expose(),
+            &new_hash,
+            hint.as_deref(),
+            &kdf_params,
+            recovery_key.as_ref().map(SecretString::expose),
+        )?;
+        *encryption = Some(new_key);
+    }
 
     log::info!("Master password updated successfully");
 
@@ -278,36 +518,29 @@ pub async fn security_update_master_password(
 
     Ok(json!({
         "success": true,
-        "state": new_state
+        "state": new_state,
+        "recoveryKey": recovery_key
     }))
 }
 
 #[tauri::command]
 pub async fn security_clear_master_password(
     state: State<'_, AppState>,
-    current_password: String,
+    current_password: SecretString,
 ) -> Result<Value, String> {
     // 1. 验证当前密码
-    let db_hash_opt = state.db.get_master_password_hash().map_err(|e| e.to_string())?;
-
-    match verify_current_password(db_hash_opt, &current_password) {
-        Ok(()) => {}
-        Err("wrong_password") => {
-            return Ok(json!({
-                "success": false,
-                "error": "当前主密码错误"
-            }))
-        }
-        Err(_) => {
-            return Ok(json!({
-                "success": false,
-                "error": "尚未设置主密码"
-            }))
-        }
+    if let Some(response) = check_current_password(&state, current_password.expose(), "当前主密码错误")? {
+        return Ok(response);
     }
 
-    // 2. 清除主密码
-    state.db.clear_master_password().map_err(|e| e.to_string())?;
+    // 2. 清除主密码，数据密钥改为设备级包装
+    let data_key = vault::unlock_with_password(&state.db, current_password.expose(), &KdfParams::default())?;
+    let device_key = vault::wrap_with_device_key(&data_key)?;
+    state
+        .db
+        .clear_master_password_with_key(&device_key)
+        .map_err(|e| e.to_string())?;
+    state.set_encryption(Some(data_key));
     
     // 3. 解锁 UI
     {
@@ -330,26 +563,39 @@ pub async fn security_clear_master_password(
 pub async fn security_set_require_master_password(
     state: State<'_, AppState>,
     require: bool,
-    password: Option<String>,
+    password: Option<SecretString>,
     hint: Option<String>,
-    current_password: Option<String>,
+    current_password: Option<SecretString>,
 ) -> Result<Value, String> {
+    let mut recovery_key = None;
     if require {
         // 已设置主密码时，仅切换“是否要求解锁”
         if state.db.has_master_password().map_err(|e| e.to_string())? {
-            state
-                .db
-                .set_require_master_password(true)
-                .map_err(|e| e.to_string())?;
+            if state.db.get_vault_key(vault::KEY_KIND_MASTER)?.is_some() {
+                state
+                    .db
+                    .set_require_master_password_with_key(true, None)
+                    .map_err(|e| e.to_string())?;
+            } else {
+                // 旧版保险库尚无主密码包装：保留设备级包装，下次解锁时补建并删除
+                state
+                    .db
+                    .set_require_master_password(true)
+                    .map_err(|e| e.to_string())?;
+            }
         } else {
             let pwd = password.ok_or("开启主密码需要提供密码".to_string())?;
-            validate_master_password(&pwd)?;
-            let hash = hash_password(&pwd);
+            validate_master_password(pwd.expose())?;
+            let data_key = state.encryption_service()?;
+            let (hash, master_key) = hash_and_wrap(pwd.expose(), &data_key, &HashParams::default())?;
+            let (new_key, recovery_wrap) = new_recovery_key(&data_key)?;
             state
                 .db
-                .set_master_password_with_require(&hash, hint.as_deref(), true)
+                .set_master_password_with_key(&hash, hint.as_deref(), true, &[master_key, recovery_wrap])
                 .map_err(|e| e.to_string())?;
+            recovery_key = Some(new_key);
         }
+        state.set_encryption(None);
 
         // 立即锁定 UI
         {
@@ -365,30 +611,18 @@ pub async fn security_set_require_master_password(
         let current_pwd = current_password.ok_or("关闭主密码需要验证当前密码".to_string())?;
 
         // 验证密码
-        let db_hash_opt = state.db.get_master_password_hash()
-            .map_err(|e| e.to_string())?;
-
-        match verify_current_password(db_hash_opt, &current_pwd) {
-            Ok(()) => {}
-            Err("wrong_password") => {
-                return Ok(json!({
-                    "success": false,
-                    "error": "密码错误"
-                }))
-            }
-            Err(_) => {
-                return Ok(json!({
-                    "success": false,
-                    "error": "主密码未设置"
-                }))
-            }
+        if let Some(response) = check_current_password(&state, current_pwd.expose(), "密码错误")? {
+            return Ok(response);
         }
-        
-        // 仅关闭 require_password，不清除主密码哈希
+
+        // 仅关闭 require_password，不清除主密码哈希；数据密钥补充设备级包装
+        let data_key = vault::unlock_with_password(&state.db, current_pwd.expose(), &KdfParams::default())?;
+        let device_key = vault::wrap_with_device_key(&data_key)?;
         state
             .db
-            .set_require_master_password(false)
+            .set_require_master_password_with_key(false, Some(&device_key))
             .map_err(|e| e.to_string())?;
+        state.set_encryption(Some(data_key));
 
         // 立即解锁 UI
         {
@@ -405,16 +639,193 @@ pub async fn security_set_require_master_password(
 
     Ok(json!({
         "success": true,
-        "state": new_state
+        "state": new_state,
+        "recoveryKey": recovery_key
     }))
 }
 
+/// 使用恢复密钥解锁，并强制设置新的主密码
+///
+/// 恢复密钥只能使用一次：成功后签发新的恢复密钥并随响应返回。
+#[tauri::command]
+pub async fn security_unlock_with_recovery_key(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    recovery_key: SecretString,
+    new_password: SecretString,
+    hint: Option<String>,
+) -> Result<Value, String> {
+    let now = Utc::now();
+    if !state.db.has_master_password()? || state.db.get_vault_key(vault::KEY_KIND_RECOVERY)?.is_none() {
+        return Ok(json!({ "success": false, "error": "未设置恢复密钥" }));
+    }
+    if let Err(e) = validate_master_password(new_password.expose()) {
+        return Ok(json!({ "success": false, "error": e }));
+    }
+    if let Some(response) = reserve_unlock_attempt(&state, now)? {
+        return Ok(response);
+    }
+
+    let data_key = match vault::unlock_with_recovery_key(&state.db, recovery_key.expose()) {
+        Ok(key) => key,
+        Err(_) => {
+            return unlock_failure_response(&state, UNLOCK_METHOD_RECOVERY_KEY, "恢复密钥错误", now);
+        }
+    };
+
+    let params = password_hashing::target_params(state.db.get_master_password_hash()?.as_deref());
+    let (hash, master_key) = hash_and_wrap(new_password.expose(), &data_key, &params)?;
+    let (next_recovery_key, recovery_wrap) = new_recovery_key(&data_key)?;
+    let (_has_master, _old_hint, require_password) = state.db.get_master_password_config()?;
+    // 未完成的主密码修改被新密码取代
+    state.db.delete_vault_rekey()?;
+    state
+        .db
+        .set_master_password_with_key(&hash, hint.as_deref(), require_password, &[master_key, recovery_wrap])?;
+    finish_unlock(app, &state, data_key, UNLOCK_METHOD_RECOVERY_KEY)?;
+    log::info!("Vault unlocked with recovery key, master password reset");
+
+    let new_state = security_get_state(state).await?;
+    Ok(json!({ "success": true, "state": new_state, "recoveryKey": next_recovery_key }))
+}
+
+/// 重新生成恢复密钥（旧恢复密钥立即失效）
+#[tauri::command]
+pub async fn security_regenerate_recovery_key(
+    state: State<'_, AppState>,
+    current_password: SecretString,
+) -> Result<Value, String> {
+    if let Some(response) = check_current_password(&state, current_password.expose(), "当前主密码错误")? {
+        return Ok(response);
+    }
+
+    let recovery_key = {
+        // 解包与写入期间阻止修改主密码，避免在轮换提交后写入已丢弃数据密钥的包装
+        let _rotation = state.key_leases.begin_rotation(KEY_ROTATION_WAIT)?;
+        let params = password_hashing::target_params(state.db.get_master_password_hash()?.as_deref());
+        let data_key =
+            vault::unlock_with_password(&state.db, current_password.expose(), &KdfParams::argon2id(&params))?;
+        let (recovery_key, recovery_wrap) = new_recovery_key(&data_key)?;
+        state.db.replace_vault_keys(&[recovery_wrap], &[])?;
+        recovery_key
+    };
+    log::info!("Recovery key regenerated");
+
+    let new_state = security_get_state(state).await?;
+    Ok(json!({ "success": true, "state": new_state, "recoveryKey": recovery_key }))
+}
+
+/// 撤销恢复密钥
+#[tauri::command]
+pub async fn security_revoke_recovery_key(
+    state: State<'_, AppState>,
+    current_password: SecretString,
+) -> Result<Value, String> {
+    if let Some(response) = check_current_password(&state, current_password.expose(), "当前主密码错误")? {
+        return Ok(response);
+    }
+
+    state.db.delete_vault_key(vault::KEY_KIND_RECOVERY)?;
+    log::info!("Recovery key revoked");
+
+    let new_state = security_get_state(state).await?;
+    Ok(json!({ "success": true, "state": new_state }))
+}
+
+/// 提高主密码哈希成本
+///
+/// 新参数须逐项不低于当前参数；主密码哈希与主密码包装在同一事务中重写。
+#[tauri::command]
+pub async fn security_update_password_hash_params(
+    state: State<'_, AppState>,
+    current_password: SecretString,
+    params: HashParams,
+) -> Result<Value, String> {
+    if let Some(response) = check_current_password(&state, current_password.expose(), "当前主密码错误")? {
+        return Ok(response);
+    }
+    let current = password_hashing::target_params(state.db.get_master_password_hash()?.as_deref());
+
+    if let Err(e) = params.validate() {
+        return Ok(json!({ "success": false, "error": e }));
+    }
+    if !params.at_least(&current) {
+        return Ok(json!({
+            "success": false,
+            "error": "新的哈希参数不能低于当前参数",
+            "current": current
+        }));
+    }
+
+    {
+        // 修改主密码不能在解包与重写主密码包装之间提交，否则新包装里是已丢弃的数据密钥
+        let _rotation = state.key_leases.begin_rotation(KEY_ROTATION_WAIT)?;
+        let data_key =
+            vault::unlock_with_password(&state.db, current_password.expose(), &KdfParams::argon2id(&current))?;
+        rehash_master_password(&state, current_password.expose(), &data_key, &params)?;
+    }
+    log::info!("Master password hash cost raised: {:?}", params);
+
+    let new_state = security_get_state(state).await?;
+    Ok(json!({ "success": true, "state": new_state }))
+}
+
+/// 获取字段加密策略
+#[tauri::command]
+pub async fn security_get_field_encryption_policy(state: State<'_, AppState>) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    let policy = field_encryption::load_policy(&state.db)?;
+    Ok(json!({ "success": true, "policy": policy }))
+}
+
+/// 修改字段加密策略，并在同一事务中加密或解密受影响的列
+#[tauri::command]
+pub async fn security_set_field_encryption_policy(
+    state: State<'_, AppState>,
+    policy: FieldEncryptionPolicy,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    // 已取出的 FieldCipher 仍按旧策略读写，须等其归还并在改写期间拒绝新的取用
+    let _rotation = state.key_leases.begin_rotation(KEY_ROTATION_WAIT)?;
+    // 持有数据密钥锁，避免与修改主密码时的数据密钥轮换并发
+    let guard = state.encryption.lock().map_err(|e| e.to_string())?;
+    let encryption = guard.as_ref().ok_or_else(crate::vault_locked_error)?;
+    match field_encryption::apply_policy(&state.db, encryption, &policy) {
+        Ok(rewritten) => {
+            log::info!("Field encryption policy updated, {} values rewritten", rewritten);
+            Ok(json!({ "success": true, "policy": policy, "rewritten": rewritten }))
+        }
+        Err(e) => Ok(json!({ "success": false, "error": e })),
+    }
+}
+
+/// 获取最近的解锁失败记录及当前节流状态
+#[tauri::command]
+pub async fn security_get_unlock_failures(
+    state: State<'_, AppState>,
+    limit: Option<u32>,
+) -> Result<Value, String> {
+    let failures = state
+        .db
+        .get_unlock_failures(limit.unwrap_or(DEFAULT_UNLOCK_FAILURE_LIST).min(200))?;
+    let throttle = state
+        .unlock_throttle
+        .lock()
+        .map_err(|_| "Failed to lock throttle state".to_string())?
+        .clone();
+    Ok(json!({
+        "success": true,
+        "failures": failures,
+        "failedAttempts": throttle.failed_attempts,
+        "cooldownSeconds": active_cooldown_seconds(&throttle, Utc::now()),
+        "cooldownUntil": throttle.cooldown_until.map(|t| t.to_rfc3339())
+    }))
+}
 
 /// 锁定 UI
 #[tauri::command]
 pub async fn security_lock_ui(state: State<'_, AppState>) -> Result<Value, String> {
-    let mut ui_locked = state.ui_locked.lock().map_err(|_| "Failed to lock state".to_string())?;
-    *ui_locked = true;
+    lock_vault(&state)?;
     Ok(json!({ "success": true }))
 }
 
@@ -428,11 +839,12 @@ pub async fn security_get_ui_lock_state(state: State<'_, AppState>) -> Result<Va
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sha2::Digest;
 
     #[test]
     fn test_throttle_activates_after_five_failures() {
         let mut throttle = UnlockThrottleState::default();
-        let now = Instant::now();
+        let now = Utc::now();
 
         for _ in 0..(UNLOCK_FAILURE_LIMIT - 1) {
             assert!(register_unlock_failure(&mut throttle, now).is_none());
@@ -440,44 +852,204 @@ mod tests {
 
         let cooldown = register_unlock_failure(&mut throttle, now);
         assert_eq!(cooldown, Some(UNLOCK_COOLDOWN_SECONDS));
-        assert!(active_cooldown_seconds(&mut throttle, now).is_some());
+        assert!(active_cooldown_seconds(&throttle, now).is_some());
     }
 
     #[test]
-    fn test_throttle_resets_after_expiry_or_success() {
+    fn test_throttle_escalates_until_success() {
         let mut throttle = UnlockThrottleState::default();
-        let now = Instant::now();
+        let now = Utc::now();
 
         for _ in 0..UNLOCK_FAILURE_LIMIT {
             let _ = register_unlock_failure(&mut throttle, now);
         }
-        assert!(active_cooldown_seconds(&mut throttle, now).is_some());
+        assert!(active_cooldown_seconds(&throttle, now).is_some());
 
-        let after_expiry = now + Duration::from_secs(UNLOCK_COOLDOWN_SECONDS + 1);
-        assert_eq!(active_cooldown_seconds(&mut throttle, after_expiry), None);
-        assert_eq!(throttle.failed_attempts, 0);
+        // 冷却结束后失败次数不清零，下一次失败冷却翻倍
+        let after_expiry = now + chrono::Duration::seconds(UNLOCK_COOLDOWN_SECONDS as i64 + 1);
+        assert_eq!(active_cooldown_seconds(&throttle, after_expiry), None);
+        assert_eq!(
+            register_unlock_failure(&mut throttle, after_expiry),
+            Some(UNLOCK_COOLDOWN_SECONDS * 2)
+        );
+        assert_eq!(
+            register_unlock_failure(&mut throttle, after_expiry),
+            Some(UNLOCK_COOLDOWN_SECONDS * 4)
+        );
+        assert_eq!(cooldown_for_failures(100), Some(UNLOCK_COOLDOWN_MAX_SECONDS));
 
-        register_unlock_failure(&mut throttle, now);
-        assert_eq!(throttle.failed_attempts, 1);
         reset_unlock_throttle(&mut throttle);
         assert_eq!(throttle.failed_attempts, 0);
         assert!(throttle.cooldown_until.is_none());
     }
 
+    #[test]
+    fn test_throttle_persists_across_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let now = Utc::now();
+        for _ in 0..UNLOCK_FAILURE_LIMIT {
+            update_unlock_throttle(&state, |t| register_unlock_failure(t, now)).unwrap();
+            state.db.record_unlock_attempt(false, UNLOCK_METHOD_MASTER_PASSWORD).unwrap();
+        }
+        state.db.record_unlock_attempt(true, UNLOCK_METHOD_MASTER_PASSWORD).unwrap();
+
+        // 模拟重启：从数据库重新载入
+        let reloaded = state.db.get_unlock_throttle().unwrap();
+        assert_eq!(reloaded.failed_attempts, UNLOCK_FAILURE_LIMIT);
+        assert!(active_cooldown_seconds(&reloaded, now).is_some());
+
+        let failures = state.db.get_unlock_failures(3).unwrap();
+        assert_eq!(failures.len(), 3);
+        assert!(failures.iter().all(|f| !f.success));
+        assert!(failures[0].id > failures[1].id);
+    }
+
+    #[test]
+    fn test_concurrent_unlock_attempts_are_reserved_before_verifying() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let now = Utc::now();
+
+        // 并发请求尚未完成校验时，每次预占都已计数，达到上限后的请求直接被拒绝
+        for _ in 0..UNLOCK_FAILURE_LIMIT {
+            assert!(reserve_unlock_attempt(&state, now).unwrap().is_none());
+        }
+        let rejected = reserve_unlock_attempt(&state, now).unwrap().expect("should be cooling down");
+        assert_eq!(rejected["errorCode"], "UNLOCK_COOLDOWN");
+        assert_eq!(state.db.get_unlock_throttle().unwrap().failed_attempts, UNLOCK_FAILURE_LIMIT);
+
+        // 解锁成功后重置
+        update_unlock_throttle(&state, reset_unlock_throttle).unwrap();
+        assert!(reserve_unlock_attempt(&state, now).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_password_checks_share_unlock_throttle() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        security_set_master_password(app.state(), "correct-password".into(), None).await.unwrap();
+        let state = app.state::<AppState>();
+
+        // 校验当前密码的命令同样计入失败次数
+        let wrong = security_revoke_recovery_key(app.state(), "wrong-password".into()).await.unwrap();
+        assert_eq!(wrong["success"], false);
+        assert_eq!(state.db.get_unlock_throttle().unwrap().failed_attempts, 1);
+        assert_eq!(state.db.get_unlock_failures(1).unwrap().len(), 1);
+
+        // 冷却期内即使密码正确也一律拒绝，且不再计数
+        for _ in 1..UNLOCK_FAILURE_LIMIT {
+            reserve_unlock_attempt(&state, Utc::now()).unwrap();
+        }
+        let password = || SecretString::from("correct-password");
+        let responses = [
+            security_update_master_password(app.state(), password(), "another-password".into(), None).await,
+            security_clear_master_password(app.state(), password()).await,
+            security_set_require_master_password(app.state(), false, None, None, Some(password())).await,
+            security_regenerate_recovery_key(app.state(), password()).await,
+            security_revoke_recovery_key(app.state(), password()).await,
+            security_update_password_hash_params(app.state(), password(), HashParams::default()).await,
+        ];
+        for response in responses {
+            assert_eq!(response.unwrap()["errorCode"], "UNLOCK_COOLDOWN");
+        }
+        assert_eq!(state.db.get_unlock_throttle().unwrap().failed_attempts, UNLOCK_FAILURE_LIMIT);
+        assert!(state.db.get_vault_key(vault::KEY_KIND_RECOVERY).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_recovery_key_regeneration_waits_for_key_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        security_set_master_password(app.state(), "correct-password".into(), None).await.unwrap();
+        let state = app.state::<AppState>();
+        let before = state.db.get_vault_key(vault::KEY_KIND_RECOVERY).unwrap().unwrap().wrapped_key;
+
+        // 修改主密码进行中时拒绝改写恢复包装
+        let rotation = state.key_leases.begin_rotation(KEY_ROTATION_WAIT).unwrap();
+        assert!(security_regenerate_recovery_key(app.state(), "correct-password".into()).await.is_err());
+        assert_eq!(state.db.get_vault_key(vault::KEY_KIND_RECOVERY).unwrap().unwrap().wrapped_key, before);
+        drop(rotation);
+
+        let response = security_regenerate_recovery_key(app.state(), "correct-password".into()).await.unwrap();
+        assert!(response["recoveryKey"].is_string());
+        assert_ne!(state.db.get_vault_key(vault::KEY_KIND_RECOVERY).unwrap().unwrap().wrapped_key, before);
+    }
+
+    #[tokio::test]
+    async fn test_field_policy_change_waits_for_key_users() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let policy = FieldEncryptionPolicy { password_notes: true, ..Default::default() };
+
+        let rotation = state.key_leases.begin_rotation(KEY_ROTATION_WAIT).unwrap();
+        assert!(security_set_field_encryption_policy(app.state(), policy).await.is_err());
+        assert_eq!(field_encryption::load_policy(&state.db).unwrap(), FieldEncryptionPolicy::default());
+        drop(rotation);
+
+        let response = security_set_field_encryption_policy(app.state(), policy).await.unwrap();
+        assert_eq!(response["success"], true);
+        assert!(state.field_cipher().unwrap().policy.password_notes);
+    }
+
     #[test]
     fn test_verify_current_password_with_wrong_input() {
-        let stored = hash_password("correct-password");
+        let cheap = HashParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let stored = password_hashing::hash("correct-password", &cheap).unwrap();
         assert_eq!(
             verify_current_password(Some(stored), "bad-password"),
             Err("wrong_password")
         );
     }
 
+    #[test]
+    fn test_verify_current_password_accepts_legacy_sha256() {
+        let legacy = hex::encode(sha2::Sha256::digest(b"legacy-password"));
+        assert_eq!(verify_current_password(Some(legacy.clone()), "legacy-password"), Ok(()));
+        assert_eq!(
+            verify_current_password(Some(legacy), "other"),
+            Err("wrong_password")
+        );
+    }
+
     #[test]
     fn test_verify_current_password_when_not_set() {
         assert_eq!(verify_current_password(None, "any"), Err("not_set"));
     }
 
+    #[test]
+    fn test_should_auto_lock_after_timeout() {
+        let five_minutes = Duration::from_secs(5 * 60);
+        assert!(should_auto_lock(true, false, five_minutes, 5));
+        assert!(!should_auto_lock(true, false, five_minutes - Duration::from_secs(1), 5));
+        assert!(!should_auto_lock(false, false, five_minutes, 5));
+        assert!(!should_auto_lock(true, true, five_minutes, 5));
+    }
+
+    #[test]
+    fn test_lock_vault_drops_key_without_device_wrap() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        *state.ui_locked.lock().unwrap() = false;
+
+        // 有设备级包装：仅锁定 UI
+        lock_vault(&state).unwrap();
+        assert!(*state.ui_locked.lock().unwrap());
+        assert!(state.is_vault_unlocked());
+
+        state.db.delete_vault_key(vault::KEY_KIND_DEVICE).unwrap();
+        lock_vault(&state).unwrap();
+        assert!(!state.is_vault_unlocked());
+    }
+
     #[test]
     fn test_lock_state_after_require_toggle() {
         assert!(lock_state_after_require_toggle(true));
@@ -0,0 +1,114 @@
+//! 一次性密码（TOTP）Commands
+
+use crate::models::{PasswordTotp, TotpCode};
+use crate::services::totp;
+use crate::AppState;
+use serde_json::{json, Value};
+use tauri::State;
+
+/// 获取密码条目的 TOTP 配置（密钥已解密）
+#[tauri::command]
+pub async fn get_password_totp(
+    state: State<'_, AppState>,
+    password_id: i64,
+) -> Result<Option<PasswordTotp>, String> {
+    state.ensure_unlocked()?;
+    let Some(mut config) = state.db.get_password_totp(password_id)? else {
+        return Ok(None);
+    };
+    config.secret = state.encryption_service()?.decrypt(config.secret.expose())?;
+    Ok(Some(config))
+}
+
+/// 通过 otpauth URI 设置密码条目的 TOTP 配置
+#[tauri::command]
+pub async fn set_password_totp(
+    state: State<'_, AppState>,
+    password_id: i64,
+    uri: String,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("set_password_totp called: password_id={}", password_id);
+    if state.db.get_password(password_id)?.is_none() {
+        return Err("Password not found".to_string());
+    }
+
+    let mut config = totp::parse_otpauth_uri(&uri)?;
+    config.password_id = password_id;
+    let encryption = state.encryption_service()?;
+    config.secret = encryption.encrypt(config.secret.expose())?.into();
+    state.db.set_password_totp(&config)?;
+
+    Ok(json!({ "success": true }))
+}
+
+/// 删除密码条目的 TOTP 配置
+#[tauri::command]
+pub async fn delete_password_totp(
+    state: State<'_, AppState>,
+    password_id: i64,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    state.db.delete_password_totp(password_id)?;
+    Ok(json!({ "success": true }))
+}
+
+/// 获取当前验证码与剩余秒数
+#[tauri::command]
+pub async fn get_totp_code(
+    state: State<'_, AppState>,
+    password_id: i64,
+) -> Result<TotpCode, String> {
+    state.ensure_unlocked()?;
+    let mut config = state
+        .db
+        .get_password_totp(password_id)?
+        .ok_or("该条目未设置 TOTP")?;
+    config.secret = state.encryption_service()?.decrypt(config.secret.expose())?;
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    totp::generate_code(&config, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&issuer=GitHub";
+
+    #[tokio::test]
+    async fn test_totp_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(get_password_totp(app.state(), 1).await);
+        assert_vault_locked(set_password_totp(app.state(), 1, URI.to_string()).await);
+        assert_vault_locked(delete_password_totp(app.state(), 1).await);
+        assert_vault_locked(get_totp_code(app.state(), 1).await);
+    }
+
+    #[tokio::test]
+    async fn test_totp_secret_is_encrypted_at_rest() {
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let password: crate::models::Password =
+            serde_json::from_value(json!({ "title": "GitHub" })).unwrap();
+        let id = state.db.add_password(&password).unwrap();
+
+        set_password_totp(app.state(), id, URI.to_string()).await.unwrap();
+        let stored = state.db.get_password_totp(id).unwrap().unwrap();
+        assert_ne!(stored.secret.expose(), "JBSWY3DPEHPK3PXP");
+
+        let config = get_password_totp(app.state(), id).await.unwrap().unwrap();
+        assert_eq!(config.secret.expose(), "JBSWY3DPEHPK3PXP");
+        assert_eq!(config.issuer.as_deref(), Some("GitHub"));
+
+        let code = get_totp_code(app.state(), id).await.unwrap();
+        assert_eq!(code.code.len(), 6);
+        assert!((1..=30).contains(&code.remaining_seconds));
+    }
+}
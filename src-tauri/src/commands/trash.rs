@@ -0,0 +1,120 @@
+//! 回收站 Commands
+
+use crate::models::{EncryptableField, ItemType, TrashItem};
+use crate::services::trash;
+use crate::AppState;
+use serde_json::{json, Value};
+use tauri::State;
+
+/// 列出回收站中的条目（先清理超过保留天数的条目）
+#[tauri::command]
+pub async fn get_trash(state: State<'_, AppState>) -> Result<Vec<TrashItem>, String> {
+    state.ensure_unlocked()?;
+    trash::purge_expired(&state.db)?;
+    let cipher = state.field_cipher()?;
+    let mut items = state.db.get_trash()?;
+    for item in &mut items {
+        if item.item_type == ItemType::SecureNote {
+            let title = std::mem::take(&mut item.title);
+            item.title = cipher
+                .decrypt_value(EncryptableField::NoteTitle, Some(title))
+                .unwrap_or_default();
+        }
+    }
+    Ok(items)
+}
+
+/// 从回收站还原条目
+#[tauri::command]
+pub async fn restore_trash_item(
+    state: State<'_, AppState>,
+    item_type: ItemType,
+    id: i64,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("restore_trash_item called: type={}, id={}", item_type.as_str(), id);
+    state.db.restore_from_trash(item_type, id)?;
+    super::search::refresh_search_index(&state, item_type, id);
+    Ok(json!({ "success": true }))
+}
+
+/// 彻底删除回收站中的条目
+#[tauri::command]
+pub async fn purge_trash_item(
+    state: State<'_, AppState>,
+    item_type: ItemType,
+    id: i64,
+) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    log::info!("purge_trash_item called: type={}, id={}", item_type.as_str(), id);
+    state.db.purge_from_trash(item_type, id)?;
+    Ok(json!({ "success": true }))
+}
+
+/// 清空回收站
+#[tauri::command]
+pub async fn empty_trash(state: State<'_, AppState>) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    let purged = state.db.purge_trash(None)?;
+    log::info!("empty_trash purged {} items", purged);
+    Ok(json!({ "success": true, "purged": purged }))
+}
+
+/// 获取回收站设置
+#[tauri::command]
+pub async fn get_trash_settings(state: State<'_, AppState>) -> Result<Value, String> {
+    Ok(json!({
+        "success": true,
+        "retentionDays": trash::retention_days(&state.db)?,
+    }))
+}
+
+/// 设置回收站保留天数（0 表示不自动清理）
+#[tauri::command]
+pub async fn set_trash_retention(state: State<'_, AppState>, retention_days: u32) -> Result<Value, String> {
+    state.ensure_unlocked()?;
+    trash::set_retention_days(&state.db, retention_days)?;
+    Ok(json!({ "success": true, "retentionDays": retention_days }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trash_commands_denied_while_locked() {
+        use crate::test_support::{app_with_state, assert_vault_locked};
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = app_with_state(&dir, false);
+        assert_vault_locked(get_trash(app.state()).await);
+        assert_vault_locked(restore_trash_item(app.state(), ItemType::Login, 1).await);
+        assert_vault_locked(purge_trash_item(app.state(), ItemType::Login, 1).await);
+        assert_vault_locked(empty_trash(app.state()).await);
+        assert_vault_locked(set_trash_retention(app.state(), 7).await);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_note_restored_from_trash() {
+        use crate::models::SecureRecord;
+        use tauri::Manager;
+
+        let dir = tempfile::tempdir().unwrap();
+        let app = crate::test_support::app_with_state(&dir, true);
+        let state = app.state::<AppState>();
+        let note: SecureRecord = serde_json::from_value(json!({ "title": "Note" })).unwrap();
+        let id = state.db.add_note(&note).unwrap();
+
+        crate::commands::notes::delete_note(app.state(), id).await.unwrap();
+        assert!(state.db.get_note(id).unwrap().is_none());
+        let trashed = get_trash(app.state()).await.unwrap();
+        assert_eq!((trashed[0].item_type, trashed[0].id), (ItemType::SecureNote, id));
+        assert_eq!(trashed[0].title, "Note");
+
+        restore_trash_item(app.state(), ItemType::SecureNote, id).await.unwrap();
+        assert!(state.db.get_note(id).unwrap().is_some());
+        assert!(restore_trash_item(app.state(), ItemType::SecureNote, id).await.is_err());
+        assert!(purge_trash_item(app.state(), ItemType::SecureNote, id).await.is_err());
+    }
+}
@@ -50,6 +50,7 @@ pub async fn open_external(app: AppHandle, url: String) -> Result<(), String> {
     }
     
     // 使用 shell plugin 打开外部链接
+    #[allow(deprecated)]
     app.shell()
         .open(&url, None)
         .map_err(|e| e.to_string())
@@ -12,21 +12,97 @@ pub mod services;
 use tauri::Manager;
 use services::database::DatabaseService;
 use services::encryption::EncryptionService;
+use services::vault::{KeyLeases, Leased};
 use std::sync::Mutex;
+pub use models::UnlockThrottleState;
 
-#[derive(Debug, Default)]
-pub struct UnlockThrottleState {
-    pub failed_attempts: u32,
-    pub cooldown_until: Option<std::time::Instant>,
+/// 保险库锁定时命令返回的错误码
+pub const VAULT_LOCKED: &str = "VAULT_LOCKED";
+
+/// 构造结构化的保险库锁定错误（JSON 字符串，前端可解析 errorCode）
+pub fn vault_locked_error() -> String {
+    serde_json::json!({
+        "errorCode": VAULT_LOCKED,
+        "error": "保险库已锁定，请先输入主密码解锁"
+    })
+    .to_string()
 }
 
 /// 应用共享状态
 pub struct AppState {
     pub db: DatabaseService,
-    pub encryption: EncryptionService,  // 应用级加密服务（始终可用）
+    pub encryption: Mutex<Option<EncryptionService>>, // 数据密钥加密服务（锁定时为 None）
     pub ui_locked: Mutex<bool>,         // UI 锁定状态
-    pub unlock_throttle: Mutex<UnlockThrottleState>, // 解锁失败节流状态
+    pub unlock_throttle: Mutex<UnlockThrottleState>, // 解锁失败节流状态（启动时从数据库载入）
     pub backup_notification: Mutex<Option<(String, String, std::time::Instant)>>, // category, message, timestamp
+    pub last_activity: Mutex<std::time::Instant>, // 最近一次敏感操作时间，用于自动锁定
+    pub search_index: Mutex<services::search_index::SearchIndex>, // 解密内容的全文索引（仅在内存中）
+    pub key_leases: KeyLeases, // 数据密钥副本的租约，修改主密码前等待全部归还
+}
+
+impl AppState {
+    /// 获取当前数据密钥加密服务；保险库锁定时返回错误
+    ///
+    /// 返回值持有密钥租约，应保留到用它加密的数据写入完成。
+    pub fn encryption_service(&self) -> Result<Leased<'_, EncryptionService>, String> {
+        self.key_leases.lease(|| self.current_key())
+    }
+
+    /// 获取按当前字段加密策略加解密条目的服务；保险库锁定时返回错误
+    pub fn field_cipher(&self) -> Result<Leased<'_, services::field_encryption::FieldCipher>, String> {
+        self.key_leases.lease(|| {
+            let policy = services::field_encryption::load_policy(&self.db)?;
+            Ok(services::field_encryption::FieldCipher::new(policy, self.current_key()?))
+        })
+    }
+
+    fn current_key(&self) -> Result<EncryptionService, String> {
+        self.encryption
+            .lock()
+            .map_err(|e| e.to_string())?
+            .clone()
+            .ok_or_else(vault_locked_error)
+    }
+
+    /// 敏感命令的统一入口检查
+    ///
+    /// 数据密钥不可用，或要求主密码且 UI 已锁定时，返回 `VAULT_LOCKED` 错误。
+    pub fn ensure_unlocked(&self) -> Result<(), String> {
+        if !self.is_vault_unlocked() {
+            return Err(vault_locked_error());
+        }
+        let ui_locked = *self.ui_locked.lock().map_err(|e| e.to_string())?;
+        if ui_locked {
+            let (_has_master, _hint, require_password) = self.db.get_master_password_config()?;
+            if require_password {
+                return Err(vault_locked_error());
+            }
+        }
+        self.touch_activity();
+        Ok(())
+    }
+
+    /// 保险库是否已解锁（数据密钥可用）
+    pub fn is_vault_unlocked(&self) -> bool {
+        self.encryption.lock().map(|e| e.is_some()).unwrap_or(false)
+    }
+
+    /// 记录一次用户活动，重置自动锁定计时
+    pub fn touch_activity(&self) {
+        if let Ok(mut last) = self.last_activity.lock() {
+            *last = std::time::Instant::now();
+        }
+    }
+
+    /// 设置或清除数据密钥加密服务；同时清空由旧密钥解密得到的搜索索引
+    pub fn set_encryption(&self, service: Option<EncryptionService>) {
+        if let Ok(mut guard) = self.encryption.lock() {
+            *guard = service;
+        }
+        if let Ok(mut index) = self.search_index.lock() {
+            index.clear();
+        }
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -58,26 +134,48 @@ pub fn run() {
             let db_service = DatabaseService::new(&db_path);
             if let Err(e) = db_service.initialize() {
                 log::error!("Failed to initialize database: {}", e);
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Database initialization failed: {}", e),
-                )));
+                return Err(Box::new(std::io::Error::other(format!(
+                    "Database initialization failed: {}",
+                    e
+                ))));
             }
 
-            // 初始化应用级加密服务（始终可用，用于数据加密）
-            let encryption_service = EncryptionService::new_with_app_key();
-            log::info!("Encryption service initialized with app-level key");
+            // 打开保险库：未要求主密码时直接解包数据密钥，否则等待主密码解锁
+            let encryption_service = match services::vault::open_on_startup(&db_service) {
+                Ok(service) => service,
+                Err(e) => {
+                    log::error!("Failed to open vault: {}", e);
+                    None
+                }
+            };
+            log::info!(
+                "Vault opened, data key {}",
+                if encryption_service.is_some() { "unlocked" } else { "locked" }
+            );
+
+            // 载入持久化的解锁节流状态，重启不会清除冷却
+            let unlock_throttle = db_service.get_unlock_throttle().unwrap_or_else(|e| {
+                log::error!("Failed to load unlock throttle: {}", e);
+                UnlockThrottleState::default()
+            });
 
             // 管理应用状态
             app.manage(AppState { 
                 db: db_service, 
-                encryption: encryption_service,
+                encryption: Mutex::new(encryption_service),
                 ui_locked: Mutex::new(true),  // 默认锁定 UI
-                unlock_throttle: Mutex::new(UnlockThrottleState::default()),
+                unlock_throttle: Mutex::new(unlock_throttle),
                 backup_notification: Mutex::new(None),
+                last_activity: Mutex::new(std::time::Instant::now()),
+                search_index: Mutex::new(Default::default()),
+                key_leases: KeyLeases::default(),
             });
 
             commands::backup::start_backup_scheduler(app.handle().clone());
+            commands::security::start_auto_lock_timer(app.handle().clone());
+            if app.state::<AppState>().is_vault_unlocked() {
+                commands::security::start_cipher_upgrade(app.handle().clone());
+            }
 
             // 显示窗口
             if let Some(window) = app.get_webview_window("main") {
@@ -95,7 +193,72 @@ pub fn run() {
             commands::passwords::delete_password,
             commands::passwords::search_passwords,
             commands::passwords::generate_password,
+            commands::passwords::generate_password_with_policy,
+            commands::passwords::get_password_policies,
+            commands::passwords::save_password_policy,
+            commands::passwords::delete_password_policy,
+            commands::passwords::set_group_password_policy,
             commands::passwords::get_password_history,
+            commands::passwords::restore_password_from_history,
+            commands::passwords::delete_password_history_entry,
+            commands::passwords::get_password_history_retention,
+            commands::passwords::set_password_history_retention,
+            // 一次性密码
+            commands::totp::get_password_totp,
+            commands::totp::set_password_totp,
+            commands::totp::delete_password_totp,
+            commands::totp::get_totp_code,
+            // 自定义字段
+            commands::custom_fields::get_custom_fields,
+            commands::custom_fields::add_custom_field,
+            commands::custom_fields::update_custom_field,
+            commands::custom_fields::delete_custom_field,
+            // 多网址 / 多账号
+            commands::accounts::get_password_urls,
+            commands::accounts::set_password_urls,
+            commands::accounts::get_password_accounts,
+            commands::accounts::add_password_account,
+            commands::accounts::update_password_account,
+            commands::accounts::delete_password_account,
+            commands::accounts::find_passwords_by_url,
+            // 附件
+            commands::attachments::list_attachments,
+            commands::attachments::add_attachment,
+            commands::attachments::read_attachment,
+            commands::attachments::extract_attachment,
+            commands::attachments::delete_attachment,
+            commands::attachments::get_attachment_settings,
+            commands::attachments::set_attachment_size_limit,
+            // 条目类型
+            commands::items::get_item_type_schemas,
+            commands::items::list_all_items,
+            commands::items::get_items,
+            commands::items::get_item,
+            commands::items::add_item,
+            commands::items::update_item,
+            commands::items::delete_item,
+            // 密码健康检查
+            commands::audit::audit_passwords,
+            // 离线泄露密码检查
+            commands::breach::import_breach_dataset,
+            commands::breach::get_breach_dataset_info,
+            commands::breach::clear_breach_dataset,
+            commands::breach::check_breached_passwords,
+            commands::breach::check_password_breached,
+            // 全文搜索
+            commands::search::search_vault,
+            commands::search::query_vault,
+            // 修订历史
+            commands::revisions::get_revisions,
+            commands::revisions::diff_revision,
+            commands::revisions::restore_revision,
+            // 回收站
+            commands::trash::get_trash,
+            commands::trash::restore_trash_item,
+            commands::trash::purge_trash_item,
+            commands::trash::empty_trash,
+            commands::trash::get_trash_settings,
+            commands::trash::set_trash_retention,
             // 分组管理
             commands::groups::get_groups,
             commands::groups::get_group_tree,
@@ -117,6 +280,13 @@ pub fn run() {
             commands::security::security_set_require_master_password,
             commands::security::security_lock_ui,
             commands::security::security_get_ui_lock_state,
+            commands::security::security_update_password_hash_params,
+            commands::security::security_get_unlock_failures,
+            commands::security::security_unlock_with_recovery_key,
+            commands::security::security_regenerate_recovery_key,
+            commands::security::security_revoke_recovery_key,
+            commands::security::security_get_field_encryption_policy,
+            commands::security::security_set_field_encryption_policy,
             // 笔记管理
             commands::notes::get_note_groups,
             commands::notes::get_note_group_tree,
@@ -152,3 +322,41 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use tauri::test::{mock_app, MockRuntime};
+
+    /// 构造带临时数据库的 mock 应用；`unlocked` 决定数据密钥是否可用
+    pub fn app_with_state(dir: &tempfile::TempDir, unlocked: bool) -> tauri::App<MockRuntime> {
+        let db = DatabaseService::new(dir.path().join("commands.db").to_str().unwrap());
+        db.initialize().unwrap();
+        let encryption = if unlocked {
+            services::vault::open_on_startup(&db).unwrap()
+        } else {
+            None
+        };
+        let app = mock_app();
+        app.manage(AppState {
+            db,
+            encryption: Mutex::new(encryption),
+            ui_locked: Mutex::new(!unlocked),
+            unlock_throttle: Mutex::new(UnlockThrottleState::default()),
+            backup_notification: Mutex::new(None),
+            last_activity: Mutex::new(std::time::Instant::now()),
+            search_index: Mutex::new(Default::default()),
+            key_leases: KeyLeases::default(),
+        });
+        app
+    }
+
+    /// 断言命令返回了 VAULT_LOCKED 错误
+    pub fn assert_vault_locked<T>(result: Result<T, String>) {
+        let Err(err) = result else {
+            panic!("command should be denied while locked");
+        };
+        let payload: serde_json::Value = serde_json::from_str(&err).expect("structured error");
+        assert_eq!(payload["errorCode"], VAULT_LOCKED);
+    }
+}
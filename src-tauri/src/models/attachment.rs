@@ -0,0 +1,23 @@
+//! 附件数据模型
+
+use serde::{Deserialize, Serialize};
+
+/// 附件元数据；内容以分块密文另存于 `attachment_chunks`
+///
+/// 附件属于且仅属于一个密码条目或一条安全笔记。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: Option<i64>,
+    pub password_id: Option<i64>,
+    pub note_id: Option<i64>,
+    pub file_name: String,
+    pub mime_type: Option<String>,
+    /// 明文大小（字节）
+    pub size: i64,
+    pub chunk_count: i64,
+    /// 由数据密钥包装的文件密钥，不返回前端
+    #[serde(skip)]
+    pub file_key: String,
+    pub created_at: Option<String>,
+}
@@ -0,0 +1,70 @@
+//! 密码健康检查数据模型
+
+use serde::{Deserialize, Serialize};
+
+/// 单个条目的问题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditFinding {
+    /// 未设置密码
+    EmptyPassword,
+    /// 密码无法用当前密钥解密
+    Undecryptable,
+    /// 强度评分低于 3
+    WeakPassword,
+    /// 与其他条目使用相同密码
+    ReusedPassword,
+    /// 密码超过设定天数未更换
+    OldPassword,
+    /// 未填写任何网址
+    MissingUrl,
+    /// 存在仅使用 HTTP 的网址
+    InsecureUrl,
+}
+
+/// 单个密码条目的检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordAuditEntry {
+    pub id: i64,
+    pub title: String,
+    #[serde(rename = "group_id")]
+    pub group_id: Option<i64>,
+    /// 估算熵（位）
+    pub entropy_bits: f64,
+    /// 强度评分 0-4
+    pub strength: u8,
+    /// 使用相同密码的其他条目
+    pub reused_with: Vec<i64>,
+    /// 距上次更换密码的天数
+    pub age_days: Option<i64>,
+    pub findings: Vec<AuditFinding>,
+    /// 条目得分 0-100
+    pub score: u8,
+}
+
+/// 分组汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupAuditSummary {
+    /// 为空表示未分组
+    #[serde(rename = "group_id")]
+    pub group_id: Option<i64>,
+    pub group_name: Option<String>,
+    pub total: usize,
+    pub weak: usize,
+    pub reused: usize,
+    pub old: usize,
+    /// 组内条目得分的平均值 0-100
+    pub score: u8,
+}
+
+/// 密码健康检查报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordAuditReport {
+    pub entries: Vec<PasswordAuditEntry>,
+    pub groups: Vec<GroupAuditSummary>,
+    /// 全部条目得分的平均值 0-100
+    pub score: u8,
+}
@@ -0,0 +1,72 @@
+//! 离线泄露密码检查数据模型
+
+use serde::{Deserialize, Serialize};
+
+/// 泄露数据集的哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreachHashKind {
+    /// SHA-1（UTF-8 明文）
+    Sha1,
+    /// NTLM（UTF-16LE 明文的 MD4）
+    Ntlm,
+}
+
+impl BreachHashKind {
+    pub const ALL: [BreachHashKind; 2] = [BreachHashKind::Sha1, BreachHashKind::Ntlm];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BreachHashKind::Sha1 => "sha1",
+            BreachHashKind::Ntlm => "ntlm",
+        }
+    }
+
+    /// 按十六进制哈希长度识别算法
+    pub fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            40 => Some(BreachHashKind::Sha1),
+            32 => Some(BreachHashKind::Ntlm),
+            _ => None,
+        }
+    }
+}
+
+/// 数据集中的一条哈希
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreachHash {
+    pub kind: BreachHashKind,
+    pub hash: Vec<u8>,
+    /// 在泄露数据中出现的次数
+    pub count: i64,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachImportReport {
+    pub imported: usize,
+    /// 无法识别的行
+    pub skipped: usize,
+}
+
+/// 已导入数据集的概况
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachDatasetInfo {
+    pub sha1_count: i64,
+    pub ntlm_count: i64,
+}
+
+/// 密码出现在泄露数据中的条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachedPassword {
+    pub id: i64,
+    pub title: String,
+    #[serde(rename = "group_id")]
+    pub group_id: Option<i64>,
+    pub count: i64,
+    /// 密码无法解密、未能检查；此时 `count` 为 0
+    pub undecryptable: bool,
+}
@@ -0,0 +1,68 @@
+//! 自定义字段数据模型
+
+use super::SecretString;
+use serde::{Deserialize, Serialize};
+
+/// 自定义字段类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFieldType {
+    #[default]
+    Text,
+    /// 隐藏字段（加密存储）
+    Hidden,
+    Url,
+    Email,
+    /// 日期，格式 YYYY-MM-DD
+    Date,
+    /// 一次性密码，值为 otpauth URI 或 Base32 密钥（加密存储）
+    Otp,
+}
+
+impl CustomFieldType {
+    /// 数据库中使用的名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CustomFieldType::Text => "text",
+            CustomFieldType::Hidden => "hidden",
+            CustomFieldType::Url => "url",
+            CustomFieldType::Email => "email",
+            CustomFieldType::Date => "date",
+            CustomFieldType::Otp => "otp",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(CustomFieldType::Text),
+            "hidden" => Some(CustomFieldType::Hidden),
+            "url" => Some(CustomFieldType::Url),
+            "email" => Some(CustomFieldType::Email),
+            "date" => Some(CustomFieldType::Date),
+            "otp" => Some(CustomFieldType::Otp),
+            _ => None,
+        }
+    }
+
+    /// 是否为机密字段：加密存储，且不参与搜索
+    pub fn is_secret(self) -> bool {
+        matches!(self, CustomFieldType::Hidden | CustomFieldType::Otp)
+    }
+}
+
+/// 密码条目的自定义字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomField {
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub password_id: i64,
+    pub field_type: CustomFieldType,
+    pub label: String,
+    /// 机密字段存储时为密文，返回前端前解密为明文
+    #[serde(default)]
+    pub value: SecretString,
+    pub sort_order: Option<i32>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
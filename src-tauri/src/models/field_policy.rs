@@ -0,0 +1,75 @@
+//! 字段加密策略数据模型
+
+use serde::{Deserialize, Serialize};
+
+/// 可选加密的字段
+///
+/// `passwords.password` 与 `secure_records.content` 始终加密，不在此列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptableField {
+    PasswordUsername,
+    PasswordUrl,
+    PasswordNotes,
+    PasswordTags,
+    NoteTitle,
+}
+
+impl EncryptableField {
+    pub const ALL: [EncryptableField; 5] = [
+        EncryptableField::PasswordUsername,
+        EncryptableField::PasswordUrl,
+        EncryptableField::PasswordNotes,
+        EncryptableField::PasswordTags,
+        EncryptableField::NoteTitle,
+    ];
+
+    /// 所在的表、主键列与字段列；附加账号的用户名、附加网址与结构化条目的备注随主字段一同加密
+    pub fn columns(self) -> &'static [(&'static str, &'static str, &'static str)] {
+        match self {
+            EncryptableField::PasswordUsername => {
+                &[("passwords", "id", "username"), ("password_accounts", "id", "username")]
+            }
+            EncryptableField::PasswordUrl => &[("passwords", "id", "url"), ("password_urls", "id", "url")],
+            EncryptableField::PasswordNotes => &[("passwords", "id", "notes"), ("vault_items", "id", "notes")],
+            EncryptableField::PasswordTags => &[("passwords", "id", "tags")],
+            EncryptableField::NoteTitle => &[("secure_records", "id", "title")],
+        }
+    }
+}
+
+/// 字段加密策略（保存在 user_settings 的 `security.field_encryption`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FieldEncryptionPolicy {
+    pub password_username: bool,
+    pub password_url: bool,
+    pub password_notes: bool,
+    pub password_tags: bool,
+    pub note_title: bool,
+}
+
+impl FieldEncryptionPolicy {
+    /// 指定字段是否加密存储
+    pub fn encrypts(&self, field: EncryptableField) -> bool {
+        match field {
+            EncryptableField::PasswordUsername => self.password_username,
+            EncryptableField::PasswordUrl => self.password_url,
+            EncryptableField::PasswordNotes => self.password_notes,
+            EncryptableField::PasswordTags => self.password_tags,
+            EncryptableField::NoteTitle => self.note_title,
+        }
+    }
+
+    /// 当前加密的字段
+    pub fn encrypted_fields(&self) -> Vec<EncryptableField> {
+        EncryptableField::ALL
+            .into_iter()
+            .filter(|field| self.encrypts(*field))
+            .collect()
+    }
+
+    /// 密码搜索涉及的字段（用户名、网址、备注）是否有被加密的
+    pub fn encrypts_password_search_fields(&self) -> bool {
+        self.password_username || self.password_url || self.password_notes
+    }
+}
@@ -0,0 +1,67 @@
+//! 密码生成器数据模型
+
+use super::SecretString;
+use serde::{Deserialize, Serialize};
+
+/// 生成方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeneratorMode {
+    /// 从字符集中随机选取字符
+    #[default]
+    Random,
+    /// 从内置词表中随机选取单词组成口令
+    Passphrase,
+}
+
+/// 密码生成器选项
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordGeneratorOptions {
+    pub mode: Option<GeneratorMode>,
+    pub length: Option<usize>,
+    pub include_uppercase: Option<bool>,
+    pub include_lowercase: Option<bool>,
+    pub include_numbers: Option<bool>,
+    pub include_symbols: Option<bool>,
+    /// 各类字符的最少数量；已启用的类型未指定时至少 1 个
+    pub min_uppercase: Option<usize>,
+    pub min_lowercase: Option<usize>,
+    pub min_numbers: Option<usize>,
+    pub min_symbols: Option<usize>,
+    /// 不使用的字符
+    pub exclude_chars: Option<String>,
+    /// 不使用易混淆字符 `0O1lI`
+    pub exclude_ambiguous: Option<bool>,
+    /// 替换默认特殊字符的自定义集合
+    pub custom_symbols: Option<String>,
+    /// 不允许相邻字符相同
+    pub no_repeats: Option<bool>,
+    /// 不允许三个连续递增或递减的字母或数字（如 `abc`、`321`）
+    pub no_sequences: Option<bool>,
+    /// 口令单词数
+    pub word_count: Option<usize>,
+    /// 口令单词分隔符
+    pub separator: Option<String>,
+    /// 口令单词首字母大写
+    pub capitalize: Option<bool>,
+    /// 在随机一个单词后追加一位随机数字
+    pub include_number: Option<bool>,
+}
+
+/// 生成结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedPassword {
+    pub password: SecretString,
+    /// 按生成方式计算的熵（位）
+    pub entropy_bits: f64,
+}
+
+/// 命名的生成规则，可关联到分组
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordPolicy {
+    pub name: String,
+    pub options: PasswordGeneratorOptions,
+}
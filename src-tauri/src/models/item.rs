@@ -0,0 +1,227 @@
+//! 条目类型数据模型
+//!
+//! 登录（`passwords`）与安全笔记（`secure_records`）沿用原有表；
+//! 其余结构化类型存于 `vault_items`，各字段按类型定义存于 `vault_item_fields`。
+
+use super::SecretString;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemType {
+    Login,
+    SecureNote,
+    Card,
+    Identity,
+    SshKey,
+    Wifi,
+    ApiCredential,
+}
+
+impl ItemType {
+    pub const ALL: [ItemType; 7] = [
+        ItemType::Login,
+        ItemType::SecureNote,
+        ItemType::Card,
+        ItemType::Identity,
+        ItemType::SshKey,
+        ItemType::Wifi,
+        ItemType::ApiCredential,
+    ];
+
+    /// 数据库中使用的名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ItemType::Login => "login",
+            ItemType::SecureNote => "secure_note",
+            ItemType::Card => "card",
+            ItemType::Identity => "identity",
+            ItemType::SshKey => "ssh_key",
+            ItemType::Wifi => "wifi",
+            ItemType::ApiCredential => "api_credential",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        ItemType::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+
+    /// 是否为存于 `vault_items` 的结构化类型
+    pub fn is_structured(self) -> bool {
+        !matches!(self, ItemType::Login | ItemType::SecureNote)
+    }
+
+    /// 结构化类型的字段定义；登录与安全笔记使用各自的表结构，返回空
+    pub fn fields(self) -> &'static [ItemFieldSpec] {
+        match self {
+            ItemType::Login | ItemType::SecureNote => &[],
+            ItemType::Card => CARD_FIELDS,
+            ItemType::Identity => IDENTITY_FIELDS,
+            ItemType::SshKey => SSH_KEY_FIELDS,
+            ItemType::Wifi => WIFI_FIELDS,
+            ItemType::ApiCredential => API_CREDENTIAL_FIELDS,
+        }
+    }
+
+    /// 字段定义
+    pub fn field(self, name: &str) -> Option<&'static ItemFieldSpec> {
+        self.fields().iter().find(|spec| spec.name == name)
+    }
+}
+
+/// 字段值的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemFieldKind {
+    Text,
+    Multiline,
+    Email,
+    Url,
+    /// YYYY-MM-DD
+    Date,
+    /// 银行卡号，保存为不含空格的数字
+    CardNumber,
+    /// 有效期，保存为 MM/YY
+    CardExpiry,
+    /// 纯数字，长度见字段定义
+    Digits,
+    /// 取值限于 `options`
+    Choice,
+    /// "true" / "false"
+    Boolean,
+    SshPrivateKey,
+    SshPublicKey,
+}
+
+/// 结构化类型的单个字段定义
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemFieldSpec {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub kind: ItemFieldKind,
+    pub required: bool,
+    /// 机密字段：以数据密钥加密存储
+    pub secret: bool,
+    /// `Digits` 的长度范围
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<(usize, usize)>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub options: &'static [&'static str],
+}
+
+impl ItemFieldSpec {
+    const fn new(name: &'static str, label: &'static str, kind: ItemFieldKind) -> Self {
+        Self { name, label, kind, required: false, secret: false, length: None, options: &[] }
+    }
+
+    const fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    const fn secret(mut self) -> Self {
+        self.secret = true;
+        self
+    }
+
+    const fn length(mut self, min: usize, max: usize) -> Self {
+        self.length = Some((min, max));
+        self
+    }
+
+    const fn options(mut self, options: &'static [&'static str]) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+/// Wi-Fi 加密方式
+pub const WIFI_SECURITY_OPTIONS: &[&str] = &["none", "wep", "wpa", "wpa2", "wpa3"];
+
+const CARD_FIELDS: &[ItemFieldSpec] = &[
+    ItemFieldSpec::new("cardholder", "持卡人", ItemFieldKind::Text),
+    ItemFieldSpec::new("number", "卡号", ItemFieldKind::CardNumber).required().secret(),
+    ItemFieldSpec::new("expiry", "有效期", ItemFieldKind::CardExpiry),
+    ItemFieldSpec::new("cvv", "安全码", ItemFieldKind::Digits).secret().length(3, 4),
+    ItemFieldSpec::new("pin", "PIN", ItemFieldKind::Digits).secret().length(4, 12),
+    ItemFieldSpec::new("brand", "卡组织", ItemFieldKind::Text),
+];
+
+const IDENTITY_FIELDS: &[ItemFieldSpec] = &[
+    ItemFieldSpec::new("full_name", "姓名", ItemFieldKind::Text).required(),
+    ItemFieldSpec::new("email", "邮箱", ItemFieldKind::Email),
+    ItemFieldSpec::new("phone", "电话", ItemFieldKind::Text),
+    ItemFieldSpec::new("address", "地址", ItemFieldKind::Multiline),
+    ItemFieldSpec::new("birth_date", "出生日期", ItemFieldKind::Date),
+    ItemFieldSpec::new("id_number", "证件号码", ItemFieldKind::Text).secret(),
+    ItemFieldSpec::new("passport_number", "护照号码", ItemFieldKind::Text).secret(),
+];
+
+const SSH_KEY_FIELDS: &[ItemFieldSpec] = &[
+    ItemFieldSpec::new("private_key", "私钥", ItemFieldKind::SshPrivateKey).required().secret(),
+    ItemFieldSpec::new("public_key", "公钥", ItemFieldKind::SshPublicKey),
+    ItemFieldSpec::new("passphrase", "私钥口令", ItemFieldKind::Text).secret(),
+    ItemFieldSpec::new("host", "主机", ItemFieldKind::Text),
+];
+
+const WIFI_FIELDS: &[ItemFieldSpec] = &[
+    ItemFieldSpec::new("ssid", "网络名称", ItemFieldKind::Text).required(),
+    ItemFieldSpec::new("security", "加密方式", ItemFieldKind::Choice).options(WIFI_SECURITY_OPTIONS),
+    ItemFieldSpec::new("password", "密码", ItemFieldKind::Text).secret(),
+    ItemFieldSpec::new("hidden", "隐藏网络", ItemFieldKind::Boolean),
+];
+
+const API_CREDENTIAL_FIELDS: &[ItemFieldSpec] = &[
+    ItemFieldSpec::new("service", "服务", ItemFieldKind::Text),
+    ItemFieldSpec::new("key_id", "Key ID", ItemFieldKind::Text),
+    ItemFieldSpec::new("secret", "密钥", ItemFieldKind::Multiline).required().secret(),
+    ItemFieldSpec::new("endpoint", "接口地址", ItemFieldKind::Url),
+    ItemFieldSpec::new("expires_at", "过期日期", ItemFieldKind::Date),
+];
+
+/// 结构化条目（银行卡、身份、SSH 密钥、Wi-Fi、API 凭据）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultItem {
+    pub id: Option<i64>,
+    pub item_type: ItemType,
+    pub title: String,
+    #[serde(rename = "group_id")]
+    pub group_id: Option<i64>,
+    pub notes: Option<String>,
+    pub favorite: Option<bool>,
+    /// 字段名到值；机密字段存储时为密文，返回前端前解密为明文
+    #[serde(default)]
+    pub fields: BTreeMap<String, SecretString>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// 任意类型条目的摘要，用于统一列表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemSummary {
+    pub item_type: ItemType,
+    /// 所在表中的 ID（登录为 passwords.id，安全笔记为 secure_records.id）
+    pub id: i64,
+    pub title: String,
+    #[serde(rename = "group_id")]
+    pub group_id: Option<i64>,
+    pub updated_at: Option<String>,
+}
+
+/// 回收站中的条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub item_type: ItemType,
+    pub id: i64,
+    pub title: String,
+    /// 原所在分组；分组已删除时还原到未分组
+    #[serde(rename = "group_id")]
+    pub group_id: Option<i64>,
+    pub deleted_at: String,
+}
@@ -4,8 +4,34 @@ pub mod password;
 pub mod group;
 pub mod note;
 pub mod setting;
+pub mod vault;
+pub mod security;
+pub mod secret;
+pub mod field_policy;
+pub mod totp;
+pub mod custom_field;
+pub mod attachment;
+pub mod item;
+pub mod revision;
+pub mod audit;
+pub mod breach;
+pub mod generator;
+pub mod search;
 
 pub use password::*;
 pub use group::*;
 pub use note::*;
 pub use setting::*;
+pub use vault::*;
+pub use security::*;
+pub use secret::*;
+pub use field_policy::*;
+pub use totp::*;
+pub use custom_field::*;
+pub use attachment::*;
+pub use item::*;
+pub use revision::*;
+pub use audit::*;
+pub use breach::*;
+pub use generator::*;
+pub use search::*;
@@ -1,3 +1,4 @@
+use super::SecretString;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,7 +17,7 @@ pub struct SecureRecordGroup {
 pub struct SecureRecord {
     pub id: Option<i64>,
     pub title: String,
-    pub content: Option<String>,
+    pub content: Option<SecretString>,
     pub group_id: Option<i64>,
     pub pinned: Option<bool>,
     pub archived: Option<bool>,
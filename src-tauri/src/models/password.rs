@@ -1,5 +1,6 @@
 //! 密码数据模型
 
+use super::SecretString;
 use serde::{Deserialize, Serialize};
 
 /// 密码条目
@@ -9,7 +10,8 @@ pub struct Password {
     pub id: Option<i64>,
     pub title: String,
     pub username: Option<String>,
-    pub password: Option<String>,
+    /// 存储时为密文，返回前端前解密为明文
+    pub password: Option<SecretString>,
     pub url: Option<String>,
     pub notes: Option<String>,
     #[serde(rename = "group_id")]
@@ -28,11 +30,23 @@ pub struct Password {
 pub struct PasswordHistory {
     pub id: Option<i64>,
     pub password_id: i64,
-    pub old_password: String,
+    pub old_password: SecretString,
     pub changed_at: String,
     pub change_reason: Option<String>,
 }
 
+/// 密码历史保留策略（保存在 user_settings 的 `password_history.retention`）
+///
+/// 两项均为 0 时不限制；同时设置时任一条件超出即删除。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PasswordHistoryRetention {
+    /// 每个条目保留的最近记录数
+    pub max_entries: u32,
+    /// 保留的天数
+    pub max_age_days: u32,
+}
+
 /// 密码搜索结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -43,4 +57,68 @@ pub struct PasswordSearchResult {
     pub url: Option<String>,
     pub group_id: Option<i64>,
     pub group_name: Option<String>,
+    /// 相关度（0~1），越大越相关
+    pub score: f64,
+}
+
+/// 登录网址匹配规则
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlMatchRule {
+    /// 完整网址一致（忽略末尾斜杠与大小写）
+    Exact,
+    /// 主机名一致
+    #[default]
+    Host,
+    /// 可注册域名一致（如 `login.example.com` 与 `www.example.com`）
+    BaseDomain,
+}
+
+impl UrlMatchRule {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UrlMatchRule::Exact => "exact",
+            UrlMatchRule::Host => "host",
+            UrlMatchRule::BaseDomain => "base_domain",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "exact" => Some(UrlMatchRule::Exact),
+            "host" => Some(UrlMatchRule::Host),
+            "base_domain" => Some(UrlMatchRule::BaseDomain),
+            _ => None,
+        }
+    }
+}
+
+/// 密码条目的附加登录网址
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordUrl {
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub password_id: i64,
+    pub url: String,
+    #[serde(default)]
+    pub match_rule: UrlMatchRule,
+    pub sort_order: Option<i32>,
+    pub created_at: Option<String>,
+}
+
+/// 密码条目下的附加账号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordAccount {
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub password_id: i64,
+    pub label: Option<String>,
+    pub username: Option<String>,
+    /// 存储时为密文，返回前端前解密为明文
+    pub password: Option<SecretString>,
+    pub sort_order: Option<i32>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
 }
@@ -0,0 +1,32 @@
+//! 修订历史数据模型
+
+use super::{ItemType, SecretString};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 修订快照：字段名到明文值，空字段不记录
+pub type RevisionSnapshot = BTreeMap<String, SecretString>;
+
+/// 密码条目或笔记的一次修订，保存修改前的完整快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revision {
+    pub id: Option<i64>,
+    pub item_type: ItemType,
+    pub item_id: i64,
+    /// 本次修改变化的字段
+    pub changed_fields: Vec<String>,
+    /// 以数据密钥加密的快照 JSON，不返回前端
+    #[serde(skip)]
+    pub snapshot: String,
+    pub created_at: Option<String>,
+}
+
+/// 两个版本之间单个字段的差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionFieldChange {
+    pub field: String,
+    pub before: Option<SecretString>,
+    pub after: Option<SecretString>,
+}
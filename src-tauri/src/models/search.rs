@@ -0,0 +1,62 @@
+//! 全文搜索数据模型
+
+use super::{ItemType, SecretString};
+use serde::{Deserialize, Serialize};
+
+/// 参与全文搜索的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Title,
+    Username,
+    Url,
+    Tags,
+    Notes,
+    Content,
+}
+
+impl SearchField {
+    /// 排序权重：标题命中优先于正文命中
+    pub fn weight(self) -> f64 {
+        match self {
+            SearchField::Title => 3.0,
+            SearchField::Username | SearchField::Url | SearchField::Tags => 2.0,
+            SearchField::Notes | SearchField::Content => 1.0,
+        }
+    }
+}
+
+/// 全文搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub item_type: ItemType,
+    pub id: i64,
+    pub title: String,
+    #[serde(rename = "group_id")]
+    pub group_id: Option<i64>,
+    /// 相关度，越大越相关
+    pub score: f64,
+    /// 摘要所在字段
+    pub field: SearchField,
+    /// 命中位置附近的摘要（明文）
+    pub snippet: SecretString,
+    /// 摘要中需要高亮的区间，按字符计的 `[起始, 结束)`
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// 结构化查询结果（密码条目与笔记统一列出）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub item_type: ItemType,
+    pub id: i64,
+    pub title: String,
+    pub username: Option<String>,
+    #[serde(rename = "group_id")]
+    pub group_id: Option<i64>,
+    /// 从根分组到所在分组的名称
+    pub group_path: Vec<String>,
+    pub favorite: bool,
+    pub updated_at: Option<String>,
+}
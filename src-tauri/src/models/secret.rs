@@ -0,0 +1,141 @@
+//! 敏感数据包装类型
+//!
+//! 释放时清零内存，`Debug` 输出中不显示内容；只能通过 `expose` 显式读取明文。
+
+use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// 敏感字符串（密码、笔记内容、云存储密钥等）
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    pub fn new(value: String) -> Self {
+        SecretString(Zeroizing::new(value))
+    }
+
+    /// 读取明文
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        SecretString::new(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        SecretString::new(value.to_string())
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.expose())
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(SecretString::new)
+    }
+}
+
+impl ToSql for SecretString {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Borrowed(ValueRef::Text(self.expose().as_bytes())))
+    }
+}
+
+impl FromSql for SecretString {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str().map(SecretString::from)
+    }
+}
+
+/// 敏感字节（导出的备份明文等）
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretBytes(Zeroizing<Vec<u8>>);
+
+impl SecretBytes {
+    pub fn new(value: Vec<u8>) -> Self {
+        SecretBytes(Zeroizing::new(value))
+    }
+
+    /// 读取明文
+    pub fn expose(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(value: Vec<u8>) -> Self {
+        SecretBytes::new(value)
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes({} bytes)", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let secret = SecretString::from("hunter2");
+        assert_eq!(format!("{:?}", secret), "SecretString(***)");
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some(SecretString(***))");
+        assert_eq!(format!("{:?}", SecretBytes::from(b"abc".to_vec())), "SecretBytes(3 bytes)");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn test_password_debug_hides_secret() {
+        let password: crate::models::Password =
+            serde_json::from_value(serde_json::json!({ "title": "Mail", "password": "hunter2" }))
+                .unwrap();
+        let debug = format!("{:?}", password);
+        assert!(debug.contains("Mail"));
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_serde_is_transparent() {
+        let secret: SecretString = serde_json::from_str("\"s3cret\"").unwrap();
+        assert_eq!(secret.expose(), "s3cret");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"s3cret\"");
+    }
+
+    #[test]
+    fn test_sqlite_roundtrip() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE t (v TEXT)", []).unwrap();
+        conn.execute("INSERT INTO t (v) VALUES (?1)", [&SecretString::from("密文")])
+            .unwrap();
+        let value: SecretString = conn.query_row("SELECT v FROM t", [], |row| row.get(0)).unwrap();
+        assert_eq!(value.expose(), "密文");
+    }
+}
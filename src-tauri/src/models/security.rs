@@ -0,0 +1,23 @@
+//! 安全相关数据模型
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 解锁失败节流状态（持久化于 unlock_throttle 表）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnlockThrottleState {
+    /// 自上次成功解锁以来的连续失败次数
+    pub failed_attempts: u32,
+    pub cooldown_until: Option<DateTime<Utc>>,
+}
+
+/// 一次解锁尝试记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockAttempt {
+    pub id: i64,
+    pub attempted_at: String,
+    pub success: bool,
+    /// 解锁方式：master_password 等
+    pub method: String,
+}
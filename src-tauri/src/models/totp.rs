@@ -0,0 +1,64 @@
+//! 一次性密码（TOTP）数据模型
+
+use super::SecretString;
+use serde::{Deserialize, Serialize};
+
+/// TOTP 使用的 HMAC 算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TotpAlgorithm {
+    #[default]
+    #[serde(rename = "SHA1")]
+    Sha1,
+    #[serde(rename = "SHA256")]
+    Sha256,
+    #[serde(rename = "SHA512")]
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    /// otpauth URI 与数据库中使用的名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TotpAlgorithm::Sha1 => "SHA1",
+            TotpAlgorithm::Sha256 => "SHA256",
+            TotpAlgorithm::Sha512 => "SHA512",
+        }
+    }
+
+    /// 解析算法名称（不区分大小写，允许 `SHA-256` 写法）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().replace('-', "").as_str() {
+            "SHA1" => Some(TotpAlgorithm::Sha1),
+            "SHA256" => Some(TotpAlgorithm::Sha256),
+            "SHA512" => Some(TotpAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+}
+
+/// 密码条目的 TOTP 配置（password_totp 表）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordTotp {
+    pub password_id: i64,
+    /// Base32 编码的共享密钥；存储时为密文，返回前端前解密为明文
+    pub secret: SecretString,
+    pub issuer: Option<String>,
+    pub account_name: Option<String>,
+    pub algorithm: TotpAlgorithm,
+    pub digits: u32,
+    /// 时间步长（秒）
+    pub period: u64,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// 当前一次性密码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpCode {
+    pub code: String,
+    /// 当前密码剩余有效秒数
+    pub remaining_seconds: u64,
+    pub period: u64,
+}
@@ -0,0 +1,30 @@
+//! 保险库密钥数据模型
+
+use serde::{Deserialize, Serialize};
+
+/// 被包装（加密）后存储的数据密钥
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultKey {
+    /// 包装方式：master（主密码派生）、device（设备级固定密钥）
+    pub kind: String,
+    pub wrapped_key: String,
+    pub kdf_salt: Option<String>,
+    pub kdf_params: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// 尚未完成的重新加密任务（vault_rekey 表）
+///
+/// 新数据密钥由旧数据密钥包装保存；任务中断后，用旧密钥解锁即可继续完成。
+#[derive(Debug, Clone)]
+pub struct VaultRekey {
+    /// 由旧数据密钥包装的新数据密钥
+    pub wrapped_new_key: String,
+    /// 完成后写入的新数据密钥包装
+    pub new_keys: Vec<VaultKey>,
+    /// 完成后写入的主密码哈希与提示
+    pub password_hash: Option<String>,
+    pub hint: Option<String>,
+    pub started_at: Option<String>,
+}
@@ -0,0 +1,191 @@
+//! 附件加密存储
+//!
+//! 每个附件使用独立的随机文件密钥，内容按 [`CHUNK_SIZE`] 分块后以 XChaCha20-Poly1305 加密，
+//! 块序号与“是否末块”作为附加认证数据，分块被调换、替换或截断都会导致解密失败。
+//! 文件密钥由数据密钥包装后存于 `attachments.file_key`，数据密钥轮换时只需重新包装文件密钥。
+
+use crate::models::{Attachment, SecretBytes, UserSetting};
+use crate::services::database::DatabaseService;
+use crate::services::encryption::EncryptionService;
+use crate::services::vault;
+use rusqlite::Connection;
+use zeroize::Zeroizing;
+
+/// 分块大小（明文）
+pub const CHUNK_SIZE: usize = 256 * 1024;
+
+/// 单个附件大小上限在 user_settings 中的键（单位 MB）
+pub const MAX_SIZE_SETTING: &str = "attachments.max_size_mb";
+/// 未设置时的单个附件大小上限（MB）
+pub const DEFAULT_MAX_SIZE_MB: u64 = 25;
+/// 可设置的上限范围（MB）
+pub const MAX_SIZE_MB_RANGE: std::ops::RangeInclusive<u64> = 1..=512;
+
+const MAX_FILE_NAME_LEN: usize = 255;
+
+/// 读取单个附件大小上限（MB）
+pub fn max_size_mb(db: &DatabaseService) -> Result<u64, String> {
+    db.get_user_setting_in_range(MAX_SIZE_SETTING, &MAX_SIZE_MB_RANGE, DEFAULT_MAX_SIZE_MB)
+}
+
+/// 设置单个附件大小上限（MB）
+pub fn set_max_size_mb(db: &DatabaseService, max_size_mb: u64) -> Result<(), String> {
+    if !MAX_SIZE_MB_RANGE.contains(&max_size_mb) {
+        return Err(format!(
+            "附件大小上限须在 {}-{} MB 之间",
+            MAX_SIZE_MB_RANGE.start(),
+            MAX_SIZE_MB_RANGE.end()
+        ));
+    }
+    db.set_user_setting(&UserSetting {
+        id: None,
+        key: MAX_SIZE_SETTING.to_string(),
+        value: max_size_mb.to_string(),
+        r#type: Some("number".to_string()),
+        category: Some("general".to_string()),
+        description: Some("单个附件大小上限（MB）".to_string()),
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+/// 规范化文件名：去除路径部分与首尾空白
+pub fn normalize_file_name(name: &str) -> Result<String, String> {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default().trim();
+    if name.is_empty() || name == "." || name == ".." {
+        return Err("文件名不能为空".to_string());
+    }
+    if name.chars().count() > MAX_FILE_NAME_LEN {
+        return Err(format!("文件名不能超过 {} 个字符", MAX_FILE_NAME_LEN));
+    }
+    Ok(name.to_string())
+}
+
+/// 加密附件内容：生成文件密钥并写入 `attachment`，返回密文分块
+///
+/// 空文件同样产生一个（空的）末块，便于校验完整性。
+pub fn seal(
+    encryption: &EncryptionService,
+    attachment: &mut Attachment,
+    content: &[u8],
+) -> Result<Vec<Vec<u8>>, String> {
+    let file_key = vault::generate_data_key();
+    let pieces: Vec<&[u8]> = if content.is_empty() {
+        vec![content]
+    } else {
+        content.chunks(CHUNK_SIZE).collect()
+    };
+    let last = pieces.len() - 1;
+    let chunks = pieces
+        .into_iter()
+        .enumerate()
+        .map(|(index, piece)| file_key.encrypt_bytes(piece, &chunk_aad(index, index == last)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    attachment.file_key = vault::wrap_data_key(encryption, &file_key)?;
+    attachment.size = content.len() as i64;
+    attachment.chunk_count = chunks.len() as i64;
+    Ok(chunks)
+}
+
+/// 解密附件内容并校验分块数量与大小
+pub fn open(
+    encryption: &EncryptionService,
+    attachment: &Attachment,
+    chunks: &[Vec<u8>],
+) -> Result<SecretBytes, String> {
+    if chunks.is_empty() || chunks.len() as i64 != attachment.chunk_count {
+        return Err("附件数据不完整".to_string());
+    }
+    let (file_key, _) = vault::unwrap_data_key(encryption, &attachment.file_key)
+        .map_err(|_| "附件密钥解包失败".to_string())?;
+
+    let last = chunks.len() - 1;
+    let mut content = Zeroizing::new(Vec::with_capacity(attachment.size.max(0) as usize));
+    for (index, chunk) in chunks.iter().enumerate() {
+        let plain = file_key
+            .decrypt_bytes(chunk, &chunk_aad(index, index == last))
+            .map_err(|_| format!("附件分块 {} 解密失败", index))?;
+        content.extend_from_slice(plain.expose());
+    }
+    if content.len() as i64 != attachment.size {
+        return Err("附件大小与记录不符".to_string());
+    }
+    Ok(SecretBytes::new(std::mem::take(&mut *content)))
+}
+
+/// 在给定连接/事务上加密并写入附件，返回新 ID
+pub fn store(
+    conn: &Connection,
+    encryption: &EncryptionService,
+    attachment: &mut Attachment,
+    content: &[u8],
+) -> Result<i64, String> {
+    let chunks = seal(encryption, attachment, content)?;
+    DatabaseService::write_attachment(conn, attachment, &chunks).map_err(|e| e.to_string())
+}
+
+/// 读取并解密附件内容
+pub fn read(
+    db: &DatabaseService,
+    encryption: &EncryptionService,
+    attachment: &Attachment,
+) -> Result<SecretBytes, String> {
+    let id = attachment.id.ok_or("Attachment ID is missing")?;
+    open(encryption, attachment, &db.get_attachment_chunks(id)?)
+}
+
+/// 分块的附加认证数据：8 字节小端块序号 + 末块标记
+fn chunk_aad(index: usize, is_last: bool) -> [u8; 9] {
+    let mut aad = [0u8; 9];
+    aad[..8].copy_from_slice(&(index as u64).to_le_bytes());
+    aad[8] = is_last as u8;
+    aad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_roundtrip_across_chunks() {
+        let encryption = EncryptionService::new("test_key");
+        let content: Vec<u8> = (0..CHUNK_SIZE * 2 + 17).map(|i| (i % 251) as u8).collect();
+        let mut attachment = Attachment::default();
+        let chunks = seal(&encryption, &mut attachment, &content).unwrap();
+        assert_eq!((chunks.len(), attachment.chunk_count), (3, 3));
+        assert_eq!(attachment.size, content.len() as i64);
+        assert_eq!(open(&encryption, &attachment, &chunks).unwrap().expose(), content.as_slice());
+
+        let mut empty = Attachment::default();
+        let chunks = seal(&encryption, &mut empty, b"").unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(open(&encryption, &empty, &chunks).unwrap().expose().is_empty());
+    }
+
+    #[test]
+    fn test_open_rejects_reordered_or_truncated_chunks() {
+        let encryption = EncryptionService::new("test_key");
+        let content = vec![7u8; CHUNK_SIZE * 2];
+        let mut attachment = Attachment::default();
+        let chunks = seal(&encryption, &mut attachment, &content).unwrap();
+
+        let swapped = vec![chunks[1].clone(), chunks[0].clone()];
+        assert!(open(&encryption, &attachment, &swapped).is_err());
+
+        let mut truncated = attachment.clone();
+        truncated.chunk_count = 1;
+        truncated.size = CHUNK_SIZE as i64;
+        assert!(open(&encryption, &truncated, &chunks[..1]).is_err());
+
+        assert!(open(&EncryptionService::new("other_key"), &attachment, &chunks).is_err());
+    }
+
+    #[test]
+    fn test_normalize_file_name() {
+        assert_eq!(normalize_file_name(" C:\\keys\\id_ed25519 ").unwrap(), "id_ed25519");
+        assert_eq!(normalize_file_name("../../etc/passwd").unwrap(), "passwd");
+        assert!(normalize_file_name("dir/").is_err());
+        assert!(normalize_file_name(&"a".repeat(256)).is_err());
+    }
+}
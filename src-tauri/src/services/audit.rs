@@ -0,0 +1,365 @@
+//! 密码健康检查
+//!
+//! 在后端解密全部密码条目，检查强度、重复使用、密码年龄与网址问题，并按分组汇总得分。
+//! 重复使用按带密钥的 HMAC-SHA256 比较，密钥在每次检查时随机生成；明文与哈希都不会返回前端。
+
+use crate::models::{
+    AuditFinding, EncryptableField, GroupAuditSummary, PasswordAuditEntry, PasswordAuditReport, SecretString,
+};
+use crate::services::database::DatabaseService;
+use crate::services::encryption::EncryptionService;
+use crate::services::field_encryption::FieldCipher;
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use std::collections::{BTreeMap, HashMap};
+
+/// 默认的密码最长使用天数
+pub const DEFAULT_MAX_AGE_DAYS: u32 = 365;
+
+/// 常见弱密码（小写，去掉末尾数字与符号后比较）
+const COMMON_PASSWORDS: &[&str] = &[
+    "password", "passw0rd", "qwerty", "qwertyuiop", "asdfgh", "abc", "abcdef", "admin", "root", "letmein",
+    "welcome", "iloveyou", "monkey", "dragon", "master", "login", "123456", "12345678", "111111", "000000",
+];
+
+/// 健康检查的单个输入（密码与网址均为明文）
+pub struct AuditInput {
+    pub id: i64,
+    pub title: String,
+    pub group_id: Option<i64>,
+    pub password: Option<SecretString>,
+    /// 密码密文存在但无法解密
+    pub undecryptable: bool,
+    pub urls: Vec<String>,
+    pub age_days: Option<i64>,
+}
+
+/// 估算密码熵（位）与 0-4 的强度评分
+///
+/// 按出现的字符类别确定字符池大小；与前一字符相同或相邻（如 `aa`、`ab`、`21`）的字符只计半个字符，
+/// 常见弱密码最多计 10 位。
+pub fn estimate_strength(password: &str) -> (f64, u8) {
+    let chars: Vec<char> = password.chars().collect();
+    if chars.is_empty() {
+        return (0.0, 0);
+    }
+    let mut pool = 0u32;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+
+    let effective: f64 = chars
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let patterned = i > 0 && (*c as i64 - chars[i - 1] as i64).abs() <= 1;
+            if patterned { 0.5 } else { 1.0 }
+        })
+        .sum();
+    let mut bits = effective * f64::from(pool.max(1)).log2();
+
+    let lower = password.to_lowercase();
+    let base = lower.trim_end_matches(|c: char| c.is_ascii_digit() || c.is_ascii_punctuation());
+    if COMMON_PASSWORDS.contains(&lower.as_str()) || (!base.is_empty() && COMMON_PASSWORDS.contains(&base)) {
+        bits = bits.min(10.0);
+    }
+
+    let score = match bits {
+        b if b < 28.0 => 0,
+        b if b < 36.0 => 1,
+        b if b < 60.0 => 2,
+        b if b < 80.0 => 3,
+        _ => 4,
+    };
+    (bits, score)
+}
+
+fn entry_score(findings: &[AuditFinding], strength: u8) -> u8 {
+    if findings.contains(&AuditFinding::EmptyPassword) || findings.contains(&AuditFinding::Undecryptable) {
+        return 0;
+    }
+    let mut score = 100i32 - (4 - i32::from(strength)) * 15;
+    for finding in findings {
+        score -= match finding {
+            AuditFinding::ReusedPassword => 30,
+            AuditFinding::OldPassword => 10,
+            AuditFinding::InsecureUrl => 10,
+            AuditFinding::MissingUrl => 5,
+            AuditFinding::EmptyPassword | AuditFinding::Undecryptable | AuditFinding::WeakPassword => 0,
+        };
+    }
+    score.clamp(0, 100) as u8
+}
+
+fn average(scores: impl Iterator<Item = u8>) -> u8 {
+    let (sum, count) = scores.fold((0u64, 0u64), |(sum, count), s| (sum + u64::from(s), count + 1));
+    sum.checked_div(count).map_or(100, |avg| avg as u8)
+}
+
+/// 根据明文输入生成报告；`group_names` 用于分组汇总的名称
+pub fn build_report(
+    inputs: Vec<AuditInput>,
+    group_names: &HashMap<i64, String>,
+    max_age_days: u32,
+) -> PasswordAuditReport {
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    let digest = |password: &str| {
+        let mut mac = <Hmac<sha2::Sha256> as KeyInit>::new_from_slice(&key).expect("HMAC accepts any key length");
+        mac.update(password.as_bytes());
+        mac.finalize().into_bytes()
+    };
+
+    let digests: Vec<_> = inputs
+        .iter()
+        .map(|input| input.password.as_ref().filter(|p| !p.is_empty()).map(|p| digest(p.expose())))
+        .collect();
+    let mut by_digest: HashMap<_, Vec<i64>> = HashMap::new();
+    for (input, digest) in inputs.iter().zip(&digests) {
+        if let Some(digest) = digest {
+            by_digest.entry(*digest).or_default().push(input.id);
+        }
+    }
+
+    let mut entries: Vec<PasswordAuditEntry> = inputs
+        .into_iter()
+        .zip(digests)
+        .map(|(input, digest)| {
+            let mut findings = Vec::new();
+            let (entropy_bits, strength) = match input.password.as_ref().filter(|p| !p.is_empty()) {
+                Some(password) => estimate_strength(password.expose()),
+                None if input.undecryptable => {
+                    findings.push(AuditFinding::Undecryptable);
+                    (0.0, 0)
+                }
+                None => {
+                    findings.push(AuditFinding::EmptyPassword);
+                    (0.0, 0)
+                }
+            };
+            if digest.is_some() && strength < 3 {
+                findings.push(AuditFinding::WeakPassword);
+            }
+            let reused_with: Vec<i64> = digest
+                .and_then(|d| by_digest.get(&d))
+                .map(|ids| ids.iter().copied().filter(|id| *id != input.id).collect())
+                .unwrap_or_default();
+            if !reused_with.is_empty() {
+                findings.push(AuditFinding::ReusedPassword);
+            }
+            if digest.is_some() && max_age_days > 0 && input.age_days.is_some_and(|d| d > i64::from(max_age_days)) {
+                findings.push(AuditFinding::OldPassword);
+            }
+            let urls: Vec<&str> = input.urls.iter().map(|u| u.trim()).filter(|u| !u.is_empty()).collect();
+            if urls.is_empty() {
+                findings.push(AuditFinding::MissingUrl);
+            } else if urls.iter().any(|u| u.to_ascii_lowercase().starts_with("http://")) {
+                findings.push(AuditFinding::InsecureUrl);
+            }
+            let score = entry_score(&findings, strength);
+            PasswordAuditEntry {
+                id: input.id,
+                title: input.title,
+                group_id: input.group_id,
+                entropy_bits: (entropy_bits * 10.0).round() / 10.0,
+                strength,
+                reused_with,
+                age_days: input.age_days,
+                findings,
+                score,
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.title.cmp(&b.title)));
+
+    let mut by_group: BTreeMap<Option<i64>, Vec<&PasswordAuditEntry>> = BTreeMap::new();
+    for entry in &entries {
+        by_group.entry(entry.group_id).or_default().push(entry);
+    }
+    let count = |entries: &[&PasswordAuditEntry], finding| entries.iter().filter(|e| e.findings.contains(&finding)).count();
+    let groups = by_group
+        .into_iter()
+        .map(|(group_id, members)| GroupAuditSummary {
+            group_id,
+            group_name: group_id.and_then(|id| group_names.get(&id).cloned()),
+            total: members.len(),
+            weak: count(&members, AuditFinding::WeakPassword),
+            reused: count(&members, AuditFinding::ReusedPassword),
+            old: count(&members, AuditFinding::OldPassword),
+            score: average(members.iter().map(|e| e.score)),
+        })
+        .collect();
+
+    PasswordAuditReport {
+        score: average(entries.iter().map(|e| e.score)),
+        entries,
+        groups,
+    }
+}
+
+/// 解密全部密码条目并生成报告；`max_age_days` 为 0 时不检查密码年龄
+pub fn run(
+    db: &DatabaseService,
+    encryption: &EncryptionService,
+    cipher: &FieldCipher,
+    max_age_days: u32,
+) -> Result<PasswordAuditReport, String> {
+    let ages = db.get_password_ages()?;
+    let mut urls: HashMap<i64, Vec<String>> = HashMap::new();
+    for saved in db.get_all_password_urls()? {
+        if let Some(url) = cipher.decrypt_value(EncryptableField::PasswordUrl, Some(saved.url)) {
+            urls.entry(saved.password_id).or_default().push(url);
+        }
+    }
+    let group_names: HashMap<i64, String> = db
+        .get_groups()?
+        .into_iter()
+        .filter_map(|g| g.id.map(|id| (id, g.name)))
+        .collect();
+
+    let inputs = db
+        .get_passwords(None)?
+        .into_iter()
+        .filter_map(|p| {
+            let id = p.id?;
+            let mut undecryptable = false;
+            let password = match p.password.filter(|c| !c.is_empty()) {
+                Some(ciphertext) => match encryption.decrypt(ciphertext.expose()) {
+                    Ok(plain) => Some(plain),
+                    Err(e) => {
+                        log::warn!("Cannot decrypt password {} in audit: {}", id, e);
+                        undecryptable = true;
+                        None
+                    }
+                },
+                None => None,
+            };
+            let mut entry_urls = urls.remove(&id).unwrap_or_default();
+            entry_urls.extend(cipher.decrypt_value(EncryptableField::PasswordUrl, p.url));
+            Some(AuditInput {
+                id,
+                title: p.title,
+                group_id: p.group_id,
+                password,
+                undecryptable,
+                urls: entry_urls,
+                age_days: ages.get(&id).copied(),
+            })
+        })
+        .collect();
+    Ok(build_report(inputs, &group_names, max_age_days))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(id: i64, password: &str, url: &str, group_id: Option<i64>) -> AuditInput {
+        AuditInput {
+            id,
+            title: format!("entry {}", id),
+            group_id,
+            password: Some(password.into()).filter(|p: &SecretString| !p.is_empty()),
+            undecryptable: false,
+            urls: vec![url.to_string()],
+            age_days: Some(10),
+        }
+    }
+
+    #[test]
+    fn test_estimate_strength() {
+        assert_eq!(estimate_strength(""), (0.0, 0));
+        assert_eq!(estimate_strength("Password123!").1, 0);
+        assert!(estimate_strength("abcdefgh").1 <= 1);
+        assert!(estimate_strength("aaaaaaaaaaaa").0 < estimate_strength("q8wz1mtr4kx0").0);
+        assert_eq!(estimate_strength("vR7#kL2!pQ9@xW4$").1, 4);
+    }
+
+    #[test]
+    fn test_report_flags_reuse_age_and_urls() {
+        let mut inputs = vec![
+            input(1, "vR7#kL2!pQ9@xW4$", "https://a.example", Some(7)),
+            input(2, "vR7#kL2!pQ9@xW4$", "http://b.example", Some(7)),
+            input(3, "Tq5!mZ8#rW2$yN6&", "", None),
+            input(4, "", "https://d.example", None),
+        ];
+        inputs[2].age_days = Some(400);
+        let groups = HashMap::from([(7, "Work".to_string())]);
+        let report = build_report(inputs, &groups, DEFAULT_MAX_AGE_DAYS);
+
+        let entry = |id| report.entries.iter().find(|e| e.id == id).unwrap();
+        assert_eq!(entry(1).reused_with, vec![2]);
+        assert_eq!(entry(1).findings, vec![AuditFinding::ReusedPassword]);
+        assert!(entry(2).findings.contains(&AuditFinding::InsecureUrl));
+        assert_eq!(entry(3).findings, vec![AuditFinding::OldPassword, AuditFinding::MissingUrl]);
+        assert_eq!(entry(4).findings, vec![AuditFinding::EmptyPassword]);
+        assert_eq!(report.entries[0].id, 4);
+
+        let work = report.groups.iter().find(|g| g.group_id == Some(7)).unwrap();
+        assert_eq!((work.group_name.as_deref(), work.total, work.reused), (Some("Work"), 2, 2));
+        assert!(work.score < 100);
+    }
+
+    #[test]
+    fn test_run_audits_ten_thousand_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseService::new(dir.path().join("test.db").to_str().unwrap());
+        db.initialize().unwrap();
+        let encryption = EncryptionService::new("test_key");
+        let cipher = FieldCipher::new(Default::default(), EncryptionService::new("test_key"));
+
+        let mut conn = db.get_connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        for i in 0..10_000 {
+            let password = encryption.encrypt(&format!("pw-{}-{}", i % 5_000, "Xy7!")).unwrap();
+            tx.execute(
+                "INSERT INTO passwords (title, password, url, updated_at) VALUES (?1, ?2, 'https://example.com', datetime('now'))",
+                (format!("entry {}", i), password),
+            )
+            .unwrap();
+        }
+        tx.commit().unwrap();
+
+        let started = std::time::Instant::now();
+        let report = run(&db, &encryption, &cipher, DEFAULT_MAX_AGE_DAYS).unwrap();
+        assert_eq!(report.entries.len(), 10_000);
+        assert!(report.entries.iter().all(|e| e.reused_with.len() == 1));
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_run_reports_undecryptable_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseService::new(dir.path().join("test.db").to_str().unwrap());
+        db.initialize().unwrap();
+        let encryption = EncryptionService::new("test_key");
+        let cipher = FieldCipher::new(Default::default(), EncryptionService::new("test_key"));
+
+        let conn = db.get_connection().unwrap();
+        let foreign = EncryptionService::new("other_key").encrypt("vR7#kL2!pQ9@xW4$").unwrap();
+        conn.execute(
+            "INSERT INTO passwords (title, password, url) VALUES ('Broken', ?1, 'https://example.com')",
+            [foreign],
+        )
+        .unwrap();
+        drop(conn);
+
+        let report = run(&db, &encryption, &cipher, DEFAULT_MAX_AGE_DAYS).unwrap();
+        assert_eq!(report.entries.len(), 1);
+        assert_eq!(report.entries[0].findings, vec![AuditFinding::Undecryptable]);
+        assert_eq!((report.entries[0].score, report.score), (0, 0));
+    }
+}
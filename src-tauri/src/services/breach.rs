@@ -0,0 +1,180 @@
+//! 离线泄露密码检查
+//!
+//! 从本地导入 HIBP 格式的哈希数据集后，检查密码是否出现在泄露数据中，整个过程不联网。
+//! 支持两种数据文件：
+//! - 完整哈希文件：每行 `HASH[:COUNT]`，按哈希长度识别 SHA-1（40 位）或 NTLM（32 位）
+//! - k-匿名范围目录：每个文件名为 5 位十六进制前缀（如 `5BAA6.txt`），每行 `SUFFIX:COUNT`
+
+use crate::models::{BreachHash, BreachHashKind, BreachImportReport};
+use crate::services::database::DatabaseService;
+use md4::Md4;
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// 每批写入数据库的哈希数量
+const IMPORT_BATCH_SIZE: usize = 50_000;
+
+/// 范围文件名（前缀）的长度
+const RANGE_PREFIX_LEN: usize = 5;
+
+/// UTF-8 明文的 SHA-1
+pub fn sha1_hash(password: &str) -> Vec<u8> {
+    Sha1::digest(password.as_bytes()).to_vec()
+}
+
+/// NTLM 哈希：UTF-16LE 明文的 MD4
+pub fn ntlm_hash(password: &str) -> Vec<u8> {
+    let utf16 = Zeroizing::new(
+        password
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<u8>>(),
+    );
+    Md4::digest(utf16.as_slice()).to_vec()
+}
+
+/// 解析一行 `HEX[:COUNT]`；`prefix` 为范围文件的前缀。返回 `None` 表示无法识别
+fn parse_line(line: &str, prefix: &str) -> Option<BreachHash> {
+    let line = line.trim();
+    let (hex_part, count) = match line.split_once(':') {
+        Some((hash, count)) => (hash.trim(), count.trim().parse::<i64>().ok()?),
+        None => (line, 1),
+    };
+    let full = format!("{}{}", prefix, hex_part);
+    let kind = BreachHashKind::from_hex_len(full.len())?;
+    let hash = hex::decode(&full).ok()?;
+    Some(BreachHash { kind, hash, count })
+}
+
+/// 逐行读取一个文件并分批写入
+fn import_file(
+    db: &DatabaseService,
+    path: &Path,
+    prefix: &str,
+    report: &mut BreachImportReport,
+) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("无法读取 {}: {}", path.display(), e))?;
+    let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(&line, prefix) {
+            // 范围接口的填充行出现次数为 0，不是真实哈希
+            Some(entry) if entry.count == 0 => {}
+            Some(entry) => batch.push(entry),
+            None => report.skipped += 1,
+        }
+        if batch.len() >= IMPORT_BATCH_SIZE {
+            report.imported += db.insert_breach_hashes(&batch)?;
+            batch.clear();
+        }
+    }
+    report.imported += db.insert_breach_hashes(&batch)?;
+    Ok(())
+}
+
+/// 导入数据集：`path` 为完整哈希文件或范围文件目录
+pub fn import(db: &DatabaseService, path: &Path) -> Result<BreachImportReport, String> {
+    let mut report = BreachImportReport::default();
+    if !path.is_dir() {
+        import_file(db, path, "", &mut report)?;
+        return Ok(report);
+    }
+
+    let mut files: Vec<_> = std::fs::read_dir(path)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    for file in files {
+        let prefix = file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|s| s.len() == RANGE_PREFIX_LEN && s.chars().all(|c| c.is_ascii_hexdigit()));
+        match prefix {
+            Some(prefix) => import_file(db, &file, prefix, &mut report)?,
+            None => log::warn!("Skipping non-range file in breach dataset: {}", file.display()),
+        }
+    }
+    Ok(report)
+}
+
+/// 检查一组明文密码，返回每个密码在泄露数据中出现的次数（SHA-1 与 NTLM 取较大值）
+pub fn check(db: &DatabaseService, passwords: &[&str]) -> Result<Vec<Option<i64>>, String> {
+    let queries: Vec<(BreachHashKind, Vec<u8>)> = passwords
+        .iter()
+        .flat_map(|p| BreachHashKind::ALL.map(|kind| (kind, hash(kind, p))))
+        .collect();
+    let counts = db.find_breach_counts(&queries)?;
+    Ok(counts
+        .chunks(BreachHashKind::ALL.len())
+        .map(|pair| pair.iter().flatten().copied().max())
+        .collect())
+}
+
+fn hash(kind: BreachHashKind, password: &str) -> Vec<u8> {
+    match kind {
+        BreachHashKind::Sha1 => sha1_hash(password),
+        BreachHashKind::Ntlm => ntlm_hash(password),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA1_SAMPLE: &str = include_str!("../../tests/fixtures/breach/pwned-sha1-sample.txt");
+    const NTLM_RANGE_SAMPLE: &str = include_str!("../../tests/fixtures/breach/ntlm-range/8846F.txt");
+
+    #[test]
+    fn test_md4_and_ntlm_vectors() {
+        assert_eq!(hex::encode(Md4::digest(b"")), "31d6cfe0d16ae931b73c59d7e0c089c0");
+        assert_eq!(hex::encode(Md4::digest(b"abc")), "a448017aaf21d8525fc10ae87aa6729d");
+        assert_eq!(
+            hex::encode(Md4::digest(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890")),
+            "e33b4ddc9c38f2199c3e7b164fcc0536"
+        );
+        assert_eq!(hex::encode_upper(ntlm_hash("password")), "8846F7EAEE8FB117AD06BDD830B7586C");
+        assert_eq!(
+            hex::encode_upper(sha1_hash("password")),
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"
+        );
+    }
+
+    #[test]
+    fn test_import_fixtures_and_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseService::new(dir.path().join("test.db").to_str().unwrap());
+        db.initialize().unwrap();
+
+        let sha1_file = dir.path().join("pwned-sha1-sample.txt");
+        std::fs::write(&sha1_file, SHA1_SAMPLE).unwrap();
+        let report = import(&db, &sha1_file).unwrap();
+        assert_eq!((report.imported, report.skipped), (8, 1));
+
+        let range_dir = dir.path().join("ntlm-range");
+        std::fs::create_dir(&range_dir).unwrap();
+        std::fs::write(range_dir.join("8846F.txt"), NTLM_RANGE_SAMPLE).unwrap();
+        std::fs::write(range_dir.join("README"), "ignored").unwrap();
+        let report = import(&db, &range_dir).unwrap();
+        assert_eq!((report.imported, report.skipped), (5, 0));
+
+        let info = db.get_breach_dataset_info().unwrap();
+        assert_eq!((info.sha1_count, info.ntlm_count), (8, 5));
+
+        let counts = check(&db, &["password", "123456", "correct horse battery staple"]).unwrap();
+        assert_eq!(counts, vec![Some(9_545_824), Some(37_359_195), None]);
+
+        // 重复导入不会重复计数
+        import(&db, &sha1_file).unwrap();
+        assert_eq!(db.get_breach_dataset_info().unwrap().sha1_count, 8);
+        db.clear_breach_hashes().unwrap();
+        assert_eq!(check(&db, &["password"]).unwrap(), vec![None]);
+    }
+}
@@ -0,0 +1,138 @@
+//! 密码条目自定义字段
+//!
+//! 隐藏字段与一次性密码字段以数据密钥加密存储在 `secret_value` 列，
+//! 其余类型明文存储在 `value` 列，可被 `search_passwords` 匹配。
+
+use crate::models::{CustomField, CustomFieldType};
+use crate::services::encryption::EncryptionService;
+use crate::services::totp;
+
+/// 标签最大长度（字符）
+const MAX_LABEL_LEN: usize = 100;
+
+/// 按字段类型校验标签与取值；空值总是允许
+pub fn validate(field: &CustomField) -> Result<(), String> {
+    let label = field.label.trim();
+    if label.is_empty() {
+        return Err("自定义字段名称不能为空".to_string());
+    }
+    if label.chars().count() > MAX_LABEL_LEN {
+        return Err(format!("自定义字段名称不能超过 {} 个字符", MAX_LABEL_LEN));
+    }
+
+    let value = field.value.expose().trim();
+    if value.is_empty() {
+        return Ok(());
+    }
+    match field.field_type {
+        CustomFieldType::Text | CustomFieldType::Hidden => Ok(()),
+        CustomFieldType::Url => {
+            if is_valid_url(value) {
+                Ok(())
+            } else {
+                Err(format!("字段 '{}' 不是有效的网址", label))
+            }
+        }
+        CustomFieldType::Email => {
+            if is_valid_email(value) {
+                Ok(())
+            } else {
+                Err(format!("字段 '{}' 不是有效的邮箱地址", label))
+            }
+        }
+        CustomFieldType::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(|_| ())
+            .map_err(|_| format!("字段 '{}' 的日期格式应为 YYYY-MM-DD", label)),
+        CustomFieldType::Otp => {
+            if value.starts_with("otpauth://") {
+                totp::parse_otpauth_uri(value).map(|_| ())
+            } else {
+                totp::normalize_secret(value).map(|_| ())
+            }
+        }
+    }
+}
+
+/// 形如 `scheme://rest` 的网址
+pub(crate) fn is_valid_url(value: &str) -> bool {
+    value
+        .split_once("://")
+        .is_some_and(|(scheme, rest)| !scheme.is_empty() && !rest.is_empty())
+}
+
+/// 宽松的邮箱格式检查：`local@domain.tld`，不含空白
+pub(crate) fn is_valid_email(value: &str) -> bool {
+    let valid = value.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.')
+    });
+    valid && !value.contains(char::is_whitespace)
+}
+
+/// 机密字段加密取值，其余原样返回
+pub fn encrypt_field(encryption: &EncryptionService, field: &mut CustomField) -> Result<(), String> {
+    if field.field_type.is_secret() && !field.value.is_empty() {
+        field.value = encryption.encrypt(field.value.expose())?.into();
+    }
+    Ok(())
+}
+
+/// 机密字段解密取值；解密失败时保留原值
+pub fn decrypt_field(encryption: &EncryptionService, field: &mut CustomField) {
+    if field.field_type.is_secret() && !field.value.is_empty() {
+        match encryption.decrypt(field.value.expose()) {
+            Ok(plain) => field.value = plain,
+            Err(e) => log::warn!("解密自定义字段失败 (id={:?}): {}", field.id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(field_type: CustomFieldType, value: &str) -> CustomField {
+        CustomField {
+            id: None,
+            password_id: 1,
+            field_type,
+            label: "Field".to_string(),
+            value: value.into(),
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_by_type() {
+        assert!(validate(&field(CustomFieldType::Text, "anything")).is_ok());
+        assert!(validate(&field(CustomFieldType::Url, "https://example.com/login")).is_ok());
+        assert!(validate(&field(CustomFieldType::Url, "example")).is_err());
+        assert!(validate(&field(CustomFieldType::Email, "ops@example.com")).is_ok());
+        assert!(validate(&field(CustomFieldType::Email, "ops@localhost")).is_err());
+        assert!(validate(&field(CustomFieldType::Date, "2024-02-29")).is_ok());
+        assert!(validate(&field(CustomFieldType::Date, "2023-02-29")).is_err());
+        assert!(validate(&field(CustomFieldType::Otp, "JBSWY3DPEHPK3PXP")).is_ok());
+        assert!(validate(&field(CustomFieldType::Otp, "otpauth://totp/a?secret=JBSWY3DPEHPK3PXP")).is_ok());
+        assert!(validate(&field(CustomFieldType::Otp, "123456")).is_err());
+        // 空值允许，空标签不允许
+        assert!(validate(&field(CustomFieldType::Date, "")).is_ok());
+        let mut unnamed = field(CustomFieldType::Text, "x");
+        unnamed.label = "  ".to_string();
+        assert!(validate(&unnamed).is_err());
+    }
+
+    #[test]
+    fn test_only_secret_fields_are_encrypted() {
+        let key = crate::services::vault::generate_data_key();
+        let mut hidden = field(CustomFieldType::Hidden, "pin 1234");
+        encrypt_field(&key, &mut hidden).unwrap();
+        assert_ne!(hidden.value.expose(), "pin 1234");
+        decrypt_field(&key, &mut hidden);
+        assert_eq!(hidden.value.expose(), "pin 1234");
+
+        let mut email = field(CustomFieldType::Email, "ops@example.com");
+        encrypt_field(&key, &mut email).unwrap();
+        assert_eq!(email.value.expose(), "ops@example.com");
+    }
+}
@@ -6,6 +6,11 @@ use rusqlite::{Connection, OptionalExtension, Result};
 use std::collections::HashSet;
 use std::path::Path;
 
+/// 保留的解锁尝试记录条数
+const UNLOCK_ATTEMPT_RETENTION: u32 = 200;
+/// 支持移入回收站的表
+const TRASHABLE_TABLES: [&str; 3] = ["passwords", "secure_records", "vault_items"];
+
 /// 数据库服务
 pub struct DatabaseService {
     pub db_path: String,
@@ -28,11 +33,11 @@ impl DatabaseService {
     ) -> Result<Vec<crate::models::password::Password>, String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
 
-        let mut stmt = if let Some(_) = group_id {
-            conn.prepare("SELECT id, title, username, password, url, notes, group_id, created_at, updated_at, last_used_at, use_count, favorite, tags FROM passwords WHERE group_id = ? ORDER BY title")
+        let mut stmt = if group_id.is_some() {
+            conn.prepare("SELECT id, title, username, password, url, notes, group_id, created_at, updated_at, last_used_at, use_count, favorite, tags FROM passwords WHERE group_id = ? AND deleted_at IS NULL ORDER BY title")
                 .map_err(|e| e.to_string())?
         } else {
-            conn.prepare("SELECT id, title, username, password, url, notes, group_id, created_at, updated_at, last_used_at, use_count, favorite, tags FROM passwords ORDER BY title")
+            conn.prepare("SELECT id, title, username, password, url, notes, group_id, created_at, updated_at, last_used_at, use_count, favorite, tags FROM passwords WHERE deleted_at IS NULL ORDER BY title")
                 .map_err(|e| e.to_string())?
         };
 
@@ -59,7 +64,7 @@ impl DatabaseService {
     ) -> Result<Option<crate::models::password::Password>, String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
 
-        let mut stmt = conn.prepare("SELECT id, title, username, password, url, notes, group_id, created_at, updated_at, last_used_at, use_count, favorite, tags FROM passwords WHERE id = ?")
+        let mut stmt = conn.prepare("SELECT id, title, username, password, url, notes, group_id, created_at, updated_at, last_used_at, use_count, favorite, tags FROM passwords WHERE id = ? AND deleted_at IS NULL")
             .map_err(|e| e.to_string())?;
 
         // 使用 query_map 获取 iterator
@@ -133,160 +138,1289 @@ impl DatabaseService {
             ).map_err(|e| e.to_string())?;
             Ok(())
         } else {
-            Err("Password ID is missing".to_string())
+            Err("Password ID is missing".to_string())
+        }
+    }
+
+    /// 删除密码（移入回收站）
+    pub fn delete_password(&self, id: i64) -> Result<(), String> {
+        self.move_to_trash("passwords", id)
+    }
+
+    /// 彻底删除密码（连同其历史记录、TOTP 配置、自定义字段、附加网址、附加账号与附件）
+    fn purge_password(conn: &Connection, id: i64) -> Result<()> {
+        Self::delete_owned_attachments(conn, "password_id", id)?;
+        for table in [
+            "password_history",
+            "password_totp",
+            "password_custom_fields",
+            "password_urls",
+            "password_accounts",
+        ] {
+            conn.execute(&format!("DELETE FROM {table} WHERE password_id = ?"), [id])?;
+        }
+        conn.execute("DELETE FROM revisions WHERE item_type = 'login' AND item_id = ?", [id])?;
+        conn.execute("DELETE FROM passwords WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    /// 自定义字段或附加网址匹配关键字的密码 ID
+    pub fn search_related_password_ids(
+        &self,
+        keyword: &str,
+        cipher: &crate::services::field_encryption::FieldCipher,
+    ) -> Result<HashSet<i64>, String> {
+        let mut ids = self.search_custom_field_password_ids(keyword)?;
+        ids.extend(self.search_url_password_ids(keyword, cipher)?);
+        Ok(ids)
+    }
+
+    /// 附加网址匹配关键字的密码 ID；网址加密存储时解密后在内存中匹配
+    fn search_url_password_ids(
+        &self,
+        keyword: &str,
+        cipher: &crate::services::field_encryption::FieldCipher,
+    ) -> Result<HashSet<i64>, String> {
+        use crate::models::EncryptableField;
+        use crate::services::field_encryption::like_contains;
+
+        if !cipher.policy.password_url {
+            let conn = self.get_connection().map_err(|e| e.to_string())?;
+            let mut stmt = conn
+                .prepare("SELECT DISTINCT password_id FROM password_urls WHERE url LIKE ?1")
+                .map_err(|e| e.to_string())?;
+            let iter = stmt
+                .query_map([format!("%{}%", keyword)], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            return iter.collect::<Result<HashSet<i64>>>().map_err(|e| e.to_string());
+        }
+        Ok(self
+            .get_all_password_urls()?
+            .into_iter()
+            .filter(|u| {
+                cipher
+                    .decrypt_value(EncryptableField::PasswordUrl, Some(u.url.clone()))
+                    .is_some_and(|url| like_contains(&url, keyword))
+            })
+            .map(|u| u.password_id)
+            .collect())
+    }
+
+    /// 非机密自定义字段取值匹配关键字的密码 ID
+    fn search_custom_field_password_ids(&self, keyword: &str) -> Result<HashSet<i64>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT password_id FROM password_custom_fields WHERE value LIKE ?1")
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([format!("%{}%", keyword)], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<HashSet<i64>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取密码历史记录
+    pub fn get_password_history(
+        &self,
+        password_id: i64,
+    ) -> Result<Vec<crate::models::password::PasswordHistory>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, old_password, changed_at, change_reason 
+             FROM password_history 
+             WHERE password_id = ?1 
+             ORDER BY changed_at DESC, id DESC",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let history_iter = stmt
+            .query_map([password_id], |row| {
+                Ok(crate::models::password::PasswordHistory {
+                    id: row.get(0)?,
+                    password_id: row.get(1)?,
+                    old_password: row.get(2)?,
+                    changed_at: row.get(3)?,
+                    change_reason: row.get(4)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut results = Vec::new();
+        for item in history_iter {
+            results.push(item.map_err(|e| e.to_string())?);
+        }
+
+        Ok(results)
+    }
+
+    /// 添加密码历史记录
+    pub fn add_password_history(
+        &self,
+        password_id: i64,
+        old_password: &str,
+        change_reason: Option<&str>,
+    ) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "INSERT INTO password_history (password_id, old_password, changed_at, change_reason) 
+             VALUES (?1, ?2, datetime('now'), ?3)",
+            (password_id, old_password, change_reason),
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// 各密码条目距上次更换密码的天数
+    ///
+    /// 以最近一条密码历史的时间为准；没有历史记录时使用条目的更新时间。
+    pub fn get_password_ages(&self) -> Result<std::collections::HashMap<i64, i64>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT p.id, CAST(julianday('now') - julianday(COALESCE(h.changed_at, p.updated_at, p.created_at)) AS INTEGER)
+                 FROM passwords p
+                 LEFT JOIN (SELECT password_id, MAX(changed_at) AS changed_at FROM password_history GROUP BY password_id) h
+                   ON h.password_id = p.id
+                 WHERE p.deleted_at IS NULL",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?)))
+            .map_err(|e| e.to_string())?;
+        let mut ages = std::collections::HashMap::new();
+        for row in rows {
+            if let (id, Some(days)) = row.map_err(|e| e.to_string())? {
+                ages.insert(id, days);
+            }
+        }
+        Ok(ages)
+    }
+
+    /// 获取单条密码历史记录
+    pub fn get_password_history_entry(
+        &self,
+        id: i64,
+    ) -> Result<Option<crate::models::password::PasswordHistory>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT id, password_id, old_password, changed_at, change_reason FROM password_history WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(crate::models::password::PasswordHistory {
+                    id: row.get(0)?,
+                    password_id: row.get(1)?,
+                    old_password: row.get(2)?,
+                    changed_at: row.get(3)?,
+                    change_reason: row.get(4)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// 删除单条密码历史记录
+    pub fn delete_password_history_entry(&self, id: i64) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM password_history WHERE id = ?", [id])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 清理密码历史：只保留最近 `max_entries` 条且不早于 `max_age_days` 天的记录（0 表示不限），返回删除数量
+    pub fn prune_password_history(
+        &self,
+        password_id: i64,
+        max_entries: u32,
+        max_age_days: u32,
+    ) -> Result<usize, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut removed = 0;
+        if max_entries > 0 {
+            removed += conn
+                .execute(
+                    "DELETE FROM password_history WHERE password_id = ?1 AND id NOT IN (
+                        SELECT id FROM password_history WHERE password_id = ?1
+                        ORDER BY changed_at DESC, id DESC LIMIT ?2
+                    )",
+                    (password_id, max_entries),
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        if max_age_days > 0 {
+            removed += conn
+                .execute(
+                    "DELETE FROM password_history WHERE password_id = ?1 AND changed_at < datetime('now', ?2)",
+                    (password_id, format!("-{} days", max_age_days)),
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(removed)
+    }
+
+    // --- Revisions ---
+
+    /// 获取条目的修订记录（新的在前）；快照为密文
+    pub fn get_revisions(
+        &self,
+        item_type: crate::models::item::ItemType,
+        item_id: i64,
+    ) -> Result<Vec<crate::models::revision::Revision>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, item_type, item_id, changed_fields, snapshot, created_at FROM revisions
+                 WHERE item_type = ?1 AND item_id = ?2 ORDER BY id DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map((item_type.as_str(), item_id), Self::map_revision_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取单条修订记录；快照为密文
+    pub fn get_revision(&self, id: i64) -> Result<Option<crate::models::revision::Revision>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT id, item_type, item_id, changed_fields, snapshot, created_at FROM revisions WHERE id = ?1",
+            [id],
+            Self::map_revision_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// 添加修订记录；快照须已加密
+    pub fn add_revision(&self, revision: &crate::models::revision::Revision) -> Result<i64, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let changed_fields = serde_json::to_string(&revision.changed_fields).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO revisions (item_type, item_id, changed_fields, snapshot, created_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+            (revision.item_type.as_str(), revision.item_id, changed_fields, &revision.snapshot),
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // --- TOTP ---
+
+    /// 获取密码条目的 TOTP 配置（密钥为密文）
+    pub fn get_password_totp(
+        &self,
+        password_id: i64,
+    ) -> Result<Option<crate::models::totp::PasswordTotp>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT password_id, secret, issuer, account_name, algorithm, digits, period, created_at, updated_at
+             FROM password_totp WHERE password_id = ?1",
+            [password_id],
+            Self::map_totp_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// 获取全部 TOTP 配置（密钥为密文）
+    pub fn get_all_password_totp(&self) -> Result<Vec<crate::models::totp::PasswordTotp>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT password_id, secret, issuer, account_name, algorithm, digits, period, created_at, updated_at
+                 FROM password_totp ORDER BY password_id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([], Self::map_totp_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 设置（覆盖）密码条目的 TOTP 配置；`totp.secret` 须已加密
+    pub fn set_password_totp(&self, totp: &crate::models::totp::PasswordTotp) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        Self::write_password_totp(&conn, totp).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 删除密码条目的 TOTP 配置
+    pub fn delete_password_totp(&self, password_id: i64) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM password_totp WHERE password_id = ?", [password_id])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 在给定连接/事务上写入 TOTP 配置
+    pub(crate) fn write_password_totp(
+        conn: &Connection,
+        totp: &crate::models::totp::PasswordTotp,
+    ) -> Result<usize> {
+        conn.execute(
+            "INSERT INTO password_totp (password_id, secret, issuer, account_name, algorithm, digits, period, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'), datetime('now'))
+             ON CONFLICT(password_id) DO UPDATE SET
+             secret=excluded.secret,
+             issuer=excluded.issuer,
+             account_name=excluded.account_name,
+             algorithm=excluded.algorithm,
+             digits=excluded.digits,
+             period=excluded.period,
+             updated_at=datetime('now')",
+            (
+                totp.password_id,
+                &totp.secret,
+                &totp.issuer,
+                &totp.account_name,
+                totp.algorithm.as_str(),
+                totp.digits,
+                totp.period as i64,
+            ),
+        )
+    }
+
+    // --- Custom Fields ---
+
+    /// 获取密码条目的自定义字段（机密字段为密文）
+    pub fn get_custom_fields(
+        &self,
+        password_id: i64,
+    ) -> Result<Vec<crate::models::custom_field::CustomField>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, field_type, label, value, secret_value, sort_order, created_at, updated_at
+                 FROM password_custom_fields WHERE password_id = ?1 ORDER BY sort_order, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([password_id], Self::map_custom_field_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取全部自定义字段（机密字段为密文）
+    pub fn get_all_custom_fields(&self) -> Result<Vec<crate::models::custom_field::CustomField>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, field_type, label, value, secret_value, sort_order, created_at, updated_at
+                 FROM password_custom_fields ORDER BY password_id, sort_order, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([], Self::map_custom_field_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取单个自定义字段
+    pub fn get_custom_field(&self, id: i64) -> Result<Option<crate::models::custom_field::CustomField>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT id, password_id, field_type, label, value, secret_value, sort_order, created_at, updated_at
+             FROM password_custom_fields WHERE id = ?1",
+            [id],
+            Self::map_custom_field_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// 添加自定义字段；机密字段的 `value` 须已加密
+    pub fn add_custom_field(&self, field: &crate::models::custom_field::CustomField) -> Result<i64, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        Self::write_custom_field(&conn, field).map_err(|e| e.to_string())
+    }
+
+    /// 更新自定义字段；机密字段的 `value` 须已加密
+    pub fn update_custom_field(&self, field: &crate::models::custom_field::CustomField) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let id = field.id.ok_or("Custom field ID is missing")?;
+        let (value, secret_value) = Self::custom_field_columns(field);
+        conn.execute(
+            "UPDATE password_custom_fields SET field_type=?1, label=?2, value=?3, secret_value=?4, sort_order=?5, updated_at=datetime('now') WHERE id=?6",
+            (field.field_type.as_str(), &field.label, value, secret_value, field.sort_order, id),
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 删除自定义字段
+    pub fn delete_custom_field(&self, id: i64) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM password_custom_fields WHERE id = ?", [id])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 在给定连接/事务上插入自定义字段，返回新 ID
+    pub(crate) fn write_custom_field(
+        conn: &Connection,
+        field: &crate::models::custom_field::CustomField,
+    ) -> Result<i64> {
+        let (value, secret_value) = Self::custom_field_columns(field);
+        conn.execute(
+            "INSERT INTO password_custom_fields (password_id, field_type, label, value, secret_value, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'), datetime('now'))",
+            (
+                field.password_id,
+                field.field_type.as_str(),
+                &field.label,
+                value,
+                secret_value,
+                field.sort_order.unwrap_or(0),
+            ),
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 机密字段写入 secret_value 列，其余写入 value 列
+    fn custom_field_columns(
+        field: &crate::models::custom_field::CustomField,
+    ) -> (Option<&str>, Option<&str>) {
+        let value = Some(field.value.expose());
+        if field.field_type.is_secret() {
+            (None, value)
+        } else {
+            (value, None)
+        }
+    }
+
+    // --- Multiple URLs / Accounts ---
+
+    /// 获取密码条目的附加网址（按策略可能为密文）
+    pub fn get_password_urls(&self, password_id: i64) -> Result<Vec<crate::models::password::PasswordUrl>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, url, match_rule, sort_order, created_at
+                 FROM password_urls WHERE password_id = ?1 ORDER BY sort_order, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([password_id], Self::map_password_url_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取全部附加网址
+    pub fn get_all_password_urls(&self) -> Result<Vec<crate::models::password::PasswordUrl>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, url, match_rule, sort_order, created_at
+                 FROM password_urls ORDER BY password_id, sort_order, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([], Self::map_password_url_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 以新列表替换密码条目的全部附加网址
+    pub fn replace_password_urls(
+        &self,
+        password_id: i64,
+        urls: &[crate::models::password::PasswordUrl],
+    ) -> Result<(), String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        Self::replace_password_urls_conn(&tx, password_id, urls).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// 在给定连接/事务上替换附加网址；排序按列表顺序重写
+    pub(crate) fn replace_password_urls_conn(
+        conn: &Connection,
+        password_id: i64,
+        urls: &[crate::models::password::PasswordUrl],
+    ) -> Result<()> {
+        conn.execute("DELETE FROM password_urls WHERE password_id = ?1", [password_id])?;
+        for (index, url) in urls.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO password_urls (password_id, url, match_rule, sort_order, created_at)
+                 VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+                (password_id, &url.url, url.match_rule.as_str(), index as i32),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 获取密码条目的附加账号（密码为密文）
+    pub fn get_password_accounts(
+        &self,
+        password_id: i64,
+    ) -> Result<Vec<crate::models::password::PasswordAccount>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, label, username, password, sort_order, created_at, updated_at
+                 FROM password_accounts WHERE password_id = ?1 ORDER BY sort_order, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([password_id], Self::map_password_account_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取全部附加账号
+    pub fn get_all_password_accounts(&self) -> Result<Vec<crate::models::password::PasswordAccount>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, label, username, password, sort_order, created_at, updated_at
+                 FROM password_accounts ORDER BY password_id, sort_order, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([], Self::map_password_account_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取单个附加账号
+    pub fn get_password_account(&self, id: i64) -> Result<Option<crate::models::password::PasswordAccount>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT id, password_id, label, username, password, sort_order, created_at, updated_at
+             FROM password_accounts WHERE id = ?1",
+            [id],
+            Self::map_password_account_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// 添加附加账号；密码须已加密
+    pub fn add_password_account(&self, account: &crate::models::password::PasswordAccount) -> Result<i64, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        Self::write_password_account(&conn, account).map_err(|e| e.to_string())
+    }
+
+    /// 更新附加账号；密码须已加密
+    pub fn update_password_account(&self, account: &crate::models::password::PasswordAccount) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let id = account.id.ok_or("Account ID is missing")?;
+        conn.execute(
+            "UPDATE password_accounts SET label=?1, username=?2, password=?3, sort_order=?4, updated_at=datetime('now') WHERE id=?5",
+            (&account.label, &account.username, &account.password, account.sort_order, id),
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 删除附加账号
+    pub fn delete_password_account(&self, id: i64) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM password_accounts WHERE id = ?", [id])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 在给定连接/事务上插入附加账号，返回新 ID
+    pub(crate) fn write_password_account(
+        conn: &Connection,
+        account: &crate::models::password::PasswordAccount,
+    ) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO password_accounts (password_id, label, username, password, sort_order, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'), datetime('now'))",
+            (
+                account.password_id,
+                &account.label,
+                &account.username,
+                &account.password,
+                account.sort_order.unwrap_or(0),
+            ),
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // --- Attachments ---
+
+    /// 获取密码条目或笔记的附件元数据（不含内容）
+    pub fn get_attachments(
+        &self,
+        password_id: Option<i64>,
+        note_id: Option<i64>,
+    ) -> Result<Vec<crate::models::attachment::Attachment>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, note_id, file_name, mime_type, size, chunk_count, file_key, created_at
+                 FROM attachments WHERE password_id IS ?1 AND note_id IS ?2 ORDER BY id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map((password_id, note_id), Self::map_attachment_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取全部附件元数据（不含回收站中条目的附件）
+    pub fn get_all_attachments(&self) -> Result<Vec<crate::models::attachment::Attachment>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, password_id, note_id, file_name, mime_type, size, chunk_count, file_key, created_at
+                 FROM attachments
+                 WHERE password_id IN (SELECT id FROM passwords WHERE deleted_at IS NULL)
+                    OR note_id IN (SELECT id FROM secure_records WHERE deleted_at IS NULL)
+                 ORDER BY id",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([], Self::map_attachment_row)
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 获取单个附件元数据
+    pub fn get_attachment(&self, id: i64) -> Result<Option<crate::models::attachment::Attachment>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT id, password_id, note_id, file_name, mime_type, size, chunk_count, file_key, created_at
+             FROM attachments WHERE id = ?1",
+            [id],
+            Self::map_attachment_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// 按顺序读取附件的全部密文分块
+    pub fn get_attachment_chunks(&self, attachment_id: i64) -> Result<Vec<Vec<u8>>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT data FROM attachment_chunks WHERE attachment_id = ?1 ORDER BY chunk_index")
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([attachment_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
+
+    /// 添加附件；文件密钥与分块须已加密
+    pub fn add_attachment(
+        &self,
+        attachment: &crate::models::attachment::Attachment,
+        chunks: &[Vec<u8>],
+    ) -> Result<i64, String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let id = Self::write_attachment(&tx, attachment, chunks).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(id)
+    }
+
+    /// 删除附件及其分块
+    pub fn delete_attachment(&self, id: i64) -> Result<(), String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM attachment_chunks WHERE attachment_id = ?", [id])
+            .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM attachments WHERE id = ?", [id])
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// 在给定连接/事务上写入附件元数据与分块，返回新 ID
+    pub(crate) fn write_attachment(
+        conn: &Connection,
+        attachment: &crate::models::attachment::Attachment,
+        chunks: &[Vec<u8>],
+    ) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO attachments (password_id, note_id, file_name, mime_type, size, chunk_count, file_key, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'))",
+            (
+                attachment.password_id,
+                attachment.note_id,
+                &attachment.file_name,
+                &attachment.mime_type,
+                attachment.size,
+                chunks.len() as i64,
+                &attachment.file_key,
+            ),
+        )?;
+        let id = conn.last_insert_rowid();
+        let mut stmt = conn.prepare(
+            "INSERT INTO attachment_chunks (attachment_id, chunk_index, data) VALUES (?1, ?2, ?3)",
+        )?;
+        for (index, chunk) in chunks.iter().enumerate() {
+            stmt.execute((id, index as i64, chunk))?;
+        }
+        Ok(id)
+    }
+
+    /// 在给定连接/事务上删除某个条目的全部附件；`owner_column` 为 `password_id` 或 `note_id`
+    pub(crate) fn delete_owned_attachments(conn: &Connection, owner_column: &str, owner_id: i64) -> Result<()> {
+        conn.execute(
+            &format!(
+                "DELETE FROM attachment_chunks WHERE attachment_id IN (SELECT id FROM attachments WHERE {owner_column} = ?1)"
+            ),
+            [owner_id],
+        )?;
+        conn.execute(&format!("DELETE FROM attachments WHERE {owner_column} = ?1"), [owner_id])?;
+        Ok(())
+    }
+
+    // --- Vault Items ---
+
+    /// 获取结构化条目（可选按类型筛选）；机密字段为密文
+    pub fn get_vault_items(
+        &self,
+        item_type: Option<crate::models::item::ItemType>,
+    ) -> Result<Vec<crate::models::item::VaultItem>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, item_type, title, group_id, notes, favorite, created_at, updated_at
+                 FROM vault_items WHERE deleted_at IS NULL AND (?1 IS NULL OR item_type = ?1) ORDER BY title",
+            )
+            .map_err(|e| e.to_string())?;
+        let iter = stmt
+            .query_map([item_type.map(|t| t.as_str())], Self::map_vault_item_row)
+            .map_err(|e| e.to_string())?;
+        let mut items = iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())?;
+
+        let mut fields: std::collections::HashMap<i64, Vec<(String, crate::models::SecretString)>> =
+            std::collections::HashMap::new();
+        let mut stmt = conn
+            .prepare("SELECT item_id, name, COALESCE(secret_value, value, '') FROM vault_item_fields")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let (item_id, name, value) = row.map_err(|e| e.to_string())?;
+            fields.entry(item_id).or_default().push((name, value));
+        }
+        for item in &mut items {
+            if let Some(values) = item.id.and_then(|id| fields.remove(&id)) {
+                item.fields = values.into_iter().collect();
+            }
+        }
+        Ok(items)
+    }
+
+    /// 获取单个结构化条目；机密字段为密文
+    pub fn get_vault_item(&self, id: i64) -> Result<Option<crate::models::item::VaultItem>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let item = conn
+            .query_row(
+                "SELECT id, item_type, title, group_id, notes, favorite, created_at, updated_at
+                 FROM vault_items WHERE id = ?1 AND deleted_at IS NULL",
+                [id],
+                Self::map_vault_item_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(mut item) = item else {
+            return Ok(None);
+        };
+        let mut stmt = conn
+            .prepare("SELECT name, COALESCE(secret_value, value, '') FROM vault_item_fields WHERE item_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        item.fields = rows.collect::<Result<_>>().map_err(|e| e.to_string())?;
+        Ok(Some(item))
+    }
+
+    /// 添加结构化条目；机密字段须已加密
+    pub fn add_vault_item(&self, item: &crate::models::item::VaultItem) -> Result<i64, String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let id = Self::write_vault_item(&tx, item).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(id)
+    }
+
+    /// 更新结构化条目并替换全部字段；条目类型不可更改
+    pub fn update_vault_item(&self, item: &crate::models::item::VaultItem) -> Result<(), String> {
+        let id = item.id.ok_or("Item ID is missing")?;
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE vault_items SET title=?1, group_id=?2, notes=?3, favorite=?4, updated_at=datetime('now') WHERE id=?5",
+            (&item.title, item.group_id, &item.notes, item.favorite.unwrap_or(false), id),
+        )
+        .map_err(|e| e.to_string())?;
+        Self::replace_vault_item_fields(&tx, id, item).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// 删除结构化条目（移入回收站）
+    pub fn delete_vault_item(&self, id: i64) -> Result<(), String> {
+        self.move_to_trash("vault_items", id)
+    }
+
+    /// 彻底删除结构化条目及其字段
+    fn purge_vault_item(conn: &Connection, id: i64) -> Result<()> {
+        conn.execute("DELETE FROM vault_item_fields WHERE item_id = ?", [id])?;
+        conn.execute("DELETE FROM vault_items WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    /// 在给定连接/事务上插入结构化条目及其字段，返回新 ID
+    pub(crate) fn write_vault_item(conn: &Connection, item: &crate::models::item::VaultItem) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO vault_items (item_type, title, group_id, notes, favorite, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'), datetime('now'))",
+            (
+                item.item_type.as_str(),
+                &item.title,
+                item.group_id,
+                &item.notes,
+                item.favorite.unwrap_or(false),
+            ),
+        )?;
+        let id = conn.last_insert_rowid();
+        Self::replace_vault_item_fields(conn, id, item)?;
+        Ok(id)
+    }
+
+    /// 替换条目的全部字段；机密字段写入 secret_value
+    pub(crate) fn replace_vault_item_fields(
+        conn: &Connection,
+        item_id: i64,
+        item: &crate::models::item::VaultItem,
+    ) -> Result<()> {
+        conn.execute("DELETE FROM vault_item_fields WHERE item_id = ?1", [item_id])?;
+        let mut stmt = conn.prepare(
+            "INSERT INTO vault_item_fields (item_id, name, value, secret_value) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (name, value) in &item.fields {
+            let (plain, secret) = if crate::services::items::is_secret_field(item.item_type, name) {
+                (None, Some(value))
+            } else {
+                (Some(value), None)
+            };
+            stmt.execute((item_id, name, plain, secret))?;
+        }
+        Ok(())
+    }
+
+    /// 所有类型条目的摘要（登录、安全笔记与结构化条目），按更新时间倒序
+    ///
+    /// 标题为存储值，按字段加密策略可能为密文。
+    pub fn get_item_summaries(&self) -> Result<Vec<crate::models::item::ItemSummary>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT item_type, id, title, group_id, updated_at FROM passwords WHERE deleted_at IS NULL
+                 UNION ALL SELECT item_type, id, title, group_id, updated_at FROM secure_records WHERE deleted_at IS NULL
+                 UNION ALL SELECT item_type, id, title, group_id, updated_at FROM vault_items WHERE deleted_at IS NULL
+                 ORDER BY updated_at DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                let item_type: String = row.get(0)?;
+                Ok((item_type, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut summaries = Vec::new();
+        for row in rows {
+            let (item_type, id, title, group_id, updated_at) = row.map_err(|e| e.to_string())?;
+            let Some(item_type) = crate::models::item::ItemType::parse(&item_type) else {
+                log::warn!("Skipping item {} with unknown type '{}'", id, item_type);
+                continue;
+            };
+            summaries.push(crate::models::item::ItemSummary { item_type, id, title, group_id, updated_at });
+        }
+        Ok(summaries)
+    }
+
+    /// 检查是否已设置主密码
+    pub fn has_master_password(&self) -> Result<bool, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        
+        // 检查 password_hash 是否为 NULL,而不是检查记录是否存在
+        let mut stmt = conn
+            .prepare("SELECT password_hash FROM master_password WHERE id = 1")
+            .map_err(|e| e.to_string())?;
+        
+        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+        
+        if let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let password_hash: Option<String> = row.get(0).map_err(|e| e.to_string())?;
+            Ok(password_hash.is_some())
+        } else {
+            // 记录不存在,说明没有设置主密码
+            Ok(false)
+        }
+    }
+
+    /// 获取主密码哈希
+    pub fn get_master_password_hash(&self) -> Result<Option<String>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT password_hash FROM master_password WHERE id = 1")
+            .map_err(|e| e.to_string())?;
+
+        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+
+        if let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let hash: Option<String> = row.get(0).map_err(|e| e.to_string())?;
+            Ok(hash)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 设置主密码
+    pub fn set_master_password(&self, hash: &str, hint: Option<&str>) -> Result<(), String> {
+        self.set_master_password_with_require(hash, hint, true)
+    }
+
+    /// 设置主密码并指定是否要求解锁
+    pub fn set_master_password_with_require(
+        &self,
+        hash: &str,
+        hint: Option<&str>,
+        require_password: bool,
+    ) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO master_password (id, password_hash, hint, require_password, created_at, updated_at) 
+             VALUES (1, ?1, ?2, ?3, datetime('now'), datetime('now'))
+             ON CONFLICT(id) DO UPDATE SET 
+             password_hash=excluded.password_hash, 
+             hint=excluded.hint,
+             require_password=excluded.require_password,
+             updated_at=datetime('now')",
+            (hash, hint, if require_password { 1 } else { 0 }),
+        ).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 设置主密码，并在同一事务中保存主密码（及恢复密钥）包装的数据密钥
+    ///
+    /// 要求解锁时同时移除设备级包装，保证数据密钥只能由主密码或恢复密钥解开。
+    pub fn set_master_password_with_key(
+        &self,
+        hash: &str,
+        hint: Option<&str>,
+        require_password: bool,
+        keys: &[crate::models::vault::VaultKey],
+    ) -> Result<(), String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO master_password (id, password_hash, hint, require_password, created_at, updated_at) 
+             VALUES (1, ?1, ?2, ?3, datetime('now'), datetime('now'))
+             ON CONFLICT(id) DO UPDATE SET 
+             password_hash=excluded.password_hash, 
+             hint=excluded.hint,
+             require_password=excluded.require_password,
+             updated_at=datetime('now')",
+            (hash, hint, if require_password { 1 } else { 0 }),
+        )
+        .map_err(|e| e.to_string())?;
+        for key in keys {
+            Self::write_vault_key(&tx, key).map_err(|e| e.to_string())?;
+        }
+        if require_password {
+            tx.execute("DELETE FROM vault_keys WHERE kind = 'device'", [])
+                .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// 清除主密码，并在同一事务中改用设备级包装保存数据密钥（恢复密钥一并作废）
+    pub fn clear_master_password_with_key(
+        &self,
+        device_key: &crate::models::vault::VaultKey,
+    ) -> Result<(), String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        Self::write_vault_key(&tx, device_key).map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE master_password 
+             SET password_hash = NULL, 
+                 hint = NULL,
+                 require_password = 0,
+                 updated_at = datetime('now')
+             WHERE id = 1",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM vault_keys WHERE kind IN ('master', 'recovery')", [])
+            .map_err(|e| e.to_string())?;
+        // 未完成的主密码修改随主密码一同作废
+        tx.execute("DELETE FROM vault_rekey", [])
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// 切换是否要求主密码，并同步设备级包装
+    ///
+    /// 开启时删除设备级包装；关闭时写入 `device_key`。
+    pub fn set_require_master_password_with_key(
+        &self,
+        require: bool,
+        device_key: Option<&crate::models::vault::VaultKey>,
+    ) -> Result<(), String> {
+        if !self.has_master_password()? {
+            return Err("必须先设置主密码才能启用锁屏功能".to_string());
+        }
+
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE master_password 
+             SET require_password = ?, 
+                 updated_at = datetime('now')
+             WHERE id = 1",
+            [if require { 1 } else { 0 }],
+        )
+        .map_err(|e| e.to_string())?;
+        if require {
+            tx.execute("DELETE FROM vault_keys WHERE kind = 'device'", [])
+                .map_err(|e| e.to_string())?;
+        } else {
+            let device_key = device_key.ok_or("关闭主密码需要设备级数据密钥")?;
+            Self::write_vault_key(&tx, device_key).map_err(|e| e.to_string())?;
         }
+        tx.commit().map_err(|e| e.to_string())
     }
 
-    /// 删除密码
-    pub fn delete_password(&self, id: i64) -> Result<(), String> {
+    // --- Unlock Throttle ---
+
+    /// 读取解锁失败节流状态
+    pub fn get_unlock_throttle(&self) -> Result<crate::models::security::UnlockThrottleState, String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM passwords WHERE id = ?", [id])
+        let row: Option<(u32, Option<String>)> = conn
+            .query_row(
+                "SELECT failed_attempts, cooldown_until FROM unlock_throttle WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
             .map_err(|e| e.to_string())?;
-        Ok(())
+        Ok(match row {
+            Some((failed_attempts, cooldown_until)) => crate::models::security::UnlockThrottleState {
+                failed_attempts,
+                cooldown_until: cooldown_until
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|t| t.with_timezone(&chrono::Utc)),
+            },
+            None => Default::default(),
+        })
     }
 
-    /// 搜索密码（模糊匹配标题、用户名、网址、备注）
-    pub fn search_passwords(
+    /// 保存解锁失败节流状态
+    pub fn save_unlock_throttle(
         &self,
-        keyword: &str,
-    ) -> Result<Vec<crate::models::password::Password>, String> {
+        throttle: &crate::models::security::UnlockThrottleState,
+    ) -> Result<(), String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
-        let pattern = format!("%{}%", keyword);
-
-        let mut stmt = conn.prepare(
-            "SELECT id, title, username, password, url, notes, group_id, created_at, updated_at, last_used_at, use_count, favorite, tags FROM passwords 
-            WHERE title LIKE ?1 OR username LIKE ?1 OR url LIKE ?1 OR notes LIKE ?1 
-            ORDER BY title"
-        ).map_err(|e| e.to_string())?;
-
-        let password_iter = stmt
-            .query_map([&pattern], Self::map_password_row)
-            .map_err(|e| e.to_string())?;
-
-        let mut passwords = Vec::new();
-        for password in password_iter {
-            passwords.push(password.map_err(|e| e.to_string())?);
-        }
-
-        Ok(passwords)
+        conn.execute(
+            "INSERT INTO unlock_throttle (id, failed_attempts, cooldown_until, updated_at)
+             VALUES (1, ?1, ?2, datetime('now'))
+             ON CONFLICT(id) DO UPDATE SET
+             failed_attempts=excluded.failed_attempts,
+             cooldown_until=excluded.cooldown_until,
+             updated_at=datetime('now')",
+            (
+                throttle.failed_attempts,
+                throttle.cooldown_until.map(|t| t.to_rfc3339()),
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
     }
 
-    /// 获取密码历史记录
-    pub fn get_password_history(
-        &self,
-        password_id: i64,
-    ) -> Result<Vec<crate::models::password::PasswordHistory>, String> {
+    /// 记录一次解锁尝试，只保留最近 UNLOCK_ATTEMPT_RETENTION 条
+    pub fn record_unlock_attempt(&self, success: bool, method: &str) -> Result<(), String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO unlock_attempts (attempted_at, success, method) VALUES (?1, ?2, ?3)",
+            (chrono::Utc::now().to_rfc3339(), success, method),
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute(
+            "DELETE FROM unlock_attempts WHERE id NOT IN
+             (SELECT id FROM unlock_attempts ORDER BY id DESC LIMIT ?1)",
+            [UNLOCK_ATTEMPT_RETENTION],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
 
+    /// 获取最近的解锁失败记录（按时间倒序）
+    pub fn get_unlock_failures(&self, limit: u32) -> Result<Vec<crate::models::security::UnlockAttempt>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
         let mut stmt = conn
             .prepare(
-                "SELECT id, password_id, old_password, changed_at, change_reason 
-             FROM password_history 
-             WHERE password_id = ?1 
-             ORDER BY changed_at DESC",
+                "SELECT id, attempted_at, success, method FROM unlock_attempts
+                 WHERE success = 0 ORDER BY id DESC LIMIT ?1",
             )
             .map_err(|e| e.to_string())?;
-
-        let history_iter = stmt
-            .query_map([password_id], |row| {
-                Ok(crate::models::password::PasswordHistory {
+        let iter = stmt
+            .query_map([limit], |row| {
+                Ok(crate::models::security::UnlockAttempt {
                     id: row.get(0)?,
-                    password_id: row.get(1)?,
-                    old_password: row.get(2)?,
-                    changed_at: row.get(3)?,
-                    change_reason: row.get(4)?,
+                    attempted_at: row.get(1)?,
+                    success: row.get(2)?,
+                    method: row.get(3)?,
                 })
             })
             .map_err(|e| e.to_string())?;
+        iter.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+    }
 
-        let mut results = Vec::new();
-        for item in history_iter {
-            results.push(item.map_err(|e| e.to_string())?);
-        }
+    // --- Vault Keys ---
 
-        Ok(results)
+    /// 获取指定包装方式的数据密钥
+    pub fn get_vault_key(&self, kind: &str) -> Result<Option<crate::models::vault::VaultKey>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT kind, wrapped_key, kdf_salt, kdf_params, created_at, updated_at FROM vault_keys WHERE kind = ?1",
+            [kind],
+            Self::map_vault_key_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
     }
 
-    /// 添加密码历史记录
-    pub fn add_password_history(
+    /// 是否已建立数据密钥（存在任意包装）
+    pub fn has_vault_keys(&self) -> Result<bool, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM vault_keys", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        Ok(count > 0)
+    }
+
+    /// 在同一事务中写入（覆盖）若干数据密钥包装并删除指定包装
+    pub fn replace_vault_keys(
         &self,
-        password_id: i64,
-        old_password: &str,
-        change_reason: Option<&str>,
+        keys: &[crate::models::vault::VaultKey],
+        delete_kinds: &[&str],
     ) -> Result<(), String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for key in keys {
+            Self::write_vault_key(&tx, key).map_err(|e| e.to_string())?;
+        }
+        for kind in delete_kinds {
+            tx.execute("DELETE FROM vault_keys WHERE kind = ?", [kind])
+                .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// 删除一条数据密钥包装
+    pub fn delete_vault_key(&self, kind: &str) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM vault_keys WHERE kind = ?", [kind])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    // --- Vault Rekey ---
+
+    /// 读取尚未完成的重新加密任务
+    pub fn get_vault_rekey(&self) -> Result<Option<crate::models::vault::VaultRekey>, String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let row = conn
+            .query_row(
+                "SELECT wrapped_new_key, new_keys, password_hash, hint, started_at FROM vault_rekey WHERE id = 1",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(1)?,
+                        crate::models::vault::VaultRekey {
+                            wrapped_new_key: row.get(0)?,
+                            new_keys: Vec::new(),
+                            password_hash: row.get(2)?,
+                            hint: row.get(3)?,
+                            started_at: row.get(4)?,
+                        },
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some((new_keys, mut rekey)) = row else {
+            return Ok(None);
+        };
+        rekey.new_keys = serde_json::from_str(&new_keys).map_err(|e| format!("重新加密任务数据无效: {}", e))?;
+        Ok(Some(rekey))
+    }
 
+    /// 记录重新加密任务（覆盖尚未完成的旧任务）
+    pub fn save_vault_rekey(&self, rekey: &crate::models::vault::VaultRekey) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let new_keys = serde_json::to_string(&rekey.new_keys).map_err(|e| e.to_string())?;
         conn.execute(
-            "INSERT INTO password_history (password_id, old_password, changed_at, change_reason) 
-             VALUES (?1, ?2, datetime('now'), ?3)",
-            (password_id, old_password, change_reason),
+            "INSERT INTO vault_rekey (id, wrapped_new_key, new_keys, password_hash, hint, started_at)
+             VALUES (1, ?1, ?2, ?3, ?4, datetime('now'))
+             ON CONFLICT(id) DO UPDATE SET
+             wrapped_new_key=excluded.wrapped_new_key,
+             new_keys=excluded.new_keys,
+             password_hash=excluded.password_hash,
+             hint=excluded.hint,
+             started_at=excluded.started_at",
+            (&rekey.wrapped_new_key, &new_keys, &rekey.password_hash, &rekey.hint),
         )
         .map_err(|e| e.to_string())?;
-
         Ok(())
     }
 
-    /// 检查是否已设置主密码
-    pub fn has_master_password(&self) -> Result<bool, String> {
+    /// 丢弃重新加密任务记录
+    pub fn delete_vault_rekey(&self) -> Result<(), String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
-        
-        // 检查 password_hash 是否为 NULL,而不是检查记录是否存在
-        let mut stmt = conn
-            .prepare("SELECT password_hash FROM master_password WHERE id = 1")
+        conn.execute("DELETE FROM vault_rekey", [])
             .map_err(|e| e.to_string())?;
-        
-        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-        
-        if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-            let password_hash: Option<String> = row.get(0).map_err(|e| e.to_string())?;
-            Ok(password_hash.is_some())
-        } else {
-            // 记录不存在,说明没有设置主密码
-            Ok(false)
-        }
+        Ok(())
     }
 
-    /// 获取主密码哈希
-    pub fn get_master_password_hash(&self) -> Result<Option<String>, String> {
-        let conn = self.get_connection().map_err(|e| e.to_string())?;
-        let mut stmt = conn
-            .prepare("SELECT password_hash FROM master_password WHERE id = 1")
-            .map_err(|e| e.to_string())?;
-
-        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-
-        if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-            let hash: Option<String> = row.get(0).map_err(|e| e.to_string())?;
-            Ok(hash)
-        } else {
-            Ok(None)
+    /// 在给定事务上完成重新加密任务：替换全部数据密钥包装、更新主密码哈希并删除任务记录
+    pub(crate) fn finish_vault_rekey(
+        conn: &Connection,
+        rekey: &crate::models::vault::VaultRekey,
+    ) -> Result<()> {
+        conn.execute("DELETE FROM vault_keys", [])?;
+        for key in &rekey.new_keys {
+            Self::write_vault_key(conn, key)?;
         }
+        if let Some(hash) = &rekey.password_hash {
+            conn.execute(
+                "UPDATE master_password SET password_hash = ?1, hint = ?2, updated_at = datetime('now') WHERE id = 1",
+                (hash, &rekey.hint),
+            )?;
+        }
+        conn.execute("DELETE FROM vault_rekey", [])?;
+        Ok(())
     }
 
-    /// 设置主密码
-    pub fn set_master_password(&self, hash: &str, hint: Option<&str>) -> Result<(), String> {
-        self.set_master_password_with_require(hash, hint, true)
-    }
-
-    /// 设置主密码并指定是否要求解锁
-    pub fn set_master_password_with_require(
-        &self,
-        hash: &str,
-        hint: Option<&str>,
-        require_password: bool,
-    ) -> Result<(), String> {
-        let conn = self.get_connection().map_err(|e| e.to_string())?;
+    /// 在给定连接/事务上写入数据密钥包装
+    pub(crate) fn write_vault_key(
+        conn: &Connection,
+        key: &crate::models::vault::VaultKey,
+    ) -> Result<usize> {
         conn.execute(
-            "INSERT INTO master_password (id, password_hash, hint, require_password, created_at, updated_at) 
-             VALUES (1, ?1, ?2, ?3, datetime('now'), datetime('now'))
-             ON CONFLICT(id) DO UPDATE SET 
-             password_hash=excluded.password_hash, 
-             hint=excluded.hint,
-             require_password=excluded.require_password,
+            "INSERT INTO vault_keys (kind, wrapped_key, kdf_salt, kdf_params, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'), datetime('now'))
+             ON CONFLICT(kind) DO UPDATE SET
+             wrapped_key=excluded.wrapped_key,
+             kdf_salt=excluded.kdf_salt,
+             kdf_params=excluded.kdf_params,
              updated_at=datetime('now')",
-            (hash, hint, if require_password { 1 } else { 0 }),
-        ).map_err(|e| e.to_string())?;
-        Ok(())
+            (&key.kind, &key.wrapped_key, &key.kdf_salt, &key.kdf_params),
+        )
     }
 
     /// 获取所有分组
@@ -381,12 +1515,9 @@ impl DatabaseService {
         }
     }
 
-    /// 删除分组
+    /// 删除分组及其全部子分组，其中的密码与结构化条目移入回收站
     pub fn delete_group(&self, id: i64) -> Result<(), String> {
-        let conn = self.get_connection().map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM groups WHERE id = ?", [id])
-            .map_err(|e| e.to_string())?;
-        Ok(())
+        self.delete_group_tree("groups", &["passwords", "vault_items"], id)
     }
 
     /// 拖拽重排分组（支持跨层级，源/目标父级压实）
@@ -476,11 +1607,9 @@ impl DatabaseService {
         }
     }
 
+    /// 删除笔记分组及其全部子分组，其中的笔记移入回收站
     pub fn delete_note_group(&self, id: i64) -> Result<(), String> {
-        let conn = self.get_connection().map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM secure_record_groups WHERE id = ?", [id])
-            .map_err(|e| e.to_string())?;
-        Ok(())
+        self.delete_group_tree("secure_record_groups", &["secure_records"], id)
     }
 
     /// 拖拽重排便签分组（支持跨层级，源/目标父级压实）
@@ -697,15 +1826,33 @@ impl DatabaseService {
 
     // --- Notes ---
 
+    /// 获取笔记列表（按标题排序）
+    ///
+    /// 传入 `cipher` 时返回解密后的笔记；标题加密存储时在解密后重新排序。
     pub fn get_notes(
         &self,
         group_id: Option<i64>,
+        cipher: Option<&crate::services::field_encryption::FieldCipher>,
+    ) -> Result<Vec<crate::models::note::SecureRecord>, String> {
+        let mut notes = self.get_notes_sql(group_id)?;
+        if let Some(cipher) = cipher {
+            notes.iter_mut().for_each(|note| cipher.decrypt_note(note));
+            if cipher.policy.note_title {
+                notes.sort_by(|a, b| a.title.cmp(&b.title));
+            }
+        }
+        Ok(notes)
+    }
+
+    fn get_notes_sql(
+        &self,
+        group_id: Option<i64>,
     ) -> Result<Vec<crate::models::note::SecureRecord>, String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
         let sql = if group_id.is_some() {
-            "SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records WHERE group_id = ? ORDER BY title"
+            "SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records WHERE group_id = ? AND deleted_at IS NULL ORDER BY title"
         } else {
-            "SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records ORDER BY title"
+            "SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records WHERE deleted_at IS NULL ORDER BY title"
         };
         let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
 
@@ -726,7 +1873,7 @@ impl DatabaseService {
 
     pub fn get_note(&self, id: i64) -> Result<Option<crate::models::note::SecureRecord>, String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
-        let mut stmt = conn.prepare("SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records WHERE id = ?").map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare("SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records WHERE id = ? AND deleted_at IS NULL").map_err(|e| e.to_string())?;
         let mut iter = stmt
             .query_map([id], Self::map_note_row)
             .map_err(|e| e.to_string())?;
@@ -772,30 +1919,233 @@ impl DatabaseService {
         }
     }
 
+    /// 删除笔记（移入回收站）
     pub fn delete_note(&self, id: i64) -> Result<(), String> {
+        self.move_to_trash("secure_records", id)
+    }
+
+    /// 彻底删除笔记（连同其附件）
+    fn purge_note(conn: &Connection, id: i64) -> Result<()> {
+        Self::delete_owned_attachments(conn, "note_id", id)?;
+        conn.execute("DELETE FROM revisions WHERE item_type = 'secure_note' AND item_id = ?", [id])?;
+        conn.execute("DELETE FROM secure_records WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    // --- Trash ---
+
+    /// 回收站条目所在的表及其分组表
+    fn trash_tables(item_type: crate::models::item::ItemType) -> (&'static str, &'static str) {
+        match item_type {
+            crate::models::item::ItemType::Login => ("passwords", "groups"),
+            crate::models::item::ItemType::SecureNote => ("secure_records", "secure_record_groups"),
+            _ => ("vault_items", "groups"),
+        }
+    }
+
+    /// 将条目移入回收站；已在回收站中的条目保留原删除时间
+    fn move_to_trash(&self, table: &str, id: i64) -> Result<(), String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        conn.execute(
+            &format!("UPDATE {table} SET deleted_at = datetime('now') WHERE id = ?1 AND deleted_at IS NULL"),
+            [id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// 在事务中删除分组子树，并将子树内的条目移入回收站
+    fn delete_group_tree(&self, group_table: &str, item_tables: &[&str], id: i64) -> Result<(), String> {
+        let subtree = format!(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT ?1 UNION SELECT g.id FROM {group_table} g JOIN subtree s ON g.parent_id = s.id
+            )"
+        );
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for table in item_tables {
+            tx.execute(
+                &format!(
+                    "{subtree} UPDATE {table} SET deleted_at = datetime('now')
+                     WHERE deleted_at IS NULL AND group_id IN (SELECT id FROM subtree)"
+                ),
+                [id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.execute(
+            &format!("{subtree} DELETE FROM {group_table} WHERE id IN (SELECT id FROM subtree)"),
+            [id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// 回收站中的全部条目，按删除时间倒序
+    ///
+    /// 标题为存储值，按字段加密策略可能为密文。
+    pub fn get_trash(&self) -> Result<Vec<crate::models::item::TrashItem>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT item_type, id, title, group_id, deleted_at FROM passwords WHERE deleted_at IS NOT NULL
+                 UNION ALL SELECT item_type, id, title, group_id, deleted_at FROM secure_records WHERE deleted_at IS NOT NULL
+                 UNION ALL SELECT item_type, id, title, group_id, deleted_at FROM vault_items WHERE deleted_at IS NOT NULL
+                 ORDER BY deleted_at DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                let item_type: String = row.get(0)?;
+                Ok((item_type, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut items = Vec::new();
+        for row in rows {
+            let (item_type, id, title, group_id, deleted_at) = row.map_err(|e| e.to_string())?;
+            let Some(item_type) = crate::models::item::ItemType::parse(&item_type) else {
+                log::warn!("Skipping trashed item {} with unknown type '{}'", id, item_type);
+                continue;
+            };
+            items.push(crate::models::item::TrashItem { item_type, id, title, group_id, deleted_at });
+        }
+        Ok(items)
+    }
+
+    /// 从回收站还原条目；原分组已删除时还原到未分组
+    pub fn restore_from_trash(&self, item_type: crate::models::item::ItemType, id: i64) -> Result<(), String> {
+        let (table, group_table) = Self::trash_tables(item_type);
         let conn = self.get_connection().map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM secure_records WHERE id = ?", [id])
+        let restored = conn
+            .execute(
+                &format!(
+                    "UPDATE {table} SET deleted_at = NULL,
+                     group_id = CASE WHEN group_id IN (SELECT id FROM {group_table}) THEN group_id END
+                     WHERE id = ?1 AND deleted_at IS NOT NULL"
+                ),
+                [id],
+            )
             .map_err(|e| e.to_string())?;
+        if restored == 0 {
+            return Err("回收站中没有该条目".to_string());
+        }
         Ok(())
     }
 
-    pub fn search_notes(
+    /// 彻底删除回收站中的单个条目
+    pub fn purge_from_trash(&self, item_type: crate::models::item::ItemType, id: i64) -> Result<(), String> {
+        let (table, _) = Self::trash_tables(item_type);
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let trashed = tx
+            .prepare(&format!("SELECT 1 FROM {table} WHERE id = ?1 AND deleted_at IS NOT NULL"))
+            .and_then(|mut stmt| stmt.exists([id]))
+            .map_err(|e| e.to_string())?;
+        if !trashed {
+            return Err("回收站中没有该条目".to_string());
+        }
+        Self::purge_row(&tx, table, id).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// 彻底删除回收站中的条目，返回删除数量
+    ///
+    /// `older_than_days` 为 `None` 时清空回收站，否则只删除移入回收站超过该天数的条目。
+    pub fn purge_trash(&self, older_than_days: Option<u32>) -> Result<usize, String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let cutoff = older_than_days.map(|days| format!("-{} days", days));
+        let mut purged = 0;
+        for table in TRASHABLE_TABLES {
+            let ids = tx
+                .prepare(&format!(
+                    "SELECT id FROM {table} WHERE deleted_at IS NOT NULL
+                     AND (?1 IS NULL OR deleted_at <= datetime('now', ?1))"
+                ))
+                .and_then(|mut stmt| {
+                    stmt.query_map([&cutoff], |row| row.get::<_, i64>(0))?
+                        .collect::<Result<Vec<_>>>()
+                })
+                .map_err(|e| e.to_string())?;
+            for id in ids {
+                Self::purge_row(&tx, table, id).map_err(|e| e.to_string())?;
+                purged += 1;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(purged)
+    }
+
+    fn purge_row(conn: &Connection, table: &str, id: i64) -> Result<()> {
+        match table {
+            "passwords" => Self::purge_password(conn, id),
+            "secure_records" => Self::purge_note(conn, id),
+            _ => Self::purge_vault_item(conn, id),
+        }
+    }
+
+    // --- Breach Dataset ---
+
+    /// 在一个事务中写入泄露哈希；已存在的哈希保留较大的出现次数
+    pub fn insert_breach_hashes(&self, hashes: &[crate::models::breach::BreachHash]) -> Result<usize, String> {
+        let mut conn = self.get_connection().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO breach_hashes (kind, hash, count) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(kind, hash) DO UPDATE SET count = MAX(count, excluded.count)",
+                )
+                .map_err(|e| e.to_string())?;
+            for entry in hashes {
+                stmt.execute((entry.kind.as_str(), &entry.hash, entry.count))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(hashes.len())
+    }
+
+    /// 查询一组哈希在泄露数据中的出现次数；未出现为 `None`
+    pub fn find_breach_counts(
         &self,
-        keyword: &str,
-    ) -> Result<Vec<crate::models::note::SecureRecord>, String> {
+        hashes: &[(crate::models::breach::BreachHashKind, Vec<u8>)],
+    ) -> Result<Vec<Option<i64>>, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT count FROM breach_hashes WHERE kind = ?1 AND hash = ?2")
+            .map_err(|e| e.to_string())?;
+        hashes
+            .iter()
+            .map(|(kind, hash)| {
+                stmt.query_row((kind.as_str(), hash), |row| row.get(0))
+                    .optional()
+                    .map_err(|e| e.to_string())
+            })
+            .collect()
+    }
+
+    /// 各算法已导入的哈希数量
+    pub fn get_breach_dataset_info(&self) -> Result<crate::models::breach::BreachDatasetInfo, String> {
+        let conn = self.get_connection().map_err(|e| e.to_string())?;
+        let count = |kind: crate::models::breach::BreachHashKind| {
+            conn.query_row("SELECT COUNT(*) FROM breach_hashes WHERE kind = ?1", [kind.as_str()], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map_err(|e| e.to_string())
+        };
+        Ok(crate::models::breach::BreachDatasetInfo {
+            sha1_count: count(crate::models::breach::BreachHashKind::Sha1)?,
+            ntlm_count: count(crate::models::breach::BreachHashKind::Ntlm)?,
+        })
+    }
+
+    /// 清空泄露数据集
+    pub fn clear_breach_hashes(&self) -> Result<(), String> {
         let conn = self.get_connection().map_err(|e| e.to_string())?;
-        let pattern = format!("%{}%", keyword);
-        let mut stmt = conn.prepare(
-            "SELECT id, title, content, group_id, pinned, archived, created_at, updated_at FROM secure_records WHERE title LIKE ?1 OR content LIKE ?1 ORDER BY title"
-        ).map_err(|e| e.to_string())?;
-        let iter = stmt
-            .query_map([&pattern], Self::map_note_row)
+        conn.execute("DELETE FROM breach_hashes", [])
             .map_err(|e| e.to_string())?;
-        let mut notes = Vec::new();
-        for note in iter {
-            notes.push(note.map_err(|e| e.to_string())?);
-        }
-        Ok(notes)
+        Ok(())
     }
 
     // --- Settings ---
@@ -841,6 +2191,23 @@ impl DatabaseService {
         }
     }
 
+    /// 读取数值配置；未设置、无法解析或超出 `range` 时返回 `default`
+    pub fn get_user_setting_in_range<T>(
+        &self,
+        key: &str,
+        range: &std::ops::RangeInclusive<T>,
+        default: T,
+    ) -> Result<T, String>
+    where
+        T: std::str::FromStr + PartialOrd,
+    {
+        Ok(self
+            .get_user_setting(key)?
+            .and_then(|s| s.value.trim().parse::<T>().ok())
+            .filter(|value| range.contains(value))
+            .unwrap_or(default))
+    }
+
     pub fn set_user_setting(
         &self,
         setting: &crate::models::setting::UserSetting,
@@ -947,6 +2314,149 @@ impl DatabaseService {
         })
     }
 
+    fn map_totp_row(
+        row: &rusqlite::Row,
+    ) -> Result<crate::models::totp::PasswordTotp, rusqlite::Error> {
+        let algorithm: String = row.get(4)?;
+        Ok(crate::models::totp::PasswordTotp {
+            password_id: row.get(0)?,
+            secret: row.get(1)?,
+            issuer: row.get(2)?,
+            account_name: row.get(3)?,
+            algorithm: crate::models::totp::TotpAlgorithm::parse(&algorithm).unwrap_or_default(),
+            digits: row.get(5)?,
+            period: row.get::<_, i64>(6)?.max(1) as u64,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+
+    fn map_custom_field_row(
+        row: &rusqlite::Row,
+    ) -> Result<crate::models::custom_field::CustomField, rusqlite::Error> {
+        use crate::models::custom_field::CustomFieldType;
+
+        let field_type = CustomFieldType::parse(&row.get::<_, String>(2)?).unwrap_or_default();
+        let value: Option<crate::models::SecretString> = if field_type.is_secret() {
+            row.get(5)?
+        } else {
+            row.get(4)?
+        };
+        Ok(crate::models::custom_field::CustomField {
+            id: row.get(0)?,
+            password_id: row.get(1)?,
+            field_type,
+            label: row.get(3)?,
+            value: value.unwrap_or_default(),
+            sort_order: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+
+    fn map_password_url_row(
+        row: &rusqlite::Row,
+    ) -> Result<crate::models::password::PasswordUrl, rusqlite::Error> {
+        use crate::models::password::UrlMatchRule;
+
+        Ok(crate::models::password::PasswordUrl {
+            id: row.get(0)?,
+            password_id: row.get(1)?,
+            url: row.get(2)?,
+            match_rule: UrlMatchRule::parse(&row.get::<_, String>(3)?).unwrap_or_default(),
+            sort_order: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+
+    fn map_password_account_row(
+        row: &rusqlite::Row,
+    ) -> Result<crate::models::password::PasswordAccount, rusqlite::Error> {
+        Ok(crate::models::password::PasswordAccount {
+            id: row.get(0)?,
+            password_id: row.get(1)?,
+            label: row.get(2)?,
+            username: row.get(3)?,
+            password: row.get(4)?,
+            sort_order: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+        })
+    }
+
+    fn map_attachment_row(
+        row: &rusqlite::Row,
+    ) -> Result<crate::models::attachment::Attachment, rusqlite::Error> {
+        Ok(crate::models::attachment::Attachment {
+            id: row.get(0)?,
+            password_id: row.get(1)?,
+            note_id: row.get(2)?,
+            file_name: row.get(3)?,
+            mime_type: row.get(4)?,
+            size: row.get(5)?,
+            chunk_count: row.get(6)?,
+            file_key: row.get(7)?,
+            created_at: row.get(8)?,
+        })
+    }
+
+    fn map_vault_item_row(
+        row: &rusqlite::Row,
+    ) -> Result<crate::models::item::VaultItem, rusqlite::Error> {
+        let item_type: String = row.get(1)?;
+        let item_type = crate::models::item::ItemType::parse(&item_type).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                1,
+                rusqlite::types::Type::Text,
+                format!("unknown item type '{}'", item_type).into(),
+            )
+        })?;
+        Ok(crate::models::item::VaultItem {
+            id: row.get(0)?,
+            item_type,
+            title: row.get(2)?,
+            group_id: row.get(3)?,
+            notes: row.get(4)?,
+            favorite: row.get(5)?,
+            fields: Default::default(),
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+        })
+    }
+
+    fn map_revision_row(row: &rusqlite::Row) -> Result<crate::models::revision::Revision, rusqlite::Error> {
+        let item_type: String = row.get(1)?;
+        let item_type = crate::models::item::ItemType::parse(&item_type).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                1,
+                rusqlite::types::Type::Text,
+                format!("unknown item type '{}'", item_type).into(),
+            )
+        })?;
+        let changed_fields: String = row.get(3)?;
+        Ok(crate::models::revision::Revision {
+            id: row.get(0)?,
+            item_type,
+            item_id: row.get(2)?,
+            changed_fields: serde_json::from_str(&changed_fields).unwrap_or_default(),
+            snapshot: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+
+    fn map_vault_key_row(
+        row: &rusqlite::Row,
+    ) -> Result<crate::models::vault::VaultKey, rusqlite::Error> {
+        Ok(crate::models::vault::VaultKey {
+            kind: row.get(0)?,
+            wrapped_key: row.get(1)?,
+            kdf_salt: row.get(2)?,
+            kdf_params: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
+    }
+
     /// 检查数据库是否存在
     pub fn exists(&self) -> bool {
         Path::new(&self.db_path).exists()
@@ -976,6 +2486,37 @@ impl DatabaseService {
         conn.execute_batch(CREATE_TABLES_SQL)
             .map_err(|e| format!("创建表失败: {}", e))?;
 
+        Self::migrate(&conn).map_err(|e| format!("数据库迁移失败: {}", e))?;
+
+        Ok(())
+    }
+
+    /// 为旧版数据库补齐新增列
+    ///
+    /// 条目类型列带默认值，已有的密码条目与笔记分别成为“登录”与“安全笔记”类型；
+    /// `deleted_at` 非空表示条目位于回收站。
+    fn migrate(conn: &Connection) -> Result<()> {
+        Self::ensure_column(conn, "passwords", "item_type", "TEXT NOT NULL DEFAULT 'login'")?;
+        Self::ensure_column(conn, "secure_records", "item_type", "TEXT NOT NULL DEFAULT 'secure_note'")?;
+        for table in TRASHABLE_TABLES {
+            Self::ensure_column(conn, table, "deleted_at", "TEXT")?;
+            conn.execute(
+                &format!("CREATE INDEX IF NOT EXISTS idx_{table}_deleted_at ON {table}(deleted_at)"),
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 列不存在时添加
+    fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+        let exists = conn
+            .prepare(&format!("SELECT 1 FROM pragma_table_info('{table}') WHERE name = ?1"))?
+            .exists([column])?;
+        if !exists {
+            conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"), [])?;
+            log::info!("Added column {}.{}", table, column);
+        }
         Ok(())
     }
 }
@@ -1010,6 +2551,8 @@ CREATE TABLE IF NOT EXISTS passwords (
     use_count INTEGER DEFAULT 0,
     favorite INTEGER DEFAULT 0,
     tags TEXT,
+    item_type TEXT NOT NULL DEFAULT 'login',
+    deleted_at TEXT,
     FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE SET NULL
 );
 
@@ -1023,6 +2566,126 @@ CREATE TABLE IF NOT EXISTS password_history (
     FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
 );
 
+-- 密码条目与笔记的修订历史（snapshot 为修改前全部字段的加密 JSON）
+CREATE TABLE IF NOT EXISTS revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_type TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    changed_fields TEXT NOT NULL,
+    snapshot TEXT NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 一次性密码（TOTP）配置，每个密码条目至多一条
+CREATE TABLE IF NOT EXISTS password_totp (
+    password_id INTEGER PRIMARY KEY,
+    secret TEXT NOT NULL,
+    issuer TEXT,
+    account_name TEXT,
+    algorithm TEXT NOT NULL DEFAULT 'SHA1',
+    digits INTEGER NOT NULL DEFAULT 6,
+    period INTEGER NOT NULL DEFAULT 30,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
+
+-- 密码条目自定义字段（机密字段密文存于 secret_value，其余明文存于 value）
+CREATE TABLE IF NOT EXISTS password_custom_fields (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    password_id INTEGER NOT NULL,
+    field_type TEXT NOT NULL DEFAULT 'text',
+    label TEXT NOT NULL,
+    value TEXT,
+    secret_value TEXT,
+    sort_order INTEGER DEFAULT 0,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
+
+-- 密码条目附加登录网址
+CREATE TABLE IF NOT EXISTS password_urls (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    password_id INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    match_rule TEXT NOT NULL DEFAULT 'host',
+    sort_order INTEGER DEFAULT 0,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
+
+-- 密码条目附加账号（同一凭据下的多组用户名/密码）
+CREATE TABLE IF NOT EXISTS password_accounts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    password_id INTEGER NOT NULL,
+    label TEXT,
+    username TEXT,
+    password TEXT,
+    sort_order INTEGER DEFAULT 0,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
+
+-- 附件元数据，属于一个密码条目或一条安全笔记（文件密钥由数据密钥包装）
+CREATE TABLE IF NOT EXISTS attachments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    password_id INTEGER,
+    note_id INTEGER,
+    file_name TEXT NOT NULL,
+    mime_type TEXT,
+    size INTEGER NOT NULL DEFAULT 0,
+    chunk_count INTEGER NOT NULL DEFAULT 0,
+    file_key TEXT NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((password_id IS NULL) <> (note_id IS NULL)),
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE,
+    FOREIGN KEY (note_id) REFERENCES secure_records(id) ON DELETE CASCADE
+);
+
+-- 附件内容分块（以附件的文件密钥加密）
+CREATE TABLE IF NOT EXISTS attachment_chunks (
+    attachment_id INTEGER NOT NULL,
+    chunk_index INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    PRIMARY KEY (attachment_id, chunk_index),
+    FOREIGN KEY (attachment_id) REFERENCES attachments(id) ON DELETE CASCADE
+);
+
+-- 结构化条目（银行卡、身份、SSH 密钥、Wi-Fi、API 凭据），与密码条目共用分组
+CREATE TABLE IF NOT EXISTS vault_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    group_id INTEGER,
+    notes TEXT,
+    favorite INTEGER DEFAULT 0,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TEXT,
+    FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE SET NULL
+);
+
+-- 结构化条目的字段（机密字段密文存于 secret_value，其余明文存于 value）
+CREATE TABLE IF NOT EXISTS vault_item_fields (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT,
+    secret_value TEXT,
+    UNIQUE (item_id, name),
+    FOREIGN KEY (item_id) REFERENCES vault_items(id) ON DELETE CASCADE
+);
+
+-- 离线泄露密码数据集（SHA-1 或 NTLM 哈希，二进制存储）
+CREATE TABLE IF NOT EXISTS breach_hashes (
+    kind TEXT NOT NULL,
+    hash BLOB NOT NULL,
+    count INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (kind, hash)
+) WITHOUT ROWID;
+
 -- 用户设置表
 CREATE TABLE IF NOT EXISTS user_settings (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -1058,6 +2721,8 @@ CREATE TABLE IF NOT EXISTS secure_records (
     archived INTEGER DEFAULT 0,
     created_at TEXT DEFAULT CURRENT_TIMESTAMP,
     updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    item_type TEXT NOT NULL DEFAULT 'secure_note',
+    deleted_at TEXT,
     FOREIGN KEY (group_id) REFERENCES secure_record_groups(id) ON DELETE SET NULL
 );
 
@@ -1071,10 +2736,53 @@ CREATE TABLE IF NOT EXISTS master_password (
     updated_at TEXT DEFAULT CURRENT_TIMESTAMP
 );
 
+-- 数据密钥表（数据密钥的各种包装形式）
+CREATE TABLE IF NOT EXISTS vault_keys (
+    kind TEXT PRIMARY KEY,
+    wrapped_key TEXT NOT NULL,
+    kdf_salt TEXT,
+    kdf_params TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 未完成的重新加密任务（修改主密码时轮换数据密钥）
+CREATE TABLE IF NOT EXISTS vault_rekey (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    wrapped_new_key TEXT NOT NULL,
+    new_keys TEXT NOT NULL,
+    password_hash TEXT,
+    hint TEXT,
+    started_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 解锁失败节流
+CREATE TABLE IF NOT EXISTS unlock_throttle (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    cooldown_until TEXT,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 解锁尝试记录
+CREATE TABLE IF NOT EXISTS unlock_attempts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    attempted_at TEXT NOT NULL,
+    success INTEGER NOT NULL DEFAULT 0,
+    method TEXT NOT NULL DEFAULT 'master_password'
+);
+
 -- 索引
 CREATE INDEX IF NOT EXISTS idx_passwords_group_id ON passwords(group_id);
 CREATE INDEX IF NOT EXISTS idx_passwords_title ON passwords(title);
 CREATE INDEX IF NOT EXISTS idx_password_history_password_id ON password_history(password_id);
+CREATE INDEX IF NOT EXISTS idx_revisions_item ON revisions(item_type, item_id);
+CREATE INDEX IF NOT EXISTS idx_password_custom_fields_password_id ON password_custom_fields(password_id);
+CREATE INDEX IF NOT EXISTS idx_password_urls_password_id ON password_urls(password_id);
+CREATE INDEX IF NOT EXISTS idx_password_accounts_password_id ON password_accounts(password_id);
+CREATE INDEX IF NOT EXISTS idx_attachments_password_id ON attachments(password_id);
+CREATE INDEX IF NOT EXISTS idx_attachments_note_id ON attachments(note_id);
+CREATE INDEX IF NOT EXISTS idx_vault_items_item_type ON vault_items(item_type);
 CREATE INDEX IF NOT EXISTS idx_groups_parent_id ON groups(parent_id);
 CREATE INDEX IF NOT EXISTS idx_user_settings_key ON user_settings(key);
 CREATE INDEX IF NOT EXISTS idx_secure_records_group_id ON secure_records(group_id);
@@ -1106,10 +2814,22 @@ mod tests {
             "groups",
             "passwords",
             "password_history",
+            "password_totp",
+            "password_custom_fields",
+            "password_urls",
+            "password_accounts",
+            "attachments",
+            "attachment_chunks",
+            "vault_items",
+            "vault_item_fields",
             "user_settings",
             "secure_record_groups",
             "secure_records",
             "master_password",
+            "vault_keys",
+            "vault_rekey",
+            "unlock_throttle",
+            "unlock_attempts",
         ];
 
         for table in tables {
@@ -1119,7 +2839,7 @@ mod tests {
                     [table],
                     |row| row.get(0),
                 )
-                .expect(&format!("Failed to query table {}", table));
+                .unwrap_or_else(|_| panic!("Failed to query table {}", table));
 
             assert_eq!(count, 1, "Table {} should exist", table);
         }
@@ -1137,7 +2857,7 @@ mod tests {
             id: None,
             title: "Test Password".to_string(),
             username: Some("user".to_string()),
-            password: Some("pass".to_string()),
+            password: Some("pass".into()),
             url: None,
             notes: None,
             group_id: None,
@@ -1164,17 +2884,327 @@ mod tests {
         let updated = db_service.get_password(id).unwrap().unwrap();
         assert_eq!(updated.title, "Updated Title");
 
-        // 4. Search
-        let results = db_service.search_passwords("Updated").unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].id, Some(id));
-
-        // 5. Delete
+        // 4. Delete
         db_service.delete_password(id).unwrap();
         let deleted = db_service.get_password(id).unwrap();
         assert!(deleted.is_none());
     }
 
+    #[test]
+    fn test_password_totp_crud() {
+        let dir = tempdir().unwrap();
+        let db_service = DatabaseService::new(dir.path().join("test_totp.db").to_str().unwrap());
+        db_service.initialize().unwrap();
+        let password: crate::models::password::Password =
+            serde_json::from_value(serde_json::json!({ "title": "GitHub" })).unwrap();
+        let id = db_service.add_password(&password).unwrap();
+
+        let mut totp = crate::services::totp::parse_otpauth_uri(
+            "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&algorithm=SHA512&digits=8",
+        )
+        .unwrap();
+        totp.password_id = id;
+        db_service.set_password_totp(&totp).unwrap();
+        totp.period = 60;
+        db_service.set_password_totp(&totp).unwrap();
+
+        let stored = db_service.get_password_totp(id).unwrap().unwrap();
+        assert_eq!(stored.algorithm, crate::models::totp::TotpAlgorithm::Sha512);
+        assert_eq!((stored.digits, stored.period), (8, 60));
+        assert_eq!(db_service.get_all_password_totp().unwrap().len(), 1);
+
+        // 彻底删除密码条目时一并删除 TOTP 配置
+        db_service.delete_password(id).unwrap();
+        assert!(db_service.get_password_totp(id).unwrap().is_some());
+        db_service.purge_from_trash(crate::models::item::ItemType::Login, id).unwrap();
+        assert!(db_service.get_password_totp(id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_custom_fields_crud_and_search() {
+        use crate::models::custom_field::{CustomField, CustomFieldType};
+
+        let dir = tempdir().unwrap();
+        let db_service = DatabaseService::new(dir.path().join("test_custom_fields.db").to_str().unwrap());
+        db_service.initialize().unwrap();
+        let password: crate::models::password::Password =
+            serde_json::from_value(serde_json::json!({ "title": "Router" })).unwrap();
+        let id = db_service.add_password(&password).unwrap();
+
+        let field = |field_type, label: &str, value: &str| CustomField {
+            id: None,
+            password_id: id,
+            field_type,
+            label: label.to_string(),
+            value: value.into(),
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let serial_id = db_service
+            .add_custom_field(&field(CustomFieldType::Text, "Serial", "SN-778899"))
+            .unwrap();
+        db_service
+            .add_custom_field(&field(CustomFieldType::Hidden, "PIN", "ciphertext-pin"))
+            .unwrap();
+
+        let fields = db_service.get_custom_fields(id).unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].value.expose(), "ciphertext-pin");
+
+        // 非机密字段可搜索，机密字段不参与
+        let cipher = crate::services::field_encryption::FieldCipher::new(
+            Default::default(),
+            crate::services::encryption::EncryptionService::new("test_key"),
+        );
+        let related = |keyword| db_service.search_related_password_ids(keyword, &cipher).unwrap();
+        assert_eq!(related("778899"), HashSet::from([id]));
+        assert!(related("ciphertext").is_empty());
+
+        let mut serial = db_service.get_custom_field(serial_id).unwrap().unwrap();
+        serial.field_type = CustomFieldType::Hidden;
+        db_service.update_custom_field(&serial).unwrap();
+        assert!(related("778899").is_empty());
+
+        db_service.delete_custom_field(serial_id).unwrap();
+        assert_eq!(db_service.get_custom_fields(id).unwrap().len(), 1);
+        db_service.delete_password(id).unwrap();
+        db_service.purge_from_trash(crate::models::item::ItemType::Login, id).unwrap();
+        assert!(db_service.get_all_custom_fields().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_password_urls_and_accounts() {
+        use crate::models::password::{PasswordAccount, PasswordUrl, UrlMatchRule};
+
+        let dir = tempdir().unwrap();
+        let db_service = DatabaseService::new(dir.path().join("test_multi.db").to_str().unwrap());
+        db_service.initialize().unwrap();
+        let password: crate::models::password::Password =
+            serde_json::from_value(serde_json::json!({ "title": "Cloud", "url": "https://console.example.com" }))
+                .unwrap();
+        let id = db_service.add_password(&password).unwrap();
+
+        let url = |url: &str, match_rule| PasswordUrl {
+            id: None,
+            password_id: id,
+            url: url.to_string(),
+            match_rule,
+            sort_order: None,
+            created_at: None,
+        };
+        db_service
+            .replace_password_urls(
+                id,
+                &[
+                    url("https://sso.example.net", UrlMatchRule::BaseDomain),
+                    url("https://billing.example.com/", UrlMatchRule::Exact),
+                ],
+            )
+            .unwrap();
+        let urls = db_service.get_password_urls(id).unwrap();
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0].match_rule, UrlMatchRule::BaseDomain);
+        assert_eq!(urls[1].sort_order, Some(1));
+
+        // 搜索覆盖附加网址
+        let cipher = crate::services::field_encryption::FieldCipher::new(
+            Default::default(),
+            crate::services::encryption::EncryptionService::new("test_key"),
+        );
+        assert_eq!(db_service.search_related_password_ids("sso.example", &cipher).unwrap(), HashSet::from([id]));
+
+        let account_id = db_service
+            .add_password_account(&PasswordAccount {
+                id: None,
+                password_id: id,
+                label: Some("Admin".to_string()),
+                username: Some("root".to_string()),
+                password: Some("cipher".into()),
+                sort_order: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .unwrap();
+        let mut account = db_service.get_password_account(account_id).unwrap().unwrap();
+        account.username = Some("admin".to_string());
+        db_service.update_password_account(&account).unwrap();
+        assert_eq!(
+            db_service.get_password_accounts(id).unwrap()[0].username.as_deref(),
+            Some("admin")
+        );
+
+        db_service.delete_password(id).unwrap();
+        db_service.purge_from_trash(crate::models::item::ItemType::Login, id).unwrap();
+        assert!(db_service.get_all_password_urls().unwrap().is_empty());
+        assert!(db_service.get_all_password_accounts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_attachments_are_chunked_and_removed_with_owner() {
+        use crate::models::attachment::Attachment;
+        use crate::services::{attachments, encryption::EncryptionService};
+
+        let dir = tempdir().unwrap();
+        let db_service = DatabaseService::new(dir.path().join("test_attachments.db").to_str().unwrap());
+        db_service.initialize().unwrap();
+        let note: crate::models::note::SecureRecord =
+            serde_json::from_value(serde_json::json!({ "title": "Server" })).unwrap();
+        let note_id = db_service.add_note(&note).unwrap();
+
+        let encryption = EncryptionService::new("test_key");
+        let content = vec![42u8; attachments::CHUNK_SIZE + 1];
+        let mut attachment = Attachment {
+            note_id: Some(note_id),
+            file_name: "id_ed25519".to_string(),
+            ..Default::default()
+        };
+        let chunks = attachments::seal(&encryption, &mut attachment, &content).unwrap();
+        let id = db_service.add_attachment(&attachment, &chunks).unwrap();
+
+        assert_eq!(db_service.get_attachments(None, Some(note_id)).unwrap().len(), 1);
+        assert!(db_service.get_attachments(Some(note_id), None).unwrap().is_empty());
+        let stored = db_service.get_attachment(id).unwrap().unwrap();
+        assert_eq!(stored.chunk_count, 2);
+        assert_eq!(
+            attachments::read(&db_service, &encryption, &stored).unwrap().expose(),
+            content.as_slice()
+        );
+
+        db_service.delete_note(note_id).unwrap();
+        assert!(db_service.get_all_attachments().unwrap().is_empty());
+        assert_eq!(db_service.get_attachment_chunks(id).unwrap().len(), 2);
+        db_service.purge_from_trash(crate::models::item::ItemType::SecureNote, note_id).unwrap();
+        assert!(db_service.get_attachment(id).unwrap().is_none());
+        assert!(db_service.get_attachment_chunks(id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migration_assigns_item_types_to_existing_entries() {
+        let dir = tempdir().unwrap();
+        let db_service = DatabaseService::new(dir.path().join("test_legacy.db").to_str().unwrap());
+        {
+            let conn = db_service.get_connection().unwrap();
+            conn.execute_batch(
+                "CREATE TABLE passwords (id INTEGER PRIMARY KEY, title TEXT NOT NULL, group_id INTEGER, updated_at TEXT);
+                 CREATE TABLE secure_records (id INTEGER PRIMARY KEY, title TEXT NOT NULL, group_id INTEGER, updated_at TEXT);
+                 INSERT INTO passwords (title) VALUES ('GitHub');
+                 INSERT INTO secure_records (title) VALUES ('Wi-Fi notes');",
+            )
+            .unwrap();
+        }
+        db_service.initialize().unwrap();
+        // 重复初始化不会重复添加列
+        db_service.initialize().unwrap();
+
+        let mut types: Vec<_> = db_service
+            .get_item_summaries()
+            .unwrap()
+            .into_iter()
+            .map(|summary| (summary.title, summary.item_type))
+            .collect();
+        types.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            types,
+            vec![
+                ("GitHub".to_string(), crate::models::item::ItemType::Login),
+                ("Wi-Fi notes".to_string(), crate::models::item::ItemType::SecureNote),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vault_item_crud_stores_secret_fields_separately() {
+        use crate::models::item::{ItemType, VaultItem};
+
+        let dir = tempdir().unwrap();
+        let db_service = DatabaseService::new(dir.path().join("test_items.db").to_str().unwrap());
+        db_service.initialize().unwrap();
+        let mut card: VaultItem = serde_json::from_value(serde_json::json!({
+            "itemType": "card",
+            "title": "Visa",
+            "fields": { "cardholder": "Alice", "number": "cipher-text" }
+        }))
+        .unwrap();
+        let id = db_service.add_vault_item(&card).unwrap();
+
+        let conn = db_service.get_connection().unwrap();
+        let (value, secret): (Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT value, secret_value FROM vault_item_fields WHERE item_id = ?1 AND name = 'number'",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((value, secret.as_deref()), (None, Some("cipher-text")));
+
+        card.id = Some(id);
+        card.fields.remove("cardholder");
+        card.title = "Visa Gold".to_string();
+        db_service.update_vault_item(&card).unwrap();
+        let stored = db_service.get_vault_item(id).unwrap().unwrap();
+        assert_eq!(stored.title, "Visa Gold");
+        assert_eq!(stored.fields.len(), 1);
+        assert_eq!(db_service.get_vault_items(Some(ItemType::Card)).unwrap().len(), 1);
+        assert!(db_service.get_vault_items(Some(ItemType::Wifi)).unwrap().is_empty());
+
+        db_service.delete_vault_item(id).unwrap();
+        assert!(db_service.get_vault_item(id).unwrap().is_none());
+        db_service.purge_from_trash(ItemType::Card, id).unwrap();
+        let orphans: i64 = conn
+            .query_row("SELECT COUNT(*) FROM vault_item_fields", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(orphans, 0);
+    }
+
+    #[test]
+    fn test_delete_group_moves_subtree_entries_to_trash() {
+        use crate::models::group::Group;
+        use crate::models::item::ItemType;
+        use crate::models::password::Password;
+
+        let dir = tempdir().unwrap();
+        let db_service = DatabaseService::new(dir.path().join("test_trash.db").to_str().unwrap());
+        db_service.initialize().unwrap();
+        let group = |name: &str, parent_id: Option<i64>| Group {
+            id: None,
+            name: name.to_string(),
+            parent_id,
+            icon: None,
+            color: None,
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let parent = db_service.add_group(&group("Work", None)).unwrap();
+        let child = db_service.add_group(&group("Servers", Some(parent))).unwrap();
+        let other = db_service.add_group(&group("Home", None)).unwrap();
+        let password = |title: &str, group_id: i64| -> Password {
+            serde_json::from_value(serde_json::json!({ "title": title, "group_id": group_id })).unwrap()
+        };
+        let in_parent = db_service.add_password(&password("Mail", parent)).unwrap();
+        let in_child = db_service.add_password(&password("SSH", child)).unwrap();
+        let kept = db_service.add_password(&password("Router", other)).unwrap();
+
+        db_service.delete_group(parent).unwrap();
+        assert!(db_service.get_group(child).unwrap().is_none());
+        assert!(db_service.get_group(other).unwrap().is_some());
+        let live: Vec<_> = db_service.get_passwords(None).unwrap().iter().filter_map(|p| p.id).collect();
+        assert_eq!(live, vec![kept]);
+        let mut trashed: Vec<_> = db_service.get_trash().unwrap().iter().map(|item| item.id).collect();
+        trashed.sort();
+        assert_eq!(trashed, vec![in_parent, in_child]);
+
+        // 原分组已删除，还原到未分组
+        db_service.restore_from_trash(ItemType::Login, in_child).unwrap();
+        assert_eq!(db_service.get_password(in_child).unwrap().unwrap().group_id, None);
+        assert!(db_service.restore_from_trash(ItemType::Login, kept).is_err());
+
+        assert_eq!(db_service.purge_trash(None).unwrap(), 1);
+        assert!(db_service.get_trash().unwrap().is_empty());
+        assert_eq!(db_service.get_passwords(None).unwrap().len(), 2);
+    }
+
     #[test]
     fn test_master_password_hash_nullable_roundtrip() {
         let dir = tempdir().unwrap();
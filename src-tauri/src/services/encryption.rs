@@ -1,23 +1,55 @@
 //! 加密服务
 //!
-//! 实现 AES-256-CBC 加密/解密，与 Electron 版本兼容
+//! 密文格式：Base64(格式字节 0x02 + 24 字节随机 nonce + XChaCha20-Poly1305 密文与 16 字节标签)
+//!
+//! 旧版（Electron 兼容）密文为 Base64(IV + AES-256-CBC 密文)，无完整性校验，仅在读取时兼容，
+//! 由后台升级任务逐步改写为新格式。
 
 use aes::Aes256;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
-use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
-use cbc::{Decryptor, Encryptor};
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use cbc::Decryptor;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
 use rand::Rng;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::models::{SecretBytes, SecretString};
 
-type Aes256CbcEnc = Encryptor<Aes256>;
 type Aes256CbcDec = Decryptor<Aes256>;
 
+/// 当前密文格式：XChaCha20-Poly1305
+const FORMAT_XCHACHA20_POLY1305: u8 = 0x02;
+const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+
+/// 密文格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherFormat {
+    /// 旧版 AES-256-CBC（无认证）
+    LegacyCbc,
+    /// 当前 AEAD 信封
+    XChaCha20Poly1305,
+}
+
 /// 加密服务
+///
+/// 释放时清零内存中的密钥。
+#[derive(Clone)]
 pub struct EncryptionService {
     key: [u8; 32],
+    /// 是否仍接受旧版 CBC 密文；全部数据升级后关闭，使篡改无法绕过认证
+    allow_legacy: bool,
 }
 
 use sha2::{Digest, Sha256};
 
+impl Drop for EncryptionService {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
 impl EncryptionService {
     /// 创建新的加密服务实例（基于用户密码）
     pub fn new(password: &str) -> Self {
@@ -27,10 +59,29 @@ impl EncryptionService {
         let result = hasher.finalize();
         key.copy_from_slice(&result);
 
-        EncryptionService { key }
+        EncryptionService { key, allow_legacy: true }
     }
 
-    /// 使用应用级固定密钥创建加密服务（推荐用于数据加密）
+    /// 使用原始 32 字节密钥创建加密服务（用于数据密钥/包装密钥）
+    pub fn from_key(key: [u8; 32]) -> Self {
+        EncryptionService { key, allow_legacy: true }
+    }
+
+    /// 不再接受旧版 CBC 密文
+    pub fn without_legacy(mut self) -> Self {
+        self.allow_legacy = false;
+        self
+    }
+
+    /// 原始密钥字节，仅供密钥包装使用
+    pub(crate) fn key_bytes(&self) -> &[u8; 32] {
+        &self.key
+    }
+
+    /// 使用应用级固定密钥创建加密服务
+    ///
+    /// 该密钥随程序分发，不能保护数据；仅用于读取旧版数据，
+    /// 以及在未要求主密码时包装数据密钥。
     pub fn new_with_app_key() -> Self {
         let app_key = Self::get_or_create_app_key();
         Self::new(&app_key)
@@ -50,42 +101,71 @@ impl EncryptionService {
     }
 
     /// 加密文本
-    /// 返回格式：Base64(IV + 加密数据)
+    /// 返回格式：Base64(格式字节 + nonce + 密文 + 标签)
     pub fn encrypt(&self, plaintext: &str) -> Result<String, String> {
         if plaintext.is_empty() {
             return Ok(String::new());
         }
 
-        // 生成随机 IV
-        let mut iv = [0u8; 16];
-        rand::thread_rng().fill(&mut iv);
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill(&mut nonce);
 
-        // PKCS7 填充
-        let plaintext_bytes = plaintext.as_bytes();
-        let block_size = 16;
-        let padding_len = block_size - (plaintext_bytes.len() % block_size);
-        let mut padded = plaintext_bytes.to_vec();
-        padded.extend(std::iter::repeat(padding_len as u8).take(padding_len));
-
-        // 加密
-        let cipher = Aes256CbcEnc::new(&self.key.into(), &iv.into());
-        let mut buffer = padded;
-        let buffer_len = buffer.len();
-        cipher
-            .encrypt_padded_mut::<aes::cipher::block_padding::NoPadding>(&mut buffer, buffer_len)
+        let cipher = XChaCha20Poly1305::new(&self.key.into());
+        let sealed = cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_bytes())
             .map_err(|e| format!("加密失败: {:?}", e))?;
 
-        // IV + 密文
-        let mut result = iv.to_vec();
-        result.extend(&buffer);
+        let mut result = Vec::with_capacity(1 + NONCE_LEN + sealed.len());
+        result.push(FORMAT_XCHACHA20_POLY1305);
+        result.extend_from_slice(&nonce);
+        result.extend(sealed);
 
         Ok(BASE64.encode(&result))
     }
 
+    /// 加密二进制数据（附件分块），`aad` 参与认证但不加密
+    /// 返回格式：格式字节 + nonce + 密文 + 标签（不做 Base64 编码）
+    pub fn encrypt_bytes(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill(&mut nonce);
+
+        let cipher = XChaCha20Poly1305::new(&self.key.into());
+        let sealed = cipher
+            .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+            .map_err(|e| format!("加密失败: {:?}", e))?;
+
+        let mut result = Vec::with_capacity(1 + NONCE_LEN + sealed.len());
+        result.push(FORMAT_XCHACHA20_POLY1305);
+        result.extend_from_slice(&nonce);
+        result.extend(sealed);
+        Ok(result)
+    }
+
+    /// 解密 [`encrypt_bytes`](Self::encrypt_bytes) 生成的二进制密文，`aad` 须与加密时一致
+    pub fn decrypt_bytes(&self, data: &[u8], aad: &[u8]) -> Result<SecretBytes, String> {
+        if data.first() != Some(&FORMAT_XCHACHA20_POLY1305) || data.len() < 1 + NONCE_LEN + TAG_LEN {
+            return Err("密文格式无效".to_string());
+        }
+        let nonce = XNonce::from_slice(&data[1..1 + NONCE_LEN]);
+        let cipher = XChaCha20Poly1305::new(&self.key.into());
+        cipher
+            .decrypt(nonce, Payload { msg: &data[1 + NONCE_LEN..], aad })
+            .map(SecretBytes::new)
+            .map_err(|_| "解密失败: 密文认证失败".to_string())
+    }
+
     /// 解密文本
-    pub fn decrypt(&self, ciphertext: &str) -> Result<String, String> {
+    pub fn decrypt(&self, ciphertext: &str) -> Result<SecretString, String> {
+        self.decrypt_with_format(ciphertext).map(|(plain, _)| plain)
+    }
+
+    /// 解密文本，并返回密文所用格式
+    pub fn decrypt_with_format(
+        &self,
+        ciphertext: &str,
+    ) -> Result<(SecretString, CipherFormat), String> {
         if ciphertext.is_empty() {
-            return Ok(String::new());
+            return Ok((SecretString::default(), CipherFormat::XChaCha20Poly1305));
         }
 
         // Base64 解码
@@ -93,6 +173,45 @@ impl EncryptionService {
             .decode(ciphertext)
             .map_err(|e| format!("Base64 解码失败: {}", e))?;
 
+        let aead_error = if data.first() == Some(&FORMAT_XCHACHA20_POLY1305)
+            && data.len() >= 1 + NONCE_LEN + TAG_LEN
+        {
+            match self.decrypt_aead(&data) {
+                Ok(plain) => return Ok((plain, CipherFormat::XChaCha20Poly1305)),
+                Err(e) => Some(e),
+            }
+        } else {
+            None
+        };
+
+        // 旧版 IV 的首字节可能恰好等于格式字节，因此认证失败后仍尝试旧格式
+        if self.allow_legacy && data.len() % 16 == 0 {
+            if let Ok(plain) = self.decrypt_legacy_cbc(&data) {
+                return Ok((plain, CipherFormat::LegacyCbc));
+            }
+        }
+
+        Err(aead_error.unwrap_or_else(|| "密文格式无效".to_string()))
+    }
+
+    /// 密文是否仍为旧格式（需能被当前密钥解密）
+    pub fn needs_upgrade(&self, ciphertext: &str) -> bool {
+        matches!(
+            self.decrypt_with_format(ciphertext),
+            Ok((_, CipherFormat::LegacyCbc))
+        )
+    }
+
+    fn decrypt_aead(&self, data: &[u8]) -> Result<SecretString, String> {
+        let nonce = XNonce::from_slice(&data[1..1 + NONCE_LEN]);
+        let cipher = XChaCha20Poly1305::new(&self.key.into());
+        let plain = cipher
+            .decrypt(nonce, &data[1 + NONCE_LEN..])
+            .map_err(|_| "解密失败: 密文认证失败".to_string())?;
+        utf8_secret(Zeroizing::new(plain))
+    }
+
+    fn decrypt_legacy_cbc(&self, data: &[u8]) -> Result<SecretString, String> {
         if data.len() < 17 {
             return Err("密文太短".to_string());
         }
@@ -103,19 +222,53 @@ impl EncryptionService {
 
         // 解密
         let cipher = Aes256CbcDec::new(&self.key.into(), &iv.into());
-        let mut buffer = encrypted.to_vec();
+        let mut buffer = Zeroizing::new(encrypted.to_vec());
         cipher
             .decrypt_padded_mut::<aes::cipher::block_padding::NoPadding>(&mut buffer)
             .map_err(|e| format!("解密失败: {:?}", e))?;
 
         // 移除 PKCS7 填充
         let padding_len = *buffer.last().ok_or("空数据")? as usize;
-        if padding_len > 16 || padding_len > buffer.len() {
+        if padding_len == 0 || padding_len > 16 || padding_len > buffer.len() {
+            return Err("填充无效".to_string());
+        }
+        if !buffer[buffer.len() - padding_len..].iter().all(|&b| b as usize == padding_len) {
             return Err("填充无效".to_string());
         }
-        buffer.truncate(buffer.len() - padding_len);
+        let plain_len = buffer.len() - padding_len;
+        buffer.truncate(plain_len);
+
+        utf8_secret(buffer)
+    }
+
+    /// 生成旧版 AES-256-CBC 密文，用于兼容性测试
+    #[cfg(test)]
+    pub(crate) fn encrypt_legacy_cbc(&self, plaintext: &str) -> String {
+        use cbc::cipher::BlockEncryptMut;
+        let mut iv = [0u8; 16];
+        rand::thread_rng().fill(&mut iv);
+        let padding_len = 16 - plaintext.len() % 16;
+        let mut buffer = plaintext.as_bytes().to_vec();
+        buffer.extend(std::iter::repeat_n(padding_len as u8, padding_len));
+        let len = buffer.len();
+        cbc::Encryptor::<Aes256>::new(&self.key.into(), &iv.into())
+            .encrypt_padded_mut::<aes::cipher::block_padding::NoPadding>(&mut buffer, len)
+            .unwrap();
+        let mut result = iv.to_vec();
+        result.extend(buffer);
+        BASE64.encode(result)
+    }
+}
 
-        String::from_utf8(buffer).map_err(|e| format!("UTF-8 解码失败: {}", e))
+/// 将明文缓冲区转换为 SecretString，失败时同样清零缓冲区
+fn utf8_secret(mut buffer: Zeroizing<Vec<u8>>) -> Result<SecretString, String> {
+    match String::from_utf8(std::mem::take(&mut *buffer)) {
+        Ok(plain) => Ok(SecretString::new(plain)),
+        Err(e) => {
+            let message = format!("UTF-8 解码失败: {}", e.utf8_error());
+            e.into_bytes().zeroize();
+            Err(message)
+        }
     }
 }
 
@@ -133,7 +286,45 @@ mod tests {
         assert_ne!(encrypted, plaintext);
 
         let decrypted = service.decrypt(&encrypted).unwrap();
-        assert_eq!(decrypted, plaintext);
+        assert_eq!(decrypted.expose(), plaintext);
+    }
+
+    #[test]
+    fn test_envelope_format_and_tamper_detection() {
+        let service = EncryptionService::new("test_key");
+        let encrypted = service.encrypt("secret").unwrap();
+        let mut data = BASE64.decode(&encrypted).unwrap();
+        assert_eq!(data[0], FORMAT_XCHACHA20_POLY1305);
+        assert_eq!(data.len(), 1 + NONCE_LEN + "secret".len() + TAG_LEN);
+        assert!(!service.needs_upgrade(&encrypted));
+
+        let last = data.len() - 1;
+        data[last] ^= 0x01;
+        assert!(service.decrypt(&BASE64.encode(&data)).is_err());
+    }
+
+    #[test]
+    fn test_legacy_cbc_is_readable_until_disabled() {
+        let service = EncryptionService::new("test_key");
+        let legacy = service.encrypt_legacy_cbc("旧数据");
+        let (plain, format) = service.decrypt_with_format(&legacy).unwrap();
+        assert_eq!((plain.expose(), format), ("旧数据", CipherFormat::LegacyCbc));
+        assert!(service.needs_upgrade(&legacy));
+
+        let strict = service.clone().without_legacy();
+        assert!(strict.decrypt(&legacy).is_err());
+        let upgraded = service.encrypt("新数据").unwrap();
+        assert_eq!(strict.decrypt(&upgraded).unwrap().expose(), "新数据");
+    }
+
+    #[test]
+    fn test_bytes_roundtrip_binds_aad() {
+        let service = EncryptionService::new("test_key");
+        let data = [0u8, 159, 146, 150, 255];
+        let sealed = service.encrypt_bytes(&data, b"chunk-0").unwrap();
+        assert_eq!(sealed.len(), 1 + NONCE_LEN + data.len() + TAG_LEN);
+        assert_eq!(service.decrypt_bytes(&sealed, b"chunk-0").unwrap().expose(), &data);
+        assert!(service.decrypt_bytes(&sealed, b"chunk-1").is_err());
     }
 
     #[test]
@@ -144,6 +335,6 @@ mod tests {
         assert_eq!(encrypted, "");
 
         let decrypted = service.decrypt("").unwrap();
-        assert_eq!(decrypted, "");
+        assert!(decrypted.is_empty());
     }
 }
@@ -0,0 +1,373 @@
+//! 字段级加密
+//!
+//! `passwords.password` 与 `secure_records.content` 始终加密；用户名、网址、备注、标签
+//! 以及笔记标题按 [`FieldEncryptionPolicy`] 决定是否加密存储。
+//! 加密字段无法在 SQL 中匹配或排序，相关查询改为解密后在内存中完成。
+
+use crate::models::{EncryptableField, FieldEncryptionPolicy, Password, SecureRecord, VaultItem};
+use crate::services::database::DatabaseService;
+use crate::services::encryption::EncryptionService;
+use rusqlite::{Connection, OptionalExtension};
+
+/// 策略在 user_settings 中的键
+pub const FIELD_POLICY_SETTING: &str = "security.field_encryption";
+
+/// 读取字段加密策略；未设置时全部不加密
+pub fn load_policy(db: &DatabaseService) -> Result<FieldEncryptionPolicy, String> {
+    let conn = db.get_connection().map_err(|e| e.to_string())?;
+    load_policy_conn(&conn)
+}
+
+/// 在给定连接/事务上读取字段加密策略
+pub(crate) fn load_policy_conn(conn: &Connection) -> Result<FieldEncryptionPolicy, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM user_settings WHERE key = ?1",
+            [FIELD_POLICY_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten();
+    match value.filter(|v| !v.trim().is_empty()) {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("字段加密策略无效: {}", e)),
+        None => Ok(FieldEncryptionPolicy::default()),
+    }
+}
+
+/// 切换字段加密策略
+///
+/// 在同一事务中加密新纳入策略的字段、解密移出策略的字段并保存策略，返回改写的值数量。
+pub fn apply_policy(
+    db: &DatabaseService,
+    encryption: &EncryptionService,
+    policy: &FieldEncryptionPolicy,
+) -> Result<usize, String> {
+    let mut conn = db.get_connection().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let current = load_policy_conn(&tx)?;
+
+    let mut count = 0;
+    for field in EncryptableField::ALL {
+        let (encrypt, decrypt) = (policy.encrypts(field), current.encrypts(field));
+        if encrypt == decrypt {
+            continue;
+        }
+        for &(table, id_column, column) in field.columns() {
+            let rows: Vec<(i64, String)> = {
+                let mut stmt = tx
+                    .prepare(&format!(
+                        "SELECT {id_column}, {column} FROM {table} WHERE {column} IS NOT NULL AND {column} != ''"
+                    ))
+                    .map_err(|e| e.to_string())?;
+                let iter = stmt
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .map_err(|e| e.to_string())?;
+                iter.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+            };
+            let update_sql = format!("UPDATE {table} SET {column} = ?1 WHERE {id_column} = ?2");
+            for (id, value) in rows {
+                let rewritten = if encrypt {
+                    encryption.encrypt(&value)?
+                } else {
+                    let plain = encryption
+                        .decrypt(&value)
+                        .map_err(|e| format!("解密 {}.{} (id={}) 失败: {}", table, column, id, e))?;
+                    plain.expose().to_string()
+                };
+                tx.execute(&update_sql, (rewritten, id)).map_err(|e| e.to_string())?;
+                count += 1;
+            }
+        }
+    }
+
+    let json = serde_json::to_string(policy).map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO user_settings (key, value, type, category, description, created_at, updated_at)
+         VALUES (?1, ?2, 'json', 'security', '字段加密策略', datetime('now'), datetime('now'))
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = datetime('now')",
+        (FIELD_POLICY_SETTING, json),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// 按策略加解密密码条目与笔记
+#[derive(Clone)]
+pub struct FieldCipher {
+    pub policy: FieldEncryptionPolicy,
+    encryption: EncryptionService,
+}
+
+impl FieldCipher {
+    pub fn new(policy: FieldEncryptionPolicy, encryption: EncryptionService) -> Self {
+        Self { policy, encryption }
+    }
+
+    /// 加密单个可选字段；策略未要求或为空时原样返回
+    pub fn encrypt_value(&self, field: EncryptableField, value: Option<&str>) -> Result<Option<String>, String> {
+        match value {
+            Some(text) if !text.is_empty() && self.policy.encrypts(field) => {
+                self.encryption.encrypt(text).map(Some)
+            }
+            other => Ok(other.map(str::to_string)),
+        }
+    }
+
+    /// 解密单个可选字段；解密失败时保留原值
+    pub fn decrypt_value(&self, field: EncryptableField, value: Option<String>) -> Option<String> {
+        match value {
+            Some(text) if !text.is_empty() && self.policy.encrypts(field) => match self.encryption.decrypt(&text) {
+                Ok(plain) => Some(plain.expose().to_string()),
+                Err(e) => {
+                    log::warn!("解密字段 {:?} 失败，保留原值: {}", field, e);
+                    Some(text)
+                }
+            },
+            other => other,
+        }
+    }
+
+    /// 加密密码条目：密码字段始终加密，其余字段按策略
+    pub fn encrypt_password(&self, p: &mut Password) -> Result<(), String> {
+        if let Some(plain) = &p.password {
+            if !plain.is_empty() {
+                p.password = Some(self.encryption.encrypt(plain.expose())?.into());
+            }
+        }
+        p.username = self.encrypt_value(EncryptableField::PasswordUsername, p.username.as_deref())?;
+        p.url = self.encrypt_value(EncryptableField::PasswordUrl, p.url.as_deref())?;
+        p.notes = self.encrypt_value(EncryptableField::PasswordNotes, p.notes.as_deref())?;
+        p.tags = self.encrypt_value(EncryptableField::PasswordTags, p.tags.as_deref())?;
+        Ok(())
+    }
+
+    /// 解密密码条目
+    pub fn decrypt_password(&self, p: &mut Password) {
+        if let Some(cipher) = &p.password {
+            if !cipher.is_empty() {
+                if let Ok(plain) = self.encryption.decrypt(cipher.expose()) {
+                    p.password = Some(plain);
+                }
+            }
+        }
+        p.username = self.decrypt_value(EncryptableField::PasswordUsername, p.username.take());
+        p.url = self.decrypt_value(EncryptableField::PasswordUrl, p.url.take());
+        p.notes = self.decrypt_value(EncryptableField::PasswordNotes, p.notes.take());
+        p.tags = self.decrypt_value(EncryptableField::PasswordTags, p.tags.take());
+    }
+
+    /// 加密笔记：内容始终加密，标题按策略
+    pub fn encrypt_note(&self, note: &mut SecureRecord) -> Result<(), String> {
+        if let Some(plain) = &note.content {
+            if !plain.is_empty() {
+                note.content = Some(self.encryption.encrypt(plain.expose())?.into());
+            }
+        }
+        if let Some(title) = self.encrypt_value(EncryptableField::NoteTitle, Some(&note.title))? {
+            note.title = title;
+        }
+        Ok(())
+    }
+
+    /// 解密笔记；内容解密失败时保留原密文，让前端可以看到原始数据
+    pub fn decrypt_note(&self, note: &mut SecureRecord) {
+        if let Some(cipher) = &note.content {
+            if !cipher.is_empty() {
+                match self.encryption.decrypt(cipher.expose()) {
+                    Ok(plain) => note.content = Some(plain),
+                    Err(e) => log::error!("[decrypt_note] 解密失败，note.id={:?}: {}", note.id, e),
+                }
+            }
+        }
+        if let Some(title) = self.decrypt_value(EncryptableField::NoteTitle, Some(std::mem::take(&mut note.title))) {
+            note.title = title;
+        }
+    }
+
+    /// 加密结构化条目：机密字段始终加密，备注按策略
+    pub fn encrypt_item(&self, item: &mut VaultItem) -> Result<(), String> {
+        for (name, value) in item.fields.iter_mut() {
+            if crate::services::items::is_secret_field(item.item_type, name) && !value.is_empty() {
+                *value = self.encryption.encrypt(value.expose())?.into();
+            }
+        }
+        item.notes = self.encrypt_value(EncryptableField::PasswordNotes, item.notes.as_deref())?;
+        Ok(())
+    }
+
+    /// 解密结构化条目；解密失败的字段保留原值
+    pub fn decrypt_item(&self, item: &mut VaultItem) {
+        for (name, value) in item.fields.iter_mut() {
+            if crate::services::items::is_secret_field(item.item_type, name) && !value.is_empty() {
+                match self.encryption.decrypt(value.expose()) {
+                    Ok(plain) => *value = plain,
+                    Err(e) => log::warn!("解密条目字段 {} 失败 (id={:?}): {}", name, item.id, e),
+                }
+            }
+        }
+        item.notes = self.decrypt_value(EncryptableField::PasswordNotes, item.notes.take());
+    }
+}
+
+/// 与 SQLite `LIKE '%keyword%'` 一致的匹配：ASCII 字母不区分大小写
+pub(crate) fn like_contains(haystack: &str, keyword: &str) -> bool {
+    haystack
+        .to_ascii_lowercase()
+        .contains(&keyword.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::vault;
+    use tempfile::tempdir;
+
+    fn open_db(dir: &tempfile::TempDir) -> DatabaseService {
+        let db = DatabaseService::new(dir.path().join("fields.db").to_str().unwrap());
+        db.initialize().unwrap();
+        db
+    }
+
+    fn stored_username(db: &DatabaseService) -> String {
+        db.get_connection()
+            .unwrap()
+            .query_row("SELECT username FROM passwords", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_apply_policy_migrates_columns_both_ways() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let key = vault::open_on_startup(&db).unwrap().unwrap();
+        db.get_connection()
+            .unwrap()
+            .execute("INSERT INTO passwords (title, username) VALUES ('mail', 'alice')", [])
+            .unwrap();
+
+        let policy = FieldEncryptionPolicy {
+            password_username: true,
+            ..Default::default()
+        };
+        assert_eq!(apply_policy(&db, &key, &policy).unwrap(), 1);
+        assert_eq!(load_policy(&db).unwrap(), policy);
+        let stored = stored_username(&db);
+        assert_ne!(stored, "alice");
+        assert_eq!(key.decrypt(&stored).unwrap().expose(), "alice");
+
+        // 再次应用同一策略不会重复加密
+        assert_eq!(apply_policy(&db, &key, &policy).unwrap(), 0);
+
+        apply_policy(&db, &key, &FieldEncryptionPolicy::default()).unwrap();
+        assert_eq!(stored_username(&db), "alice");
+    }
+
+    #[test]
+    fn test_policy_covers_additional_urls() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let key = vault::open_on_startup(&db).unwrap().unwrap();
+        let conn = db.get_connection().unwrap();
+        conn.execute("INSERT INTO passwords (title) VALUES ('cloud')", []).unwrap();
+        conn.execute(
+            "INSERT INTO password_urls (password_id, url) VALUES (1, 'https://sso.example.net')",
+            [],
+        )
+        .unwrap();
+
+        let policy = FieldEncryptionPolicy {
+            password_url: true,
+            ..Default::default()
+        };
+        assert_eq!(apply_policy(&db, &key, &policy).unwrap(), 1);
+        let stored = db.get_password_urls(1).unwrap().remove(0).url;
+        assert_ne!(stored, "https://sso.example.net");
+
+        // 按密文列 LIKE 匹配不到，解密后才能匹配
+        let plain_policy = FieldCipher::new(FieldEncryptionPolicy::default(), key.clone());
+        assert!(db.search_related_password_ids("sso.example", &plain_policy).unwrap().is_empty());
+        let cipher = FieldCipher::new(policy, key);
+        assert_eq!(db.search_related_password_ids("sso.example", &cipher).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_and_sort_work_on_encrypted_fields() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let key = vault::open_on_startup(&db).unwrap().unwrap();
+        let policy = FieldEncryptionPolicy {
+            password_username: true,
+            note_title: true,
+            ..Default::default()
+        };
+        apply_policy(&db, &key, &policy).unwrap();
+        let cipher = FieldCipher::new(policy, key);
+
+        let mut password: Password =
+            serde_json::from_value(serde_json::json!({ "title": "Mail", "username": "Alice@example.com" }))
+                .unwrap();
+        cipher.encrypt_password(&mut password).unwrap();
+        db.add_password(&password).unwrap();
+        for title in ["b", "c", "a"] {
+            let mut note: SecureRecord =
+                serde_json::from_value(serde_json::json!({ "title": title, "content": format!("{title} body") }))
+                    .unwrap();
+            cipher.encrypt_note(&mut note).unwrap();
+            db.add_note(&note).unwrap();
+        }
+
+        let mut found = db.get_passwords(None).unwrap().remove(0);
+        assert!(!like_contains(found.username.as_deref().unwrap(), "alice"));
+        cipher.decrypt_password(&mut found);
+        assert!(like_contains(found.username.as_deref().unwrap(), "alice"));
+
+        let titles: Vec<String> = db
+            .get_notes(None, Some(&cipher))
+            .unwrap()
+            .into_iter()
+            .map(|note| note.title)
+            .collect();
+        assert_eq!(titles, ["a", "b", "c"]);
+        let found: Vec<SecureRecord> = db
+            .get_notes(None, Some(&cipher))
+            .unwrap()
+            .into_iter()
+            .filter(|note| note.content.as_ref().is_some_and(|c| like_contains(c.expose(), "C BODY")))
+            .collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].title, "c");
+    }
+
+    #[test]
+    fn test_cipher_roundtrip_respects_policy() {
+        let key = vault::generate_data_key();
+        let cipher = FieldCipher::new(
+            FieldEncryptionPolicy {
+                password_url: true,
+                note_title: true,
+                ..Default::default()
+            },
+            key,
+        );
+        let mut password: Password =
+            serde_json::from_value(serde_json::json!({
+                "title": "Mail", "username": "bob", "url": "https://mail", "password": "pw"
+            }))
+            .unwrap();
+        cipher.encrypt_password(&mut password).unwrap();
+        assert_eq!(password.username.as_deref(), Some("bob"));
+        assert_ne!(password.url.as_deref(), Some("https://mail"));
+        cipher.decrypt_password(&mut password);
+        assert_eq!(password.url.as_deref(), Some("https://mail"));
+        assert_eq!(password.password.as_ref().map(crate::models::SecretString::expose), Some("pw"));
+
+        let mut note: SecureRecord =
+            serde_json::from_value(serde_json::json!({ "title": "Diary", "content": "body" })).unwrap();
+        cipher.encrypt_note(&mut note).unwrap();
+        assert_ne!(note.title, "Diary");
+        cipher.decrypt_note(&mut note);
+        assert_eq!(note.title, "Diary");
+    }
+}
@@ -0,0 +1,332 @@
+//! 模糊匹配与拼音匹配
+//!
+//! 为标题类短文本打相关度分（0~1）：完全一致 > 前缀 > 包含 > 拼音全拼/首字母 > 拼写错误 > 子序列。
+//! 汉字读音来自 `deunicode`，每字只有一个读音，常见多音字在 `POLYPHONES` 中补充其他读音。
+
+use std::collections::HashSet;
+
+/// 完全一致
+const EXACT_SCORE: f64 = 1.0;
+/// 以关键字开头
+const PREFIX_SCORE: f64 = 0.9;
+/// 包含关键字
+const CONTAINS_SCORE: f64 = 0.8;
+/// 拼音全拼匹配（从第一个字开始时再加 `PINYIN_START_BONUS`）
+const PINYIN_FULL_SCORE: f64 = 0.72;
+/// 拼音首字母或全拼与首字母混合匹配
+const PINYIN_INITIALS_SCORE: f64 = 0.66;
+const PINYIN_START_BONUS: f64 = 0.06;
+/// 编辑距离为 1 时的得分，每多一处错误减 `TYPO_PENALTY`
+const TYPO_SCORE: f64 = 0.5;
+const TYPO_PENALTY: f64 = 0.1;
+/// 子序列匹配的基础分，按紧凑程度最多再加 `SUBSEQUENCE_BONUS`
+const SUBSEQUENCE_SCORE: f64 = 0.25;
+const SUBSEQUENCE_BONUS: f64 = 0.2;
+/// 只在正文、备注等长文本中包含关键字时的得分
+pub const CONTENT_SCORE: f64 = 0.3;
+/// 拼写错误与子序列匹配要求的最少关键字长度，过短时误匹配太多
+const MIN_TYPO_LEN: usize = 4;
+const MIN_SUBSEQUENCE_LEN: usize = 3;
+
+/// 常见多音字的其他读音
+const POLYPHONES: &[(char, &[&str])] = &[
+    ('行', &["hang"]),
+    ('长', &["zhang"]),
+    ('重', &["chong"]),
+    ('乐', &["yue"]),
+    ('还', &["hai"]),
+    ('朝', &["chao"]),
+    ('都', &["dou"]),
+    ('调', &["tiao"]),
+    ('单', &["shan"]),
+    ('会', &["kuai"]),
+    ('传', &["zhuan"]),
+    ('藏', &["zang"]),
+    ('曾', &["zeng"]),
+    ('区', &["ou"]),
+    ('解', &["xie"]),
+    ('沈', &["shen"]),
+    ('参', &["shen", "cen"]),
+    ('差', &["cha", "ci"]),
+    ('率', &["lv", "shuai"]),
+    ('省', &["xing"]),
+    ('着', &["zhe", "zhuo"]),
+    ('降', &["xiang"]),
+    ('角', &["jue"]),
+    ('便', &["pian"]),
+    ('觉', &["jiao"]),
+    ('薄', &["bao"]),
+];
+
+/// 预处理后的关键字，可对多个候选文本重复打分
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    /// 小写关键字
+    lower: String,
+    /// 去除空白后的小写字符
+    chars: Vec<char>,
+}
+
+impl Matcher {
+    pub fn new(query: &str) -> Self {
+        let lower = query.trim().to_lowercase();
+        let chars = lower.chars().filter(|c| !c.is_whitespace()).collect();
+        Self { lower, chars }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// 计算候选文本的相关度；不匹配时返回 `None`，空关键字匹配任何文本且得分为 0
+    pub fn score(&self, candidate: &str) -> Option<f64> {
+        if self.is_empty() {
+            return Some(0.0);
+        }
+        let candidate = candidate.to_lowercase();
+        if candidate.trim() == self.lower {
+            return Some(EXACT_SCORE);
+        }
+        if candidate.starts_with(&self.lower) {
+            return Some(PREFIX_SCORE);
+        }
+        if candidate.contains(&self.lower) {
+            return Some(CONTAINS_SCORE);
+        }
+        if let Some(score) = self.pinyin_score(&candidate) {
+            return Some(score);
+        }
+        [self.typo_score(&candidate), self.subsequence_score(&candidate)]
+            .into_iter()
+            .flatten()
+            .reduce(f64::max)
+    }
+
+    /// 按拼音匹配含汉字的候选文本；关键字须全为字母或数字
+    fn pinyin_score(&self, candidate: &str) -> Option<f64> {
+        if !self.chars.iter().all(char::is_ascii_alphanumeric) || candidate.is_ascii() {
+            return None;
+        }
+        let syllables = syllables(candidate);
+        let query: Vec<u8> = self.chars.iter().map(|&c| c as u8).collect();
+        // 单个字母只在第一个字匹配，避免几乎所有条目都命中
+        let starts = if query.len() < 2 { 0..1 } else { 0..syllables.len() };
+        for strict in [true, false] {
+            if let Some(start) = starts
+                .clone()
+                .find(|&start| walk(&query, 0, &syllables, start, strict, &mut HashSet::new()))
+            {
+                let base = if strict { PINYIN_FULL_SCORE } else { PINYIN_INITIALS_SCORE };
+                return Some(if start == 0 { base + PINYIN_START_BONUS } else { base });
+            }
+        }
+        None
+    }
+
+    /// 与候选文本中各单词（或其等长前缀）的编辑距离足够小时视为拼写错误
+    fn typo_score(&self, candidate: &str) -> Option<f64> {
+        if self.chars.len() < MIN_TYPO_LEN {
+            return None;
+        }
+        let max_distance = (self.chars.len() / 4).max(1);
+        let words: Vec<Vec<char>> = candidate
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.chars().collect())
+            .chain(std::iter::once(candidate.chars().filter(|c| !c.is_whitespace()).collect()))
+            .collect();
+        let distance = words
+            .iter()
+            .flat_map(|word| {
+                let prefix = &word[..word.len().min(self.chars.len())];
+                [edit_distance(&self.chars, word), edit_distance(&self.chars, prefix)]
+            })
+            .min()?;
+        (distance <= max_distance).then(|| TYPO_SCORE - TYPO_PENALTY * distance.saturating_sub(1) as f64)
+    }
+
+    /// 关键字各字符按顺序出现在候选文本中，且跨度不超过关键字长度的两倍
+    fn subsequence_score(&self, candidate: &str) -> Option<f64> {
+        if self.chars.len() < MIN_SUBSEQUENCE_LEN {
+            return None;
+        }
+        let candidate: Vec<char> = candidate.chars().collect();
+        let span = (0..candidate.len())
+            .filter_map(|start| {
+                let mut next = 0;
+                candidate[start..]
+                    .iter()
+                    .position(|c| {
+                        if *c == self.chars[next] {
+                            next += 1;
+                        }
+                        next == self.chars.len()
+                    })
+                    .map(|end| end + 1)
+            })
+            .min()?;
+        let compactness = self.chars.len() as f64 / span as f64;
+        (compactness >= 0.5).then_some(SUBSEQUENCE_SCORE + SUBSEQUENCE_BONUS * compactness)
+    }
+}
+
+/// 计算 `query` 对 `candidate` 的相关度，见 [`Matcher::score`]
+pub fn score(query: &str, candidate: &str) -> Option<f64> {
+    Matcher::new(query).score(candidate)
+}
+
+/// 按得分从高到低排序，得分相同时按标题排序
+pub fn rank<T>(items: &mut [(T, f64)], title: impl Fn(&T) -> &str) {
+    items.sort_by(|(a, a_score), (b, b_score)| b_score.total_cmp(a_score).then_with(|| title(a).cmp(title(b))));
+}
+
+/// 单个汉字的全部拼音（小写）；非汉字返回空
+pub fn pinyin(c: char) -> Vec<String> {
+    if c.is_ascii() {
+        return Vec::new();
+    }
+    let mut readings: Vec<String> = deunicode::deunicode_char(c)
+        .map(|s| s.trim().to_ascii_lowercase())
+        .filter(|s| !s.is_empty() && s.bytes().all(|b| b.is_ascii_lowercase()))
+        .into_iter()
+        .collect();
+    if let Some((_, extra)) = POLYPHONES.iter().find(|(p, _)| *p == c) {
+        readings.extend(extra.iter().map(|s| s.to_string()));
+    }
+    readings
+}
+
+/// 把候选文本切成“音节”：汉字取各读音，字母和数字取自身，其余字符忽略
+fn syllables(candidate: &str) -> Vec<Vec<String>> {
+    candidate
+        .chars()
+        .filter_map(|c| {
+            if c.is_ascii_alphanumeric() {
+                return Some(vec![c.to_string()]);
+            }
+            let readings = pinyin(c);
+            (!readings.is_empty()).then_some(readings)
+        })
+        .collect()
+}
+
+/// 从第 `index` 个音节起匹配 `query[pos..]`
+///
+/// 严格模式下每个音节须完整输入（最后一个可只输入前缀）；否则每个音节可只输入任意前缀，
+/// 从而同时支持首字母（`zsyh`）与全拼、首字母混合（`zhaoshangyh`）。
+fn walk(
+    query: &[u8],
+    pos: usize,
+    syllables: &[Vec<String>],
+    index: usize,
+    strict: bool,
+    failed: &mut HashSet<(usize, usize)>,
+) -> bool {
+    if pos == query.len() {
+        return true;
+    }
+    if index == syllables.len() || failed.contains(&(pos, index)) {
+        return false;
+    }
+    let rest = &query[pos..];
+    for reading in &syllables[index] {
+        let reading = reading.as_bytes();
+        let common = reading.iter().zip(rest).take_while(|(a, b)| a == b).count();
+        if common > 0 && common == rest.len() {
+            return true;
+        }
+        let shortest = if strict { reading.len() } else { 1 };
+        for len in (shortest..=common).rev() {
+            if walk(query, pos + len, syllables, index + 1, strict, failed) {
+                return true;
+            }
+        }
+    }
+    failed.insert((pos, index));
+    false
+}
+
+/// 编辑距离（含相邻字符交换）
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_matches_rank_above_fuzzy() {
+        assert_eq!(score("github", "GitHub"), Some(EXACT_SCORE));
+        assert_eq!(score("git", "GitHub"), Some(PREFIX_SCORE));
+        assert_eq!(score("hub", "GitHub"), Some(CONTAINS_SCORE));
+        assert_eq!(score("", "GitHub"), Some(0.0));
+        assert_eq!(score("gitlab", "GitHub"), None);
+    }
+
+    #[test]
+    fn test_typos_and_subsequences() {
+        // 相邻字符交换与漏字
+        assert_eq!(score("gihtub", "GitHub"), Some(TYPO_SCORE));
+        assert_eq!(score("gthub", "GitHub Enterprise"), Some(TYPO_SCORE));
+        assert_eq!(edit_distance(&['a', 'b', 'c'], &['x', 'y', 'z']), 3);
+        assert_eq!(score("abc", "xyz"), None);
+
+        let gmail = score("gml", "Gmail").unwrap();
+        assert!(gmail > SUBSEQUENCE_SCORE && gmail < TYPO_SCORE);
+        // 跨度过大
+        assert_eq!(score("gml", "Google Cloud Console Mail"), None);
+    }
+
+    #[test]
+    fn test_pinyin_full_and_initials() {
+        let full = score("zhaoshang", "招商银行").unwrap();
+        let initials = score("zsyh", "招商银行").unwrap();
+        let mixed = score("zhaoshangyh", "招商银行").unwrap();
+        assert_eq!(full, PINYIN_FULL_SCORE + PINYIN_START_BONUS);
+        assert_eq!(initials, PINYIN_INITIALS_SCORE + PINYIN_START_BONUS);
+        assert_eq!(mixed, initials);
+        assert!(full > initials);
+
+        // 从中间的字开始、末尾音节只输入一半
+        assert_eq!(score("yinha", "招商银行"), Some(PINYIN_FULL_SCORE));
+        // 多音字与中英混排
+        assert!(score("chongqing", "重庆 VPN").is_some());
+        assert!(score("cqvpn", "重庆 VPN").is_some());
+        assert_eq!(pinyin('a'), Vec::<String>::new());
+
+        assert_eq!(score("zsx", "招商银行"), None);
+        // 单个字母只匹配第一个字
+        assert!(score("z", "招商银行").is_some());
+        assert_eq!(score("y", "招商银行"), None);
+    }
+
+    #[test]
+    fn test_rank_orders_by_score_then_title() {
+        let matcher = Matcher::new("zsyh");
+        let mut items: Vec<(&str, f64)> = ["招商银行信用卡", "招商银行", "工商银行"]
+            .into_iter()
+            .filter_map(|title| matcher.score(title).map(|score| (title, score)))
+            .collect();
+        rank(&mut items, |title| title);
+        let titles: Vec<&str> = items.iter().map(|(title, _)| *title).collect();
+        assert_eq!(titles, vec!["招商银行", "招商银行信用卡"]);
+    }
+}
@@ -0,0 +1,458 @@
+//! 密码生成器
+//!
+//! 支持随机字符密码与单词口令两种方式，每次生成都返回按生成方式计算的熵（位），
+//! 即攻击者已知生成规则时需要穷举的空间大小，而不是对结果字符串的估算。
+
+use crate::models::{
+    GeneratedPassword, GeneratorMode, PasswordGeneratorOptions, PasswordPolicy, SecretString, UserSetting,
+};
+use crate::services::database::DatabaseService;
+use rand::RngCore;
+use std::collections::{BTreeMap, HashSet};
+use zeroize::Zeroizing;
+
+/// 内置口令词表（BIP-39 英文词表，2048 个词，每个词 11 位熵）
+const WORDLIST: &str = include_str!("wordlist.txt");
+
+pub const DEFAULT_LENGTH: usize = 16;
+/// 默认口令单词数：7 × 11 = 77 位，与 6 个 EFF 长词表单词（约 77.5 位）相当
+pub const DEFAULT_WORD_COUNT: usize = 7;
+pub const DEFAULT_SEPARATOR: &str = "-";
+const WORD_COUNT_RANGE: std::ops::RangeInclusive<usize> = 3..=20;
+const MAX_SEPARATOR_CHARS: usize = 8;
+
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const NUMBERS: &str = "0123456789";
+const DEFAULT_SYMBOLS: &str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
+/// 易混淆字符
+const AMBIGUOUS_CHARS: &str = "0O1lI";
+/// 随机密码不满足限制时的最大重试次数
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// 命名规则在 user_settings 中的键
+pub const POLICIES_SETTING: &str = "generator.policies";
+/// 分组关联的规则名在 user_settings 中的键
+pub const GROUP_POLICIES_SETTING: &str = "generator.group_policies";
+const MAX_POLICY_NAME_CHARS: usize = 64;
+
+fn words() -> Vec<&'static str> {
+    WORDLIST.lines().filter(|w| !w.is_empty()).collect()
+}
+
+/// 按选项生成密码或口令
+pub fn generate(options: &PasswordGeneratorOptions) -> Result<GeneratedPassword, String> {
+    match options.mode.unwrap_or_default() {
+        GeneratorMode::Random => generate_random(options),
+        GeneratorMode::Passphrase => generate_passphrase(options),
+    }
+}
+
+/// 一类字符及其最少数量
+struct CharClass {
+    chars: Vec<char>,
+    min: usize,
+}
+
+/// [0, n) 内的均匀随机数：丢弃超出 n 的最大整数倍的取值，避免取模偏差
+fn uniform_index<R: RngCore>(rng: &mut R, n: usize) -> usize {
+    let n = n as u64;
+    let zone = (1u64 << 32) - (1u64 << 32) % n;
+    loop {
+        let value = u64::from(rng.next_u32());
+        if value < zone {
+            return (value % n) as usize;
+        }
+    }
+}
+
+/// 按选项确定启用的字符类型；各类型互不重叠
+fn char_classes(options: &PasswordGeneratorOptions) -> Result<Vec<CharClass>, String> {
+    let mut excluded: HashSet<char> = options.exclude_chars.as_deref().unwrap_or_default().chars().collect();
+    if options.exclude_ambiguous.unwrap_or(false) {
+        excluded.extend(AMBIGUOUS_CHARS.chars());
+    }
+    let symbols: String = match options.custom_symbols.as_deref() {
+        Some(custom) => custom
+            .chars()
+            .filter(|c| !c.is_ascii_alphanumeric() && !c.is_whitespace() && !c.is_control())
+            .collect(),
+        None => DEFAULT_SYMBOLS.to_string(),
+    };
+
+    let specs = [
+        ("大写字母", options.include_uppercase, options.min_uppercase, UPPERCASE),
+        ("小写字母", options.include_lowercase, options.min_lowercase, LOWERCASE),
+        ("数字", options.include_numbers, options.min_numbers, NUMBERS),
+        ("特殊字符", options.include_symbols, options.min_symbols, symbols.as_str()),
+    ];
+    let mut classes = Vec::new();
+    for (label, include, min, chars) in specs {
+        let include = include.unwrap_or(true);
+        let min = min.unwrap_or(usize::from(include));
+        if !include {
+            if min > 0 {
+                return Err(format!("未启用{}，不能要求至少 {} 个", label, min));
+            }
+            continue;
+        }
+        let mut chars: Vec<char> = chars.chars().filter(|c| !excluded.contains(c)).collect();
+        chars.sort_unstable();
+        chars.dedup();
+        if chars.is_empty() {
+            return Err(format!("{}在排除后没有可用字符", label));
+        }
+        classes.push(CharClass { chars, min });
+    }
+    if classes.is_empty() {
+        return Err("至少需要选择一种字符类型".to_string());
+    }
+    Ok(classes)
+}
+
+/// 三个连续递增或递减的字母或数字
+fn is_sequence(window: &[char]) -> bool {
+    if !window.iter().all(char::is_ascii_alphanumeric) {
+        return false;
+    }
+    let (a, b, c) = (window[0] as i64, window[1] as i64, window[2] as i64);
+    (b - a == 1 && c - b == 1) || (a - b == 1 && b - c == 1)
+}
+
+fn satisfies(candidate: &[char], classes: &[CharClass], options: &PasswordGeneratorOptions) -> bool {
+    classes
+        .iter()
+        .all(|class| candidate.iter().filter(|c| class.chars.contains(c)).count() >= class.min)
+        && !(options.no_repeats.unwrap_or(false) && candidate.windows(2).any(|w| w[0] == w[1]))
+        && !(options.no_sequences.unwrap_or(false) && candidate.windows(3).any(is_sequence))
+}
+
+/// 随机字符密码
+///
+/// 每个字符从全部可用字符中均匀选取，不满足最少数量、重复或连续字符限制的结果整体丢弃重来，
+/// 因此结果在所有满足限制的密码中均匀分布。返回的熵为未加限制时的值，是实际熵的上限。
+fn generate_random(options: &PasswordGeneratorOptions) -> Result<GeneratedPassword, String> {
+    let length = options.length.unwrap_or(DEFAULT_LENGTH).clamp(4, 128);
+    let classes = char_classes(options)?;
+    let required: usize = classes.iter().map(|class| class.min).sum();
+    if required > length {
+        return Err(format!("各类字符最少数量之和 ({}) 超过密码长度 ({})", required, length));
+    }
+
+    let pool: Vec<char> = classes.iter().flat_map(|class| class.chars.iter().copied()).collect();
+    let mut rng = rand::thread_rng();
+    for _ in 0..MAX_ATTEMPTS {
+        let candidate: Zeroizing<Vec<char>> =
+            Zeroizing::new((0..length).map(|_| pool[uniform_index(&mut rng, pool.len())]).collect());
+        if satisfies(&candidate, &classes, options) {
+            return Ok(GeneratedPassword {
+                password: SecretString::new(candidate.iter().collect()),
+                entropy_bits: length as f64 * (pool.len() as f64).log2(),
+            });
+        }
+    }
+    Err("无法在当前限制下生成密码，请放宽要求".to_string())
+}
+
+fn generate_passphrase(options: &PasswordGeneratorOptions) -> Result<GeneratedPassword, String> {
+    let word_count = options.word_count.unwrap_or(DEFAULT_WORD_COUNT);
+    if !WORD_COUNT_RANGE.contains(&word_count) {
+        return Err(format!(
+            "单词数必须在 {} 到 {} 之间",
+            WORD_COUNT_RANGE.start(),
+            WORD_COUNT_RANGE.end()
+        ));
+    }
+    let separator = options.separator.as_deref().unwrap_or(DEFAULT_SEPARATOR);
+    if separator.chars().count() > MAX_SEPARATOR_CHARS {
+        return Err(format!("分隔符不能超过 {} 个字符", MAX_SEPARATOR_CHARS));
+    }
+
+    let words = words();
+    let mut rng = rand::thread_rng();
+    let mut chosen: Vec<Zeroizing<String>> = (0..word_count)
+        .map(|_| {
+            let word = words[uniform_index(&mut rng, words.len())];
+            Zeroizing::new(if options.capitalize.unwrap_or(false) {
+                capitalize(word)
+            } else {
+                word.to_string()
+            })
+        })
+        .collect();
+    let mut entropy_bits = word_count as f64 * (words.len() as f64).log2();
+
+    if options.include_number.unwrap_or(false) {
+        let index = uniform_index(&mut rng, word_count);
+        let digit = uniform_index(&mut rng, 10) as u8;
+        chosen[index].push(char::from(b'0' + digit));
+        entropy_bits += ((10 * word_count) as f64).log2();
+    }
+
+    let mut passphrase = String::new();
+    for (i, word) in chosen.iter().enumerate() {
+        if i > 0 {
+            passphrase.push_str(separator);
+        }
+        passphrase.push_str(word);
+    }
+    Ok(GeneratedPassword {
+        password: SecretString::new(passphrase),
+        entropy_bits,
+    })
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn load_setting<T: serde::de::DeserializeOwned + Default>(db: &DatabaseService, key: &str) -> Result<T, String> {
+    match db.get_user_setting(key)?.map(|s| s.value) {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| format!("密码生成规则无效: {}", e))
+        }
+        _ => Ok(T::default()),
+    }
+}
+
+fn save_setting<T: serde::Serialize>(
+    db: &DatabaseService,
+    key: &str,
+    value: &T,
+    description: &str,
+) -> Result<(), String> {
+    db.set_user_setting(&UserSetting {
+        id: None,
+        key: key.to_string(),
+        value: serde_json::to_string(value).map_err(|e| e.to_string())?,
+        r#type: Some("json".to_string()),
+        category: Some("security".to_string()),
+        description: Some(description.to_string()),
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+/// 全部命名规则
+pub fn load_policies(db: &DatabaseService) -> Result<Vec<PasswordPolicy>, String> {
+    load_setting(db, POLICIES_SETTING)
+}
+
+/// 分组 ID 到规则名的关联
+pub fn load_group_policies(db: &DatabaseService) -> Result<BTreeMap<i64, String>, String> {
+    load_setting(db, GROUP_POLICIES_SETTING)
+}
+
+/// 新增或替换同名规则；保存前先试生成一次，确保规则可用
+pub fn save_policy(db: &DatabaseService, policy: PasswordPolicy) -> Result<(), String> {
+    let name = policy.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_POLICY_NAME_CHARS {
+        return Err(format!("规则名称不能为空且不能超过 {} 个字符", MAX_POLICY_NAME_CHARS));
+    }
+    generate(&policy.options)?;
+    let policy = PasswordPolicy { name: name.to_string(), ..policy };
+    let mut policies = load_policies(db)?;
+    match policies.iter_mut().find(|p| p.name == policy.name) {
+        Some(existing) => *existing = policy,
+        None => policies.push(policy),
+    }
+    save_setting(db, POLICIES_SETTING, &policies, "密码生成规则")
+}
+
+/// 删除规则，并解除其与分组的关联
+pub fn delete_policy(db: &DatabaseService, name: &str) -> Result<(), String> {
+    let mut policies = load_policies(db)?;
+    let before = policies.len();
+    policies.retain(|p| p.name != name);
+    if policies.len() == before {
+        return Err("Policy not found".to_string());
+    }
+    save_setting(db, POLICIES_SETTING, &policies, "密码生成规则")?;
+    let mut group_policies = load_group_policies(db)?;
+    group_policies.retain(|_, policy_name| policy_name != name);
+    save_setting(db, GROUP_POLICIES_SETTING, &group_policies, "分组密码生成规则")
+}
+
+/// 为分组关联规则；`name` 为空时解除关联
+pub fn set_group_policy(db: &DatabaseService, group_id: i64, name: Option<&str>) -> Result<(), String> {
+    db.get_group(group_id)?.ok_or("Group not found")?;
+    let mut group_policies = load_group_policies(db)?;
+    match name {
+        Some(name) => {
+            if !load_policies(db)?.iter().any(|p| p.name == name) {
+                return Err("Policy not found".to_string());
+            }
+            group_policies.insert(group_id, name.to_string());
+        }
+        None => {
+            group_policies.remove(&group_id);
+        }
+    }
+    save_setting(db, GROUP_POLICIES_SETTING, &group_policies, "分组密码生成规则")
+}
+
+/// 查找适用的规则：优先使用指定名称，否则使用分组或最近的上级分组关联的规则
+pub fn resolve_policy(
+    db: &DatabaseService,
+    name: Option<&str>,
+    group_id: Option<i64>,
+) -> Result<Option<PasswordPolicy>, String> {
+    let policies = load_policies(db)?;
+    let find = |name: &str| policies.iter().find(|p| p.name == name).cloned();
+    if let Some(name) = name {
+        return find(name).map(Some).ok_or_else(|| "Policy not found".to_string());
+    }
+
+    let group_policies = load_group_policies(db)?;
+    let mut visited = HashSet::new();
+    let mut current = group_id;
+    while let Some(id) = current.filter(|id| visited.insert(*id)) {
+        if let Some(policy) = group_policies.get(&id).and_then(|name| find(name)) {
+            return Ok(Some(policy));
+        }
+        current = db.get_group(id)?.and_then(|g| g.parent_id);
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wordlist_is_unique() {
+        let words = words();
+        assert_eq!(words.len(), 2048);
+        assert!(DEFAULT_WORD_COUNT as f64 * (words.len() as f64).log2() >= 77.0);
+        let unique: std::collections::HashSet<_> = words.iter().collect();
+        assert_eq!(unique.len(), words.len());
+        assert!(words.iter().all(|w| w.chars().all(|c| c.is_ascii_lowercase())));
+    }
+
+    #[test]
+    fn test_passphrase_options_and_entropy() {
+        let options = PasswordGeneratorOptions {
+            mode: Some(GeneratorMode::Passphrase),
+            word_count: Some(5),
+            separator: Some(" ".to_string()),
+            capitalize: Some(true),
+            include_number: Some(true),
+            ..Default::default()
+        };
+        let generated = generate(&options).unwrap();
+        let parts: Vec<&str> = generated.password.expose().split(' ').collect();
+        assert_eq!(parts.len(), 5);
+        assert!(parts.iter().all(|w| w.starts_with(|c: char| c.is_ascii_uppercase())));
+        assert_eq!(parts.iter().filter(|w| w.ends_with(|c: char| c.is_ascii_digit())).count(), 1);
+        assert!((generated.entropy_bits - (55.0 + 50f64.log2())).abs() < 1e-9);
+
+        let plain = generate(&PasswordGeneratorOptions {
+            mode: Some(GeneratorMode::Passphrase),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(plain.password.expose().split('-').count(), DEFAULT_WORD_COUNT);
+        assert_eq!(plain.entropy_bits, 77.0);
+
+        let too_few = PasswordGeneratorOptions { word_count: Some(2), ..options.clone() };
+        assert!(generate(&too_few).is_err());
+    }
+
+    #[test]
+    fn test_random_entropy() {
+        let options = PasswordGeneratorOptions {
+            length: Some(10),
+            include_uppercase: Some(false),
+            include_lowercase: Some(false),
+            include_symbols: Some(false),
+            ..Default::default()
+        };
+        let generated = generate(&options).unwrap();
+        assert_eq!(generated.password.len(), 10);
+        assert!((generated.entropy_bits - 10.0 * 10f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_random_honors_class_minimums_and_exclusions() {
+        let options = PasswordGeneratorOptions {
+            length: Some(12),
+            min_numbers: Some(3),
+            min_symbols: Some(2),
+            exclude_ambiguous: Some(true),
+            exclude_chars: Some("xyz".to_string()),
+            custom_symbols: Some("#$a ".to_string()),
+            no_repeats: Some(true),
+            no_sequences: Some(true),
+            ..Default::default()
+        };
+        for _ in 0..200 {
+            let generated = generate(&options).unwrap();
+            let chars: Vec<char> = generated.password.expose().chars().collect();
+            assert_eq!(chars.len(), 12);
+            assert!(chars.iter().filter(|c| c.is_ascii_digit()).count() >= 3);
+            assert!(chars.iter().filter(|c| **c == '#' || **c == '$').count() >= 2);
+            assert!(chars.iter().any(char::is_ascii_uppercase));
+            assert!(chars.iter().any(char::is_ascii_lowercase));
+            assert!(!chars.iter().any(|c| "0O1lIxyz".contains(*c)));
+            assert!(chars.iter().all(|c| c.is_ascii_alphanumeric() || *c == '#' || *c == '$'));
+            assert!(chars.windows(2).all(|w| w[0] != w[1]));
+            assert!(!chars.windows(3).any(is_sequence));
+        }
+
+        let impossible = PasswordGeneratorOptions { length: Some(4), min_numbers: Some(5), ..Default::default() };
+        assert!(generate(&impossible).is_err());
+        let disabled = PasswordGeneratorOptions {
+            include_numbers: Some(false),
+            min_numbers: Some(1),
+            ..Default::default()
+        };
+        assert!(generate(&disabled).is_err());
+        assert!(is_sequence(&['c', 'b', 'a']) && !is_sequence(&['a', 'c', 'e']));
+    }
+
+    #[test]
+    fn test_group_policy_resolution() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseService::new(dir.path().join("test.db").to_str().unwrap());
+        db.initialize().unwrap();
+        let group = |name: &str, parent_id: Option<i64>| crate::models::Group {
+            id: None,
+            name: name.to_string(),
+            parent_id,
+            icon: None,
+            color: None,
+            sort_order: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let parent = db.add_group(&group("Bank", None)).unwrap();
+        let child = db.add_group(&group("Cards", Some(parent))).unwrap();
+
+        let pin = PasswordPolicy {
+            name: " PIN ".to_string(),
+            options: PasswordGeneratorOptions {
+                length: Some(6),
+                include_uppercase: Some(false),
+                include_lowercase: Some(false),
+                include_symbols: Some(false),
+                ..Default::default()
+            },
+        };
+        save_policy(&db, pin).unwrap();
+        assert!(set_group_policy(&db, parent, Some("missing")).is_err());
+        set_group_policy(&db, parent, Some("PIN")).unwrap();
+
+        let resolved = resolve_policy(&db, None, Some(child)).unwrap().unwrap();
+        assert_eq!(resolved.name, "PIN");
+        assert!(generate(&resolved.options).unwrap().password.expose().chars().all(|c| c.is_ascii_digit()));
+        assert!(resolve_policy(&db, Some("missing"), None).is_err());
+
+        delete_policy(&db, "PIN").unwrap();
+        assert!(load_group_policies(&db).unwrap().is_empty());
+        assert_eq!(resolve_policy(&db, None, Some(child)).unwrap(), None);
+    }
+}
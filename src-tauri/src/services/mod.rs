@@ -2,3 +2,20 @@
 
 pub mod database;
 pub mod encryption;
+pub mod field_encryption;
+pub mod vault;
+pub mod password_hashing;
+pub mod totp;
+pub mod custom_fields;
+pub mod url_match;
+pub mod attachments;
+pub mod items;
+pub mod trash;
+pub mod revisions;
+pub mod password_history;
+pub mod audit;
+pub mod breach;
+pub mod generator;
+pub mod search_index;
+pub mod query;
+pub mod fuzzy;
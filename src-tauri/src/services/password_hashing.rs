@@ -0,0 +1,212 @@
+//! 主密码哈希
+//!
+//! 存储格式（`master_password.password_hash`）：
+//! - 当前版本：Argon2id PHC 字符串 `$argon2id$v=19$m=..,t=..,p=..$<salt>$<hash>`，
+//!   随机盐值与成本参数随哈希一同保存
+//! - 旧版本：64 位十六进制的无盐 SHA-256，校验通过后自动升级
+
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 默认成本：64 MiB 内存、3 次迭代、单线程
+pub const DEFAULT_MEMORY_KIB: u32 = 64 * 1024;
+pub const DEFAULT_ITERATIONS: u32 = 3;
+pub const DEFAULT_PARALLELISM: u32 = 1;
+
+/// 允许设置的最低成本（OWASP 推荐下限）
+pub const MIN_MEMORY_KIB: u32 = 19 * 1024;
+pub const MIN_ITERATIONS: u32 = 2;
+
+/// Argon2id 成本参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for HashParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: DEFAULT_MEMORY_KIB,
+            iterations: DEFAULT_ITERATIONS,
+            parallelism: DEFAULT_PARALLELISM,
+        }
+    }
+}
+
+impl HashParams {
+    /// 是否每一项都不低于 `other`
+    pub fn at_least(&self, other: &HashParams) -> bool {
+        self.memory_kib >= other.memory_kib
+            && self.iterations >= other.iterations
+            && self.parallelism >= other.parallelism
+    }
+
+    /// 逐项取较大值
+    pub fn max(&self, other: &HashParams) -> HashParams {
+        HashParams {
+            memory_kib: self.memory_kib.max(other.memory_kib),
+            iterations: self.iterations.max(other.iterations),
+            parallelism: self.parallelism.max(other.parallelism),
+        }
+    }
+
+    /// 校验参数是否在允许范围内
+    pub fn validate(&self) -> Result<(), String> {
+        if self.memory_kib < MIN_MEMORY_KIB {
+            return Err(format!("内存成本不能低于 {} KiB", MIN_MEMORY_KIB));
+        }
+        if self.iterations < MIN_ITERATIONS {
+            return Err(format!("迭代次数不能低于 {}", MIN_ITERATIONS));
+        }
+        if self.parallelism == 0 {
+            return Err("并行度至少为 1".to_string());
+        }
+        self.argon2().map(|_| ())
+    }
+
+    pub(crate) fn argon2(&self) -> Result<Argon2<'static>, String> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| format!("Argon2 参数无效: {}", e))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+/// 校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Invalid,
+    /// 校验通过；`needs_upgrade` 表示存储格式过旧或成本低于目标
+    Valid { needs_upgrade: bool },
+}
+
+/// 使用随机盐值计算 Argon2id 哈希
+pub fn hash(password: &str, params: &HashParams) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    params
+        .argon2()?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| format!("计算密码哈希失败: {}", e))
+}
+
+/// 校验密码，并判断是否需要升级到 `target` 成本
+pub fn verify(stored: &str, password: &str, target: &HashParams) -> Verification {
+    if is_legacy(stored) {
+        return if legacy_sha256(password) == stored.to_ascii_lowercase() {
+            Verification::Valid { needs_upgrade: true }
+        } else {
+            Verification::Invalid
+        };
+    }
+
+    let Ok(parsed) = PasswordHash::new(stored) else {
+        return Verification::Invalid;
+    };
+    let Some(params) = params_of(stored) else {
+        return Verification::Invalid;
+    };
+    let verified = params
+        .argon2()
+        .map(|argon2| argon2.verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false);
+    if verified {
+        Verification::Valid {
+            needs_upgrade: !params.at_least(target),
+        }
+    } else {
+        Verification::Invalid
+    }
+}
+
+/// 读取已存储哈希的成本参数；旧版 SHA-256 返回 None
+pub fn params_of(stored: &str) -> Option<HashParams> {
+    let parsed = PasswordHash::new(stored).ok()?;
+    if parsed.algorithm.as_str() != "argon2id" {
+        return None;
+    }
+    let params = Params::try_from(&parsed).ok()?;
+    Some(HashParams {
+        memory_kib: params.m_cost(),
+        iterations: params.t_cost(),
+        parallelism: params.p_cost(),
+    })
+}
+
+/// 新哈希应使用的成本：不低于默认值，也不低于当前已存储的成本
+pub fn target_params(stored: Option<&str>) -> HashParams {
+    stored
+        .and_then(params_of)
+        .map(|p| p.max(&HashParams::default()))
+        .unwrap_or_default()
+}
+
+fn is_legacy(stored: &str) -> bool {
+    stored.len() == 64 && stored.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn legacy_sha256(password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(password.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cheap() -> HashParams {
+        HashParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn test_hash_roundtrip_and_params() {
+        let stored = hash("correct horse", &cheap()).unwrap();
+        assert!(stored.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert_eq!(params_of(&stored), Some(cheap()));
+        assert_eq!(
+            verify(&stored, "correct horse", &cheap()),
+            Verification::Valid { needs_upgrade: false }
+        );
+        assert_eq!(verify(&stored, "wrong horse", &cheap()), Verification::Invalid);
+
+        // 相同密码每次盐值不同
+        assert_ne!(stored, hash("correct horse", &cheap()).unwrap());
+    }
+
+    #[test]
+    fn test_weaker_params_need_upgrade() {
+        let stored = hash("pw", &cheap()).unwrap();
+        let target = HashParams { iterations: 2, ..cheap() };
+        assert_eq!(
+            verify(&stored, "pw", &target),
+            Verification::Valid { needs_upgrade: true }
+        );
+    }
+
+    #[test]
+    fn test_legacy_sha256_verifies_and_needs_upgrade() {
+        let legacy = legacy_sha256("old-password");
+        assert_eq!(params_of(&legacy), None);
+        assert_eq!(
+            verify(&legacy, "old-password", &cheap()),
+            Verification::Valid { needs_upgrade: true }
+        );
+        assert_eq!(verify(&legacy, "other", &cheap()), Verification::Invalid);
+    }
+
+    #[test]
+    fn test_validate_rejects_low_cost() {
+        assert!(HashParams::default().validate().is_ok());
+        assert!(cheap().validate().is_err());
+        assert_eq!(target_params(None), HashParams::default());
+    }
+}
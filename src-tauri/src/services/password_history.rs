@@ -0,0 +1,95 @@
+//! 密码历史保留策略
+
+use crate::models::{PasswordHistoryRetention, UserSetting};
+use crate::services::database::DatabaseService;
+
+/// 保留策略在 user_settings 中的键
+pub const RETENTION_SETTING: &str = "password_history.retention";
+/// 每个条目可保留的记录数上限
+pub const MAX_ENTRIES_LIMIT: u32 = 1000;
+/// 可保留的天数上限
+pub const MAX_AGE_DAYS_LIMIT: u32 = 3650;
+
+/// 读取保留策略；未设置时不限制
+pub fn load_retention(db: &DatabaseService) -> Result<PasswordHistoryRetention, String> {
+    match db.get_user_setting(RETENTION_SETTING)?.map(|s| s.value) {
+        Some(json) if !json.trim().is_empty() => {
+            serde_json::from_str(&json).map_err(|e| format!("密码历史保留策略无效: {}", e))
+        }
+        _ => Ok(PasswordHistoryRetention::default()),
+    }
+}
+
+/// 保存保留策略
+pub fn save_retention(db: &DatabaseService, retention: &PasswordHistoryRetention) -> Result<(), String> {
+    if retention.max_entries > MAX_ENTRIES_LIMIT {
+        return Err(format!("保留记录数不能超过 {}", MAX_ENTRIES_LIMIT));
+    }
+    if retention.max_age_days > MAX_AGE_DAYS_LIMIT {
+        return Err(format!("保留天数不能超过 {}", MAX_AGE_DAYS_LIMIT));
+    }
+    let json = serde_json::to_string(retention).map_err(|e| e.to_string())?;
+    db.set_user_setting(&UserSetting {
+        id: None,
+        key: RETENTION_SETTING.to_string(),
+        value: json,
+        r#type: Some("json".to_string()),
+        category: Some("security".to_string()),
+        description: Some("密码历史保留策略".to_string()),
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+/// 按保留策略清理单个条目的历史记录，返回删除数量
+pub fn enforce(db: &DatabaseService, password_id: i64) -> Result<usize, String> {
+    let retention = load_retention(db)?;
+    if retention == PasswordHistoryRetention::default() {
+        return Ok(0);
+    }
+    db.prune_password_history(password_id, retention.max_entries, retention.max_age_days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_keeps_recent_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseService::new(dir.path().join("test.db").to_str().unwrap());
+        db.initialize().unwrap();
+
+        let password: crate::models::Password =
+            serde_json::from_value(serde_json::json!({ "title": "GitHub" })).unwrap();
+        let (id, other_id) = (db.add_password(&password).unwrap(), db.add_password(&password).unwrap());
+        for old in ["a", "b", "c", "d"] {
+            db.add_password_history(id, old, None).unwrap();
+        }
+        db.add_password_history(other_id, "other", None).unwrap();
+        let conn = db.get_connection().unwrap();
+        conn.execute(
+            "UPDATE password_history SET changed_at = datetime('now', '-40 days') WHERE old_password = 'a'",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(enforce(&db, id).unwrap(), 0);
+        assert!(save_retention(&db, &PasswordHistoryRetention { max_entries: 5000, max_age_days: 0 }).is_err());
+
+        save_retention(&db, &PasswordHistoryRetention { max_entries: 0, max_age_days: 30 }).unwrap();
+        assert_eq!(enforce(&db, id).unwrap(), 1);
+
+        save_retention(&db, &PasswordHistoryRetention { max_entries: 2, max_age_days: 30 }).unwrap();
+        assert_eq!(load_retention(&db).unwrap().max_entries, 2);
+        assert_eq!(enforce(&db, id).unwrap(), 1);
+        let kept: Vec<String> = db
+            .get_password_history(id)
+            .unwrap()
+            .into_iter()
+            .map(|h| h.old_password.expose().to_string())
+            .collect();
+        assert_eq!(kept, vec!["d".to_string(), "c".to_string()]);
+        assert_eq!(db.get_password_history(other_id).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,455 @@
+//! 结构化搜索查询
+//!
+//! 查询由空格分隔的条件组成，所有条件须同时满足，条件前加 `-` 表示排除：
+//! - `tag:work`：标签
+//! - `group:"Infra"`：所在分组或任一上级分组的名称，含 `/` 时按分组路径前缀匹配
+//! - `url:github.com`：网址包含
+//! - `is:favorite` / `is:pinned` / `is:archived`：收藏、置顶、归档
+//! - `type:login` / `type:note`：条目类型
+//! - `updated:<30d` / `updated:>1y`：最近 30 天内更新 / 超过一年未更新（单位 d、w、m、y，默认为天）
+//! - 其他词或带引号的短语：在标题、用户名、网址、备注与笔记内容中查找
+//!
+//! 不认识的 `key:value`（如网址 `https://…`）按普通文字处理。
+
+use crate::models::{EncryptableField, ItemType, QueryResult};
+use crate::services::database::DatabaseService;
+use crate::services::field_encryption::FieldCipher;
+use chrono::{NaiveDateTime, Utc};
+use std::collections::HashMap;
+use zeroize::Zeroizing;
+
+/// 状态条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryFlag {
+    Favorite,
+    Pinned,
+    Archived,
+}
+
+/// 单个查询条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryFilter {
+    Text(String),
+    Tag(String),
+    Group(String),
+    Url(String),
+    Is(QueryFlag),
+    Type(ItemType),
+    /// `within` 为真表示最近 `days` 天内更新，否则表示超过 `days` 天未更新
+    Updated { within: bool, days: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTerm {
+    pub negated: bool,
+    pub filter: QueryFilter,
+}
+
+/// 解析后的查询
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub terms: Vec<QueryTerm>,
+}
+
+const KEYWORDS: [&str; 6] = ["tag", "group", "url", "is", "type", "updated"];
+
+/// 读取一个值：带引号时读到闭合引号（支持 `\"` 转义），否则读到空白
+fn read_value(chars: &[char], pos: &mut usize) -> Result<String, String> {
+    let mut value = String::new();
+    if chars.get(*pos) == Some(&'"') {
+        *pos += 1;
+        loop {
+            match chars.get(*pos) {
+                None => return Err("引号未闭合".to_string()),
+                Some('"') => {
+                    *pos += 1;
+                    return Ok(value);
+                }
+                Some('\\') if chars.get(*pos + 1) == Some(&'"') => {
+                    value.push('"');
+                    *pos += 2;
+                }
+                Some(c) => {
+                    value.push(*c);
+                    *pos += 1;
+                }
+            }
+        }
+    }
+    while let Some(c) = chars.get(*pos).filter(|c| !c.is_whitespace()) {
+        value.push(*c);
+        *pos += 1;
+    }
+    Ok(value)
+}
+
+fn parse_flag(value: &str) -> Result<QueryFlag, String> {
+    match value.to_lowercase().as_str() {
+        "favorite" | "fav" | "starred" => Ok(QueryFlag::Favorite),
+        "pinned" => Ok(QueryFlag::Pinned),
+        "archived" => Ok(QueryFlag::Archived),
+        _ => Err(format!("未知的状态条件: is:{}", value)),
+    }
+}
+
+fn parse_type(value: &str) -> Result<ItemType, String> {
+    match value.to_lowercase().as_str() {
+        "login" | "password" => Ok(ItemType::Login),
+        "note" | "secure_note" => Ok(ItemType::SecureNote),
+        _ => Err(format!("未知的条目类型: type:{}", value)),
+    }
+}
+
+/// 解析 `<30d`、`>2w`、`7` 等时间条件；不带比较符时表示“以内”
+fn parse_updated(value: &str) -> Result<QueryFilter, String> {
+    let invalid = || format!("无效的时间条件: updated:{}", value);
+    let (within, rest) = match value.strip_prefix('<') {
+        Some(rest) => (true, rest),
+        None => match value.strip_prefix('>') {
+            Some(rest) => (false, rest),
+            None => (true, value),
+        },
+    };
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+    let (number, unit_days) = match rest.char_indices().last() {
+        Some((i, 'd')) => (&rest[..i], 1),
+        Some((i, 'w')) => (&rest[..i], 7),
+        Some((i, 'm')) => (&rest[..i], 30),
+        Some((i, 'y')) => (&rest[..i], 365),
+        _ => (rest, 1),
+    };
+    let count: u32 = number.parse().map_err(|_| invalid())?;
+    let days = count.checked_mul(unit_days).ok_or_else(invalid)?;
+    Ok(QueryFilter::Updated { within, days })
+}
+
+/// 解析查询字符串
+pub fn parse(input: &str) -> Result<SearchQuery, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut terms = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        if chars[pos].is_whitespace() {
+            pos += 1;
+            continue;
+        }
+        let negated = chars[pos] == '-' && chars.get(pos + 1).is_some_and(|c| !c.is_whitespace());
+        if negated {
+            pos += 1;
+        }
+
+        let key_start = pos;
+        while pos < chars.len() && chars[pos].is_ascii_alphabetic() {
+            pos += 1;
+        }
+        let key: String = chars[key_start..pos].iter().collect::<String>().to_lowercase();
+        let filter = if chars.get(pos) == Some(&':') && KEYWORDS.contains(&key.as_str()) {
+            pos += 1;
+            let value = read_value(&chars, &mut pos)?;
+            if value.trim().is_empty() {
+                return Err(format!("搜索条件 {} 缺少值", key));
+            }
+            match key.as_str() {
+                "tag" => QueryFilter::Tag(value),
+                "group" => QueryFilter::Group(value),
+                "url" => QueryFilter::Url(value),
+                "is" => QueryFilter::Is(parse_flag(&value)?),
+                "type" => QueryFilter::Type(parse_type(&value)?),
+                _ => parse_updated(&value)?,
+            }
+        } else {
+            pos = key_start;
+            let value = read_value(&chars, &mut pos)?;
+            if value.is_empty() {
+                continue;
+            }
+            QueryFilter::Text(value)
+        };
+        terms.push(QueryTerm { negated, filter });
+    }
+    Ok(SearchQuery { terms })
+}
+
+/// 参与匹配的条目（明文）
+pub struct QueryCandidate {
+    pub item_type: ItemType,
+    pub id: i64,
+    pub title: String,
+    pub username: Option<String>,
+    pub group_id: Option<i64>,
+    pub group_path: Vec<String>,
+    pub urls: Vec<String>,
+    pub tags: Vec<String>,
+    /// 备注或笔记内容
+    pub body: Option<Zeroizing<String>>,
+    pub favorite: bool,
+    pub pinned: bool,
+    pub archived: bool,
+    pub updated_at: Option<String>,
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// 拆分标签字段，兼容逗号、分号、空白分隔与 JSON 数组写法
+pub fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(|c: char| c.is_whitespace() || matches!(c, ',' | '，' | ';' | '；'))
+        .map(|tag| tag.trim_matches(|c| matches!(c, '[' | ']' | '"' | '#')))
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl QueryFilter {
+    fn matches(&self, c: &QueryCandidate, now: NaiveDateTime) -> bool {
+        match self {
+            QueryFilter::Text(text) => {
+                contains_ignore_case(&c.title, text)
+                    || c.username.as_deref().is_some_and(|u| contains_ignore_case(u, text))
+                    || c.urls.iter().any(|u| contains_ignore_case(u, text))
+                    || c.body.as_deref().is_some_and(|b| contains_ignore_case(b, text))
+            }
+            QueryFilter::Tag(tag) => c.tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()),
+            QueryFilter::Group(group) => {
+                let group = group.trim_matches('/').to_lowercase();
+                let path: Vec<String> = c.group_path.iter().map(|name| name.to_lowercase()).collect();
+                if group.contains('/') {
+                    let joined = path.join("/");
+                    joined == group || joined.starts_with(&format!("{}/", group))
+                } else {
+                    path.contains(&group)
+                }
+            }
+            QueryFilter::Url(url) => c.urls.iter().any(|u| contains_ignore_case(u, url)),
+            QueryFilter::Is(QueryFlag::Favorite) => c.favorite,
+            QueryFilter::Is(QueryFlag::Pinned) => c.pinned,
+            QueryFilter::Is(QueryFlag::Archived) => c.archived,
+            QueryFilter::Type(item_type) => c.item_type == *item_type,
+            QueryFilter::Updated { within, days } => {
+                let Some(updated) = c
+                    .updated_at
+                    .as_deref()
+                    .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").ok())
+                else {
+                    return false;
+                };
+                let age = now.signed_duration_since(updated).num_seconds();
+                let limit = i64::from(*days) * 86_400;
+                if *within {
+                    age <= limit
+                } else {
+                    age > limit
+                }
+            }
+        }
+    }
+}
+
+impl SearchQuery {
+    /// 条目是否满足全部条件
+    pub fn matches(&self, candidate: &QueryCandidate, now: NaiveDateTime) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.filter.matches(candidate, now) != term.negated)
+    }
+}
+
+/// 按 `parent_id` 计算每个分组从根开始的名称路径
+pub fn group_paths(groups: &[(i64, Option<i64>, String)]) -> HashMap<i64, Vec<String>> {
+    let by_id: HashMap<i64, (Option<i64>, &str)> =
+        groups.iter().map(|(id, parent, name)| (*id, (*parent, name.as_str()))).collect();
+    groups
+        .iter()
+        .map(|(id, _, _)| {
+            let mut path = Vec::new();
+            let mut current = Some(*id);
+            // 路径长度不超过分组总数，防止数据中存在环
+            while let Some((parent, name)) = current.and_then(|id| by_id.get(&id)).filter(|_| path.len() < by_id.len()) {
+                path.push(name.to_string());
+                current = *parent;
+            }
+            path.reverse();
+            (*id, path)
+        })
+        .collect()
+}
+
+/// 在全部密码条目与笔记中执行查询（不含回收站中的条目），按标题排序
+pub fn run(db: &DatabaseService, cipher: &FieldCipher, query: &SearchQuery) -> Result<Vec<QueryResult>, String> {
+    let password_groups: Vec<_> = db
+        .get_groups()?
+        .into_iter()
+        .filter_map(|g| g.id.map(|id| (id, g.parent_id, g.name)))
+        .collect();
+    let note_groups: Vec<_> = db
+        .get_note_groups()?
+        .into_iter()
+        .filter_map(|g| g.id.map(|id| (id, g.parent_id, g.name)))
+        .collect();
+    let (password_paths, note_paths) = (group_paths(&password_groups), group_paths(&note_groups));
+    let path_of = |paths: &HashMap<i64, Vec<String>>, group_id: Option<i64>| {
+        group_id.and_then(|id| paths.get(&id).cloned()).unwrap_or_default()
+    };
+
+    let mut urls: HashMap<i64, Vec<String>> = HashMap::new();
+    for saved in db.get_all_password_urls()? {
+        if let Some(url) = cipher.decrypt_value(EncryptableField::PasswordUrl, Some(saved.url)) {
+            urls.entry(saved.password_id).or_default().push(url);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for mut p in db.get_passwords(None)? {
+        let Some(id) = p.id else { continue };
+        p.password = None;
+        cipher.decrypt_password(&mut p);
+        let mut entry_urls = urls.remove(&id).unwrap_or_default();
+        entry_urls.extend(p.url.take().filter(|u| !u.is_empty()));
+        candidates.push(QueryCandidate {
+            item_type: ItemType::Login,
+            id,
+            group_path: path_of(&password_paths, p.group_id),
+            urls: entry_urls,
+            tags: p.tags.as_deref().map(split_tags).unwrap_or_default(),
+            body: p.notes.take().map(Zeroizing::new),
+            favorite: p.favorite.unwrap_or(false),
+            pinned: false,
+            archived: false,
+            title: p.title,
+            username: p.username,
+            group_id: p.group_id,
+            updated_at: p.updated_at,
+        });
+    }
+    for note in db.get_notes(None, Some(cipher))? {
+        let Some(id) = note.id else { continue };
+        candidates.push(QueryCandidate {
+            item_type: ItemType::SecureNote,
+            id,
+            group_path: path_of(&note_paths, note.group_id),
+            urls: Vec::new(),
+            tags: Vec::new(),
+            body: note.content.as_ref().map(|c| Zeroizing::new(c.expose().to_string())),
+            favorite: false,
+            pinned: note.pinned.unwrap_or(false),
+            archived: note.archived.unwrap_or(false),
+            title: note.title,
+            username: None,
+            group_id: note.group_id,
+            updated_at: note.updated_at,
+        });
+    }
+
+    let now = Utc::now().naive_utc();
+    let mut results: Vec<QueryResult> = candidates
+        .into_iter()
+        .filter(|c| query.matches(c, now))
+        .map(|c| QueryResult {
+            item_type: c.item_type,
+            id: c.id,
+            title: c.title,
+            username: c.username,
+            group_id: c.group_id,
+            group_path: c.group_path,
+            favorite: c.favorite,
+            updated_at: c.updated_at,
+        })
+        .collect();
+    results.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()).then(a.id.cmp(&b.id)));
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(negated: bool, filter: QueryFilter) -> QueryTerm {
+        QueryTerm { negated, filter }
+    }
+
+    #[test]
+    fn test_parse_example_query() {
+        let query = parse(r#"tag:work group:"Infra Team" url:github.com is:favorite updated:<30d"#).unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                term(false, QueryFilter::Tag("work".to_string())),
+                term(false, QueryFilter::Group("Infra Team".to_string())),
+                term(false, QueryFilter::Url("github.com".to_string())),
+                term(false, QueryFilter::Is(QueryFlag::Favorite)),
+                term(false, QueryFilter::Updated { within: true, days: 30 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_text_negation_and_unknown_keys() {
+        let query = parse(r#"  -TAG:old "deploy key"  https://a.example/x 银行 type:note updated:>2w -"#).unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                term(true, QueryFilter::Tag("old".to_string())),
+                term(false, QueryFilter::Text("deploy key".to_string())),
+                term(false, QueryFilter::Text("https://a.example/x".to_string())),
+                term(false, QueryFilter::Text("银行".to_string())),
+                term(false, QueryFilter::Type(ItemType::SecureNote)),
+                term(false, QueryFilter::Updated { within: false, days: 14 }),
+                term(false, QueryFilter::Text("-".to_string())),
+            ]
+        );
+        assert_eq!(parse(r#"group:"say \"hi\"""#).unwrap().terms[0].filter, QueryFilter::Group("say \"hi\"".to_string()));
+        assert_eq!(parse("updated:7").unwrap().terms[0].filter, QueryFilter::Updated { within: true, days: 7 });
+        assert_eq!(parse("").unwrap(), SearchQuery::default());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(r#"group:"Infra"#).is_err());
+        assert!(parse("tag:").is_err());
+        assert!(parse("is:deleted").is_err());
+        assert!(parse("type:wifi").is_err());
+        assert!(parse("updated:<soon").is_err());
+        assert!(parse("updated:99999999999y").is_err());
+    }
+
+    #[test]
+    fn test_matches_candidate() {
+        let now = NaiveDateTime::parse_from_str("2024-06-30 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let candidate = QueryCandidate {
+            item_type: ItemType::Login,
+            id: 1,
+            title: "GitHub".to_string(),
+            username: Some("alice".to_string()),
+            group_id: Some(2),
+            group_path: vec!["Infra".to_string(), "CI".to_string()],
+            urls: vec!["https://github.com/login".to_string()],
+            tags: split_tags("work, ops"),
+            body: Some(Zeroizing::new("部署密钥".to_string())),
+            favorite: true,
+            pinned: false,
+            archived: false,
+            updated_at: Some("2024-06-20 08:00:00".to_string()),
+        };
+        let matches = |q: &str| parse(q).unwrap().matches(&candidate, now);
+        assert!(matches(r#"tag:work group:"Infra" url:github.com is:favorite updated:<30d"#));
+        assert!(matches("group:infra/ci 部署 ALICE -tag:personal type:login"));
+        assert!(!matches("group:infra/c"));
+        assert!(!matches("updated:>30d"));
+        assert!(!matches("-is:favorite"));
+        assert!(!matches("tag:wor"));
+    }
+
+    #[test]
+    fn test_group_paths_handles_cycles() {
+        let groups = vec![
+            (1, None, "Infra".to_string()),
+            (2, Some(1), "CI".to_string()),
+            (3, Some(4), "A".to_string()),
+            (4, Some(3), "B".to_string()),
+        ];
+        let paths = group_paths(&groups);
+        assert_eq!(paths[&2], vec!["Infra".to_string(), "CI".to_string()]);
+        assert!(paths[&3].len() <= groups.len());
+    }
+}
@@ -0,0 +1,219 @@
+//! 修订历史
+//!
+//! 密码条目与笔记每次更新前，将修改前的全部字段以明文快照形式序列化后整体加密保存。
+//! 是否产生修订按明文比较：重新加密相同内容（随机 IV 导致密文不同）不会产生修订。
+
+use crate::models::{
+    ItemType, Password, Revision, RevisionFieldChange, RevisionSnapshot, SecretString, SecureRecord,
+};
+use crate::services::database::DatabaseService;
+use crate::services::encryption::EncryptionService;
+use std::collections::BTreeSet;
+use zeroize::Zeroizing;
+
+/// 支持修订历史的条目类型
+pub fn ensure_supported(item_type: ItemType) -> Result<(), String> {
+    match item_type {
+        ItemType::Login | ItemType::SecureNote => Ok(()),
+        _ => Err("该条目类型没有修订历史".to_string()),
+    }
+}
+
+fn insert_text(snapshot: &mut RevisionSnapshot, name: &str, value: Option<&str>) {
+    if let Some(value) = value.filter(|v| !v.is_empty()) {
+        snapshot.insert(name.to_string(), value.into());
+    }
+}
+
+/// 布尔字段只记录为真的情况，`None` 与 `false` 视为相同
+fn insert_flag(snapshot: &mut RevisionSnapshot, name: &str, value: Option<bool>) {
+    if value == Some(true) {
+        snapshot.insert(name.to_string(), "true".into());
+    }
+}
+
+fn text(snapshot: &RevisionSnapshot, name: &str) -> Option<String> {
+    snapshot.get(name).map(|v| v.expose().to_string())
+}
+
+fn flag(snapshot: &RevisionSnapshot, name: &str) -> Option<bool> {
+    Some(snapshot.get(name).is_some_and(|v| v.expose() == "true"))
+}
+
+fn group_id(snapshot: &RevisionSnapshot) -> Result<Option<i64>, String> {
+    snapshot
+        .get("group_id")
+        .map(|v| v.expose().parse::<i64>().map_err(|_| "修订快照中的分组无效".to_string()))
+        .transpose()
+}
+
+/// 密码条目（明文）的快照
+pub fn password_snapshot(p: &Password) -> RevisionSnapshot {
+    let mut snapshot = RevisionSnapshot::new();
+    insert_text(&mut snapshot, "title", Some(&p.title));
+    insert_text(&mut snapshot, "username", p.username.as_deref());
+    insert_text(&mut snapshot, "password", p.password.as_ref().map(SecretString::expose));
+    insert_text(&mut snapshot, "url", p.url.as_deref());
+    insert_text(&mut snapshot, "notes", p.notes.as_deref());
+    insert_text(&mut snapshot, "group_id", p.group_id.map(|id| id.to_string()).as_deref());
+    insert_flag(&mut snapshot, "favorite", p.favorite);
+    insert_text(&mut snapshot, "tags", p.tags.as_deref());
+    snapshot
+}
+
+/// 将快照中的字段写回密码条目（明文），其余字段保持不变
+pub fn apply_password_snapshot(p: &mut Password, snapshot: &RevisionSnapshot) -> Result<(), String> {
+    p.title = text(snapshot, "title").unwrap_or_default();
+    p.username = text(snapshot, "username");
+    p.password = snapshot.get("password").cloned();
+    p.url = text(snapshot, "url");
+    p.notes = text(snapshot, "notes");
+    p.group_id = group_id(snapshot)?;
+    p.favorite = flag(snapshot, "favorite");
+    p.tags = text(snapshot, "tags");
+    Ok(())
+}
+
+/// 笔记（明文）的快照
+pub fn note_snapshot(note: &SecureRecord) -> RevisionSnapshot {
+    let mut snapshot = RevisionSnapshot::new();
+    insert_text(&mut snapshot, "title", Some(&note.title));
+    insert_text(&mut snapshot, "content", note.content.as_ref().map(SecretString::expose));
+    insert_text(&mut snapshot, "group_id", note.group_id.map(|id| id.to_string()).as_deref());
+    insert_flag(&mut snapshot, "pinned", note.pinned);
+    insert_flag(&mut snapshot, "archived", note.archived);
+    snapshot
+}
+
+/// 将快照中的字段写回笔记（明文），其余字段保持不变
+pub fn apply_note_snapshot(note: &mut SecureRecord, snapshot: &RevisionSnapshot) -> Result<(), String> {
+    note.title = text(snapshot, "title").unwrap_or_default();
+    note.content = snapshot.get("content").cloned();
+    note.group_id = group_id(snapshot)?;
+    note.pinned = flag(snapshot, "pinned");
+    note.archived = flag(snapshot, "archived");
+    Ok(())
+}
+
+/// 逐字段比较两个版本，返回有变化的字段
+pub fn diff(before: &RevisionSnapshot, after: &RevisionSnapshot) -> Vec<RevisionFieldChange> {
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| before.get(*name) != after.get(*name))
+        .map(|name| RevisionFieldChange {
+            field: name.clone(),
+            before: before.get(name).cloned(),
+            after: after.get(name).cloned(),
+        })
+        .collect()
+}
+
+/// 加密快照
+pub fn seal(encryption: &EncryptionService, snapshot: &RevisionSnapshot) -> Result<String, String> {
+    let json = Zeroizing::new(serde_json::to_string(snapshot).map_err(|e| e.to_string())?);
+    encryption.encrypt(&json)
+}
+
+/// 解密快照
+pub fn open(encryption: &EncryptionService, revision: &Revision) -> Result<RevisionSnapshot, String> {
+    let json = encryption.decrypt(&revision.snapshot)?;
+    serde_json::from_str(json.expose()).map_err(|e| format!("修订快照格式无效: {}", e))
+}
+
+/// 明文有变化时保存修改前的快照，返回修订 ID
+pub fn record(
+    db: &DatabaseService,
+    encryption: &EncryptionService,
+    item_type: ItemType,
+    item_id: i64,
+    before: &RevisionSnapshot,
+    after: &RevisionSnapshot,
+) -> Result<Option<i64>, String> {
+    let changed_fields: Vec<String> = diff(before, after).into_iter().map(|change| change.field).collect();
+    if changed_fields.is_empty() {
+        return Ok(None);
+    }
+    let revision = Revision {
+        id: None,
+        item_type,
+        item_id,
+        changed_fields,
+        snapshot: seal(encryption, before)?,
+        created_at: None,
+    };
+    db.add_revision(&revision).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_password() -> Password {
+        serde_json::from_value(serde_json::json!({
+            "title": "GitHub",
+            "username": "alice",
+            "password": "s3cret",
+            "group_id": 3,
+            "favorite": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip_and_diff() {
+        let encryption = EncryptionService::new("test_key");
+        let original = sample_password();
+        let before = password_snapshot(&original);
+        assert!(!before.contains_key("favorite"));
+
+        let mut edited = original.clone();
+        edited.password = Some("n3w-secret".into());
+        edited.favorite = Some(true);
+        edited.notes = Some(String::new());
+        let after = password_snapshot(&edited);
+        let changes = diff(&before, &after);
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["favorite", "password"]);
+        assert_eq!(changes[1].before.as_ref().map(SecretString::expose), Some("s3cret"));
+
+        let revision = Revision {
+            id: None,
+            item_type: ItemType::Login,
+            item_id: 1,
+            changed_fields: vec![],
+            snapshot: seal(&encryption, &before).unwrap(),
+            created_at: None,
+        };
+        assert!(!revision.snapshot.contains("s3cret"));
+        let opened = open(&encryption, &revision).unwrap();
+        assert_eq!(opened, before);
+
+        apply_password_snapshot(&mut edited, &opened).unwrap();
+        assert_eq!(edited.password.as_ref().map(SecretString::expose), Some("s3cret"));
+        assert_eq!((edited.group_id, edited.favorite), (Some(3), Some(false)));
+    }
+
+    #[test]
+    fn test_record_skips_unchanged_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseService::new(dir.path().join("test.db").to_str().unwrap());
+        db.initialize().unwrap();
+        let encryption = EncryptionService::new("test_key");
+
+        let note: SecureRecord =
+            serde_json::from_value(serde_json::json!({ "title": "Todo", "content": "milk" })).unwrap();
+        let before = note_snapshot(&note);
+        assert_eq!(record(&db, &encryption, ItemType::SecureNote, 1, &before, &before).unwrap(), None);
+
+        let mut edited = note.clone();
+        edited.content = Some("milk, eggs".into());
+        let id = record(&db, &encryption, ItemType::SecureNote, 1, &before, &note_snapshot(&edited))
+            .unwrap()
+            .unwrap();
+        let revisions = db.get_revisions(ItemType::SecureNote, 1).unwrap();
+        assert_eq!(revisions[0].id, Some(id));
+        assert_eq!(revisions[0].changed_fields, vec!["content".to_string()]);
+        assert_eq!(open(&encryption, &revisions[0]).unwrap(), before);
+    }
+}
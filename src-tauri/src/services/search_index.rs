@@ -0,0 +1,424 @@
+//! 全文搜索索引
+//!
+//! 解锁后在内存中为解密后的标题、用户名、网址、标签、备注与笔记内容建立倒排索引，
+//! 不写入磁盘，数据密钥被丢弃时一并清空。密码字段不参与索引。
+//! 字母与数字按单词切分，中日韩文字按单字与相邻双字切分，因此不需要分词词典也能搜索中文。
+
+use crate::models::{EncryptableField, ItemType, Password, SearchField, SearchHit, SecretString, SecureRecord};
+use crate::services::database::DatabaseService;
+use crate::services::field_encryption::FieldCipher;
+use std::collections::{BTreeMap, HashMap};
+use zeroize::Zeroizing;
+
+type DocKey = (ItemType, i64);
+
+/// 摘要长度（字符）
+const SNIPPET_CHARS: usize = 64;
+/// 摘要中命中位置之前保留的字符数
+const SNIPPET_LEADING_CHARS: usize = 16;
+/// 单个查询词按前缀最多扩展的索引词数
+const MAX_PREFIX_EXPANSION: usize = 64;
+/// 前缀命中相对完整命中的得分比例
+const PREFIX_MATCH_FACTOR: f64 = 0.6;
+/// BM25 词频饱和参数
+const BM25_K1: f64 = 1.2;
+
+/// 待索引的条目（明文）
+pub struct SearchDocument {
+    pub item_type: ItemType,
+    pub id: i64,
+    pub title: String,
+    pub group_id: Option<i64>,
+    pub fields: Vec<(SearchField, Zeroizing<String>)>,
+}
+
+/// 切分出的词及其在原文中的字符区间
+struct Token {
+    term: String,
+    start: usize,
+    end: usize,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F
+    )
+}
+
+/// 切分文本
+///
+/// 建索引时中日韩文字同时产生单字与双字；查询时连续两个以上的中日韩文字只取双字，
+/// 避免“银行卡”匹配到只含“银”“行”“卡”单字的条目。
+fn tokenize(text: &str, for_query: bool) -> Vec<Token> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        if is_cjk(chars[i]) {
+            while i < chars.len() && is_cjk(chars[i]) {
+                i += 1;
+            }
+            let run = &chars[start..i];
+            if !for_query || run.len() == 1 {
+                for (offset, c) in run.iter().enumerate() {
+                    tokens.push(Token { term: c.to_string(), start: start + offset, end: start + offset + 1 });
+                }
+            }
+            for (offset, pair) in run.windows(2).enumerate() {
+                tokens.push(Token { term: pair.iter().collect(), start: start + offset, end: start + offset + 2 });
+            }
+        } else if chars[i].is_alphanumeric() {
+            while i < chars.len() && chars[i].is_alphanumeric() && !is_cjk(chars[i]) {
+                i += 1;
+            }
+            let term = chars[start..i].iter().flat_map(|c| c.to_lowercase()).collect();
+            tokens.push(Token { term, start, end: i });
+        } else {
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// 查询词是否命中索引词：中日韩文字须完全相同，其余按前缀匹配
+fn term_matches(query: &str, term: &str) -> bool {
+    if query.starts_with(is_cjk) {
+        term == query
+    } else {
+        term.starts_with(query)
+    }
+}
+
+/// 内存中的倒排索引
+#[derive(Default)]
+pub struct SearchIndex {
+    built: bool,
+    documents: HashMap<DocKey, SearchDocument>,
+    /// 词 -> 条目 -> 按字段权重累计的词频
+    postings: BTreeMap<String, HashMap<DocKey, f64>>,
+}
+
+impl SearchIndex {
+    /// 是否已从数据库建立
+    pub fn is_built(&self) -> bool {
+        self.built
+    }
+
+    /// 已索引的条目数
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// 清空索引，下次使用前需要重新建立
+    pub fn clear(&mut self) {
+        *self = SearchIndex::default();
+    }
+
+    /// 新增或替换条目
+    pub fn upsert(&mut self, document: SearchDocument) {
+        let key = (document.item_type, document.id);
+        self.remove(document.item_type, document.id);
+        for (field, text) in &document.fields {
+            for token in tokenize(text, false) {
+                *self.postings.entry(token.term).or_default().entry(key).or_default() += field.weight();
+            }
+        }
+        self.documents.insert(key, document);
+    }
+
+    /// 移除条目
+    pub fn remove(&mut self, item_type: ItemType, id: i64) {
+        let key = (item_type, id);
+        let Some(document) = self.documents.remove(&key) else {
+            return;
+        };
+        for (_, text) in &document.fields {
+            for token in tokenize(text, false) {
+                if let Some(docs) = self.postings.get_mut(&token.term) {
+                    docs.remove(&key);
+                    if docs.is_empty() {
+                        self.postings.remove(&token.term);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 查询词命中的条目及加权词频；字母数字查询词同时匹配以其为前缀的索引词
+    fn lookup(&self, query: &str) -> HashMap<DocKey, f64> {
+        let mut matches: HashMap<DocKey, f64> = HashMap::new();
+        let candidates = self
+            .postings
+            .range(query.to_string()..)
+            .take_while(|(term, _)| term_matches(query, term))
+            .take(MAX_PREFIX_EXPANSION);
+        for (term, docs) in candidates {
+            let factor = if term == query { 1.0 } else { PREFIX_MATCH_FACTOR };
+            for (key, frequency) in docs {
+                let entry = matches.entry(*key).or_default();
+                *entry = entry.max(frequency * factor);
+            }
+        }
+        matches
+    }
+
+    /// 搜索：所有查询词都须命中，按 BM25 相关度从高到低排序
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let mut terms: Vec<String> = tokenize(query, true).into_iter().map(|t| t.term).collect();
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let total = self.documents.len() as f64;
+        let mut scores: Option<HashMap<DocKey, f64>> = None;
+        for term in &terms {
+            let matches = self.lookup(term);
+            let df = matches.len() as f64;
+            let idf = ((total - df + 0.5) / (df + 0.5) + 1.0).ln();
+            let term_scores = matches
+                .into_iter()
+                .map(|(key, tf)| (key, idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1)));
+            scores = Some(match scores {
+                None => term_scores.collect(),
+                Some(mut previous) => {
+                    let term_scores: HashMap<DocKey, f64> = term_scores.collect();
+                    previous.retain(|key, _| term_scores.contains_key(key));
+                    for (key, score) in previous.iter_mut() {
+                        *score += term_scores[key];
+                    }
+                    previous
+                }
+            });
+        }
+
+        let mut ranked: Vec<(DocKey, f64)> = scores.unwrap_or_default().into_iter().collect();
+        ranked.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| self.documents[&a.0].title.cmp(&self.documents[&b.0].title))
+                .then_with(|| a.0 .1.cmp(&b.0 .1))
+        });
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(key, score)| {
+                let document = &self.documents[&key];
+                let (field, snippet, highlights) = snippet(document, &terms);
+                SearchHit {
+                    item_type: document.item_type,
+                    id: document.id,
+                    title: document.title.clone(),
+                    group_id: document.group_id,
+                    score,
+                    field,
+                    snippet,
+                    highlights,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 在命中最多（按字段权重）的字段中截取命中位置附近的摘要，并给出高亮区间
+fn snippet(document: &SearchDocument, terms: &[String]) -> (SearchField, SecretString, Vec<(usize, usize)>) {
+    let best = document
+        .fields
+        .iter()
+        .map(|(field, text)| {
+            let spans: Vec<(usize, usize)> = tokenize(text, false)
+                .into_iter()
+                .filter(|token| terms.iter().any(|q| term_matches(q, &token.term)))
+                .map(|token| (token.start, token.end))
+                .collect();
+            (*field, text, spans)
+        })
+        .filter(|(_, _, spans)| !spans.is_empty())
+        .max_by(|a, b| {
+            (a.0.weight() * a.2.len() as f64)
+                .total_cmp(&(b.0.weight() * b.2.len() as f64))
+                .then_with(|| b.0.weight().total_cmp(&a.0.weight()))
+        });
+    let Some((field, text, mut spans)) = best else {
+        return (SearchField::Title, SecretString::new(document.title.clone()), Vec::new());
+    };
+
+    spans.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let chars: Zeroizing<Vec<char>> = Zeroizing::new(text.chars().collect());
+    let mut start = merged[0].0.saturating_sub(SNIPPET_LEADING_CHARS);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+    start = start.min(end.saturating_sub(SNIPPET_CHARS));
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let offset = snippet.chars().count();
+    snippet.extend(chars[start..end].iter().map(|c| if c.is_whitespace() { ' ' } else { *c }));
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    let highlights = merged
+        .into_iter()
+        .filter(|(s, _)| *s >= start && *s < end)
+        .map(|(s, e)| (s - start + offset, e.min(end) - start + offset))
+        .collect();
+    (field, SecretString::new(snippet), highlights)
+}
+
+fn text_field(field: SearchField, value: Option<String>) -> Option<(SearchField, Zeroizing<String>)> {
+    value.filter(|v| !v.trim().is_empty()).map(|v| (field, Zeroizing::new(v)))
+}
+
+/// 密码条目（密文）的索引文档；`urls` 为已解密的附加网址
+fn password_document(cipher: &FieldCipher, mut p: Password, urls: Vec<String>) -> Option<SearchDocument> {
+    let id = p.id?;
+    p.password = None;
+    cipher.decrypt_password(&mut p);
+    let mut fields: Vec<_> = [
+        text_field(SearchField::Title, Some(p.title.clone())),
+        text_field(SearchField::Username, p.username.take()),
+        text_field(SearchField::Url, p.url.take()),
+        text_field(SearchField::Tags, p.tags.take()),
+        text_field(SearchField::Notes, p.notes.take()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    fields.extend(urls.into_iter().filter_map(|url| text_field(SearchField::Url, Some(url))));
+    Some(SearchDocument { item_type: ItemType::Login, id, title: p.title, group_id: p.group_id, fields })
+}
+
+/// 笔记（明文）的索引文档
+fn note_document(note: SecureRecord) -> Option<SearchDocument> {
+    let id = note.id?;
+    let content = note.content.as_ref().map(|c| c.expose().to_string());
+    let fields = [
+        text_field(SearchField::Title, Some(note.title.clone())),
+        text_field(SearchField::Content, content),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    Some(SearchDocument { item_type: ItemType::SecureNote, id, title: note.title, group_id: note.group_id, fields })
+}
+
+/// 从数据库解密全部密码条目与笔记并建立索引（不含回收站中的条目）
+pub fn build(db: &DatabaseService, cipher: &FieldCipher) -> Result<SearchIndex, String> {
+    let mut urls: HashMap<i64, Vec<String>> = HashMap::new();
+    for saved in db.get_all_password_urls()? {
+        if let Some(url) = cipher.decrypt_value(EncryptableField::PasswordUrl, Some(saved.url)) {
+            urls.entry(saved.password_id).or_default().push(url);
+        }
+    }
+
+    let mut index = SearchIndex { built: true, ..Default::default() };
+    for p in db.get_passwords(None)? {
+        let entry_urls = p.id.and_then(|id| urls.remove(&id)).unwrap_or_default();
+        if let Some(document) = password_document(cipher, p, entry_urls) {
+            index.upsert(document);
+        }
+    }
+    for note in db.get_notes(None, Some(cipher))? {
+        if let Some(document) = note_document(note) {
+            index.upsert(document);
+        }
+    }
+    Ok(index)
+}
+
+/// 重新读取单个条目的索引文档；条目不存在或已移入回收站时返回 `None`
+pub fn load_document(
+    db: &DatabaseService,
+    cipher: &FieldCipher,
+    item_type: ItemType,
+    id: i64,
+) -> Result<Option<SearchDocument>, String> {
+    match item_type {
+        ItemType::Login => {
+            let Some(p) = db.get_password(id)? else {
+                return Ok(None);
+            };
+            let urls = db
+                .get_password_urls(id)?
+                .into_iter()
+                .filter_map(|saved| cipher.decrypt_value(EncryptableField::PasswordUrl, Some(saved.url)))
+                .collect();
+            Ok(password_document(cipher, p, urls))
+        }
+        ItemType::SecureNote => {
+            let Some(mut note) = db.get_note(id)? else {
+                return Ok(None);
+            };
+            cipher.decrypt_note(&mut note);
+            Ok(note_document(note))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(item_type: ItemType, id: i64, fields: &[(SearchField, &str)]) -> SearchDocument {
+        SearchDocument {
+            item_type,
+            id,
+            title: fields[0].1.to_string(),
+            group_id: None,
+            fields: fields.iter().map(|(f, t)| (*f, Zeroizing::new(t.to_string()))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_tokenize_mixed_cjk_and_latin() {
+        let terms: Vec<String> = tokenize("招商银行 App-Login", false).into_iter().map(|t| t.term).collect();
+        assert!(terms.contains(&"银行".to_string()) && terms.contains(&"银".to_string()));
+        assert!(terms.contains(&"app".to_string()) && terms.contains(&"login".to_string()));
+        let query: Vec<String> = tokenize("银行卡", true).into_iter().map(|t| t.term).collect();
+        assert_eq!(query, vec!["银行".to_string(), "行卡".to_string()]);
+    }
+
+    #[test]
+    fn test_search_ranks_title_hits_and_highlights_snippet() {
+        let mut index = SearchIndex::default();
+        index.upsert(document(
+            ItemType::SecureNote,
+            1,
+            &[(SearchField::Title, "旅行计划"), (SearchField::Content, "周末去杭州，记得带上招商银行卡和身份证")],
+        ));
+        index.upsert(document(ItemType::Login, 2, &[(SearchField::Title, "招商银行"), (SearchField::Username, "alice")]));
+        index.upsert(document(ItemType::Login, 3, &[(SearchField::Title, "GitHub"), (SearchField::Url, "https://github.com")]));
+
+        let hits = index.search("招商银行", 10);
+        assert_eq!(hits.iter().map(|h| h.id).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(hits[1].field, SearchField::Content);
+        let snippet: Vec<char> = hits[1].snippet.expose().chars().collect();
+        let (start, end) = hits[1].highlights[0];
+        assert_eq!(snippet[start..end].iter().collect::<String>(), "招商银行");
+
+        let hits = index.search("git", 10);
+        assert_eq!((hits.len(), hits[0].field), (1, SearchField::Title));
+        assert!(index.search("github 银行", 10).is_empty());
+        assert!(index.search("  ", 10).is_empty());
+
+        index.remove(ItemType::Login, 2);
+        assert_eq!(index.search("招商", 10).len(), 1);
+        assert!(!index.postings.contains_key("alice"));
+    }
+}
@@ -0,0 +1,262 @@
+//! 一次性密码（RFC 6238 TOTP）
+//!
+//! 解析 `otpauth://totp/...` URI，并按 HMAC-SHA1 / SHA256 / SHA512 计算当前验证码。
+//! 共享密钥以 Base32 文本形式传递与存储（存储前由调用方加密）。
+
+use crate::models::{PasswordTotp, SecretString, TotpAlgorithm, TotpCode};
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use zeroize::Zeroizing;
+
+pub const DEFAULT_DIGITS: u32 = 6;
+pub const DEFAULT_PERIOD: u64 = 30;
+
+/// 解析 otpauth URI，返回未绑定条目（`password_id = 0`）的 TOTP 配置
+///
+/// 支持 `secret`、`issuer`、`algorithm`、`digits`、`period` 参数；
+/// 标签形如 `Issuer:account`，参数中的 issuer 优先。
+pub fn parse_otpauth_uri(uri: &str) -> Result<PasswordTotp, String> {
+    let rest = uri
+        .trim()
+        .strip_prefix("otpauth://")
+        .ok_or("不是有效的 otpauth URI")?;
+    let (kind, rest) = rest.split_once('/').ok_or("otpauth URI 缺少标签")?;
+    if !kind.eq_ignore_ascii_case("totp") {
+        return Err(format!("不支持的一次性密码类型: {}", kind));
+    }
+    let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let label = decode_component(label)?;
+    let (label_issuer, account_name) = match label.split_once(':') {
+        Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim().to_string()),
+        None => (None, label.trim().to_string()),
+    };
+
+    let mut secret = None;
+    let mut issuer = None;
+    let mut algorithm = TotpAlgorithm::default();
+    let mut digits = DEFAULT_DIGITS;
+    let mut period = DEFAULT_PERIOD;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = decode_component(value)?;
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => secret = Some(SecretString::new(value)),
+            "issuer" => issuer = Some(value),
+            "algorithm" => {
+                algorithm = TotpAlgorithm::parse(&value)
+                    .ok_or_else(|| format!("不支持的 TOTP 算法: {}", value))?
+            }
+            "digits" => digits = value.parse().map_err(|_| format!("位数无效: {}", value))?,
+            "period" => period = value.parse().map_err(|_| format!("时间步长无效: {}", value))?,
+            _ => {}
+        }
+    }
+
+    let totp = PasswordTotp {
+        password_id: 0,
+        secret: normalize_secret(secret.ok_or("otpauth URI 缺少 secret 参数")?.expose())?,
+        issuer: issuer.or(label_issuer).filter(|s| !s.is_empty()),
+        account_name: Some(account_name).filter(|s| !s.is_empty()),
+        algorithm,
+        digits,
+        period,
+        created_at: None,
+        updated_at: None,
+    };
+    validate(&totp)?;
+    Ok(totp)
+}
+
+/// 生成 otpauth URI（导出备份时使用）
+pub fn to_otpauth_uri(totp: &PasswordTotp) -> SecretString {
+    let account = totp.account_name.as_deref().unwrap_or_default();
+    let label = match totp.issuer.as_deref() {
+        Some(issuer) => format!("{}:{}", encode_component(issuer), encode_component(account)),
+        None => encode_component(account),
+    };
+    let mut uri = format!(
+        "otpauth://totp/{}?secret={}&algorithm={}&digits={}&period={}",
+        label,
+        totp.secret.expose(),
+        totp.algorithm.as_str(),
+        totp.digits,
+        totp.period
+    );
+    if let Some(issuer) = totp.issuer.as_deref() {
+        uri.push_str("&issuer=");
+        uri.push_str(&encode_component(issuer));
+    }
+    SecretString::new(uri)
+}
+
+/// 规范化 Base32 密钥：去除空白与填充并转为大写，校验可解码
+pub fn normalize_secret(secret: &str) -> Result<SecretString, String> {
+    let normalized: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if normalized.is_empty() {
+        return Err("TOTP 密钥不能为空".to_string());
+    }
+    decode_secret(&normalized)?;
+    Ok(SecretString::new(normalized))
+}
+
+/// 校验位数与时间步长
+pub fn validate(totp: &PasswordTotp) -> Result<(), String> {
+    if !(6..=8).contains(&totp.digits) {
+        return Err("TOTP 位数必须在 6 到 8 之间".to_string());
+    }
+    if totp.period == 0 {
+        return Err("TOTP 时间步长必须大于 0".to_string());
+    }
+    Ok(())
+}
+
+/// 计算指定 Unix 时间（秒）的验证码；`totp.secret` 须为明文
+pub fn generate_code(totp: &PasswordTotp, unix_time: u64) -> Result<TotpCode, String> {
+    validate(totp)?;
+    let key = decode_secret(totp.secret.expose())?;
+    let counter = unix_time / totp.period;
+    Ok(TotpCode {
+        code: hotp(totp.algorithm, &key, counter, totp.digits)?,
+        remaining_seconds: totp.period - unix_time % totp.period,
+        period: totp.period,
+    })
+}
+
+/// RFC 4226 HOTP：HMAC 后动态截断取 `digits` 位十进制数
+pub fn hotp(algorithm: TotpAlgorithm, key: &[u8], counter: u64, digits: u32) -> Result<String, String> {
+    let digest = match algorithm {
+        TotpAlgorithm::Sha1 => hmac_digest::<Hmac<sha1::Sha1>>(key, &counter.to_be_bytes())?,
+        TotpAlgorithm::Sha256 => hmac_digest::<Hmac<sha2::Sha256>>(key, &counter.to_be_bytes())?,
+        TotpAlgorithm::Sha512 => hmac_digest::<Hmac<sha2::Sha512>>(key, &counter.to_be_bytes())?,
+    };
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    let code = binary as u64 % 10u64.pow(digits);
+    Ok(format!("{:0width$}", code, width = digits as usize))
+}
+
+fn hmac_digest<M: Mac + KeyInit>(key: &[u8], message: &[u8]) -> Result<Zeroizing<Vec<u8>>, String> {
+    let mut mac = <M as Mac>::new_from_slice(key).map_err(|e| format!("HMAC 初始化失败: {}", e))?;
+    mac.update(message);
+    Ok(Zeroizing::new(mac.finalize().into_bytes().to_vec()))
+}
+
+/// 解码 Base32 密钥；部分服务生成的密钥末尾有多余位，解码时忽略
+fn decode_secret(secret: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    let mut spec = data_encoding::BASE32_NOPAD.specification();
+    spec.check_trailing_bits = false;
+    spec.encoding()
+        .map_err(|e| e.to_string())?
+        .decode(secret.trim_end_matches('=').as_bytes())
+        .map(Zeroizing::new)
+        .map_err(|_| "TOTP 密钥不是有效的 Base32 编码".to_string())
+}
+
+fn decode_component(value: &str) -> Result<String, String> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .map(|s| s.into_owned())
+        .map_err(|_| "otpauth URI 编码无效".to_string())
+}
+
+fn encode_component(value: &str) -> String {
+    utf8_percent_encode(value, NON_ALPHANUMERIC).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED_SHA1: &[u8] = b"12345678901234567890";
+    const SEED_SHA256: &[u8] = b"12345678901234567890123456789012";
+    const SEED_SHA512: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    fn totp(algorithm: TotpAlgorithm, seed: &[u8]) -> PasswordTotp {
+        PasswordTotp {
+            password_id: 1,
+            secret: data_encoding::BASE32_NOPAD.encode(seed).into(),
+            issuer: None,
+            account_name: None,
+            algorithm,
+            digits: 8,
+            period: 30,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        // RFC 6238 附录 B
+        let vectors: [(u64, &str, &str, &str); 6] = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        let sha1 = totp(TotpAlgorithm::Sha1, SEED_SHA1);
+        let sha256 = totp(TotpAlgorithm::Sha256, SEED_SHA256);
+        let sha512 = totp(TotpAlgorithm::Sha512, SEED_SHA512);
+        for (time, expected_sha1, expected_sha256, expected_sha512) in vectors {
+            assert_eq!(generate_code(&sha1, time).unwrap().code, expected_sha1, "SHA1 @ {}", time);
+            assert_eq!(generate_code(&sha256, time).unwrap().code, expected_sha256, "SHA256 @ {}", time);
+            assert_eq!(generate_code(&sha512, time).unwrap().code, expected_sha512, "SHA512 @ {}", time);
+        }
+    }
+
+    #[test]
+    fn test_remaining_seconds_and_short_codes() {
+        let mut config = totp(TotpAlgorithm::Sha1, SEED_SHA1);
+        config.digits = 6;
+        let code = generate_code(&config, 59).unwrap();
+        assert_eq!(code.code, "287082");
+        assert_eq!(code.remaining_seconds, 1);
+        assert_eq!(generate_code(&config, 60).unwrap().remaining_seconds, 30);
+    }
+
+    #[test]
+    fn test_parse_otpauth_uri() {
+        let parsed = parse_otpauth_uri(
+            "otpauth://totp/ACME%20Co:john.doe%40email.com?secret=hxdm vjec-jjws&issuer=ACME%20Co&algorithm=sha256&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(parsed.secret.expose(), "HXDMVJECJJWS");
+        assert_eq!(parsed.issuer.as_deref(), Some("ACME Co"));
+        assert_eq!(parsed.account_name.as_deref(), Some("john.doe@email.com"));
+        assert_eq!(parsed.algorithm, TotpAlgorithm::Sha256);
+        assert_eq!((parsed.digits, parsed.period), (8, 60));
+
+        let minimal = parse_otpauth_uri("otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP").unwrap();
+        assert_eq!(minimal.issuer.as_deref(), Some("GitHub"));
+        assert_eq!(minimal.algorithm, TotpAlgorithm::Sha1);
+        assert_eq!((minimal.digits, minimal.period), (DEFAULT_DIGITS, DEFAULT_PERIOD));
+
+        let roundtrip = parse_otpauth_uri(to_otpauth_uri(&parsed).expose()).unwrap();
+        assert_eq!(roundtrip.secret, parsed.secret);
+        assert_eq!(roundtrip.issuer, parsed.issuer);
+        assert_eq!(roundtrip.account_name, parsed.account_name);
+        assert_eq!(roundtrip.algorithm, parsed.algorithm);
+    }
+
+    #[test]
+    fn test_parse_otpauth_uri_rejects_invalid() {
+        assert!(parse_otpauth_uri("https://example.com").is_err());
+        assert!(parse_otpauth_uri("otpauth://hotp/a?secret=JBSWY3DPEHPK3PXP&counter=1").is_err());
+        assert!(parse_otpauth_uri("otpauth://totp/a").is_err());
+        assert!(parse_otpauth_uri("otpauth://totp/a?secret=not*base32").is_err());
+        assert!(parse_otpauth_uri("otpauth://totp/a?secret=JBSWY3DPEHPK3PXP&digits=4").is_err());
+        assert!(parse_otpauth_uri("otpauth://totp/a?secret=JBSWY3DPEHPK3PXP&algorithm=MD5").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! 回收站保留期限
+//!
+//! 删除的密码、笔记与结构化条目先移入回收站，超过保留天数后在解锁或查看回收站时自动彻底删除。
+
+use crate::models::UserSetting;
+use crate::services::database::DatabaseService;
+
+/// 回收站保留天数在 user_settings 中的键
+pub const RETENTION_SETTING: &str = "trash.retention_days";
+/// 未设置时的保留天数
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+/// 可设置的保留天数范围；0 表示不自动清理
+pub const RETENTION_DAYS_RANGE: std::ops::RangeInclusive<u32> = 0..=3650;
+
+/// 读取回收站保留天数
+pub fn retention_days(db: &DatabaseService) -> Result<u32, String> {
+    db.get_user_setting_in_range(RETENTION_SETTING, &RETENTION_DAYS_RANGE, DEFAULT_RETENTION_DAYS)
+}
+
+/// 设置回收站保留天数
+pub fn set_retention_days(db: &DatabaseService, days: u32) -> Result<(), String> {
+    if !RETENTION_DAYS_RANGE.contains(&days) {
+        return Err(format!(
+            "回收站保留天数须在 {}-{} 之间",
+            RETENTION_DAYS_RANGE.start(),
+            RETENTION_DAYS_RANGE.end()
+        ));
+    }
+    db.set_user_setting(&UserSetting {
+        id: None,
+        key: RETENTION_SETTING.to_string(),
+        value: days.to_string(),
+        r#type: Some("number".to_string()),
+        category: Some("general".to_string()),
+        description: Some("回收站保留天数（0 表示不自动清理）".to_string()),
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+/// 彻底删除超过保留天数的条目，返回删除数量
+pub fn purge_expired(db: &DatabaseService) -> Result<usize, String> {
+    match retention_days(db)? {
+        0 => Ok(0),
+        days => db.purge_trash(Some(days)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_setting_and_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DatabaseService::new(dir.path().join("test.db").to_str().unwrap());
+        db.initialize().unwrap();
+
+        assert_eq!(retention_days(&db).unwrap(), DEFAULT_RETENTION_DAYS);
+        assert!(set_retention_days(&db, 5000).is_err());
+
+        let password: crate::models::Password =
+            serde_json::from_value(serde_json::json!({ "title": "Old" })).unwrap();
+        let old_id = db.add_password(&password).unwrap();
+        let recent_id = db.add_password(&password).unwrap();
+        db.delete_password(old_id).unwrap();
+        db.delete_password(recent_id).unwrap();
+        let conn = db.get_connection().unwrap();
+        conn.execute(
+            "UPDATE passwords SET deleted_at = datetime('now', '-10 days') WHERE id = ?1",
+            [old_id],
+        )
+        .unwrap();
+
+        set_retention_days(&db, 0).unwrap();
+        assert_eq!(purge_expired(&db).unwrap(), 0);
+
+        set_retention_days(&db, 7).unwrap();
+        assert_eq!(purge_expired(&db).unwrap(), 1);
+        let remaining: Vec<i64> = db.get_trash().unwrap().iter().map(|item| item.id).collect();
+        assert_eq!(remaining, vec![recent_id]);
+    }
+}
@@ -0,0 +1,104 @@
+//! 登录网址匹配
+//!
+//! 不依赖完整的 URL 解析：只提取协议、主机名与路径做比较，足以覆盖自动填充场景。
+
+use crate::models::UrlMatchRule;
+
+/// 常见的二级公共后缀（如 `co.uk`、`com.cn`）使用的二级标签
+const SECOND_LEVEL_LABELS: &[&str] = &["co", "com", "net", "org", "gov", "edu", "ac", "or", "ne", "go"];
+
+/// 按规则判断 `candidate` 是否匹配已保存的网址 `saved`
+pub fn matches(rule: UrlMatchRule, saved: &str, candidate: &str) -> bool {
+    match rule {
+        UrlMatchRule::Exact => {
+            let (saved, candidate) = (normalize_url(saved), normalize_url(candidate));
+            !saved.is_empty() && saved == candidate
+        }
+        UrlMatchRule::Host => match (host(saved), host(candidate)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        },
+        UrlMatchRule::BaseDomain => match (host(saved), host(candidate)) {
+            (Some(a), Some(b)) => base_domain(&a) == base_domain(&b),
+            _ => false,
+        },
+    }
+}
+
+/// 提取小写主机名（去除协议、用户信息与端口）
+pub fn host(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = if let Some(ipv6) = authority.strip_prefix('[') {
+        ipv6.split(']').next().unwrap_or_default()
+    } else {
+        authority.split(':').next().unwrap_or_default()
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+/// 可注册域名：一般取最后两级，`example.co.uk` 这类二级公共后缀取最后三级；IP 地址原样返回
+pub fn base_domain(host: &str) -> String {
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return host.to_string();
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    let keep = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && SECOND_LEVEL_LABELS.contains(second) && labels.len() >= 3 => 3,
+        _ => 2,
+    };
+    labels[labels.len().saturating_sub(keep)..].join(".")
+}
+
+/// 精确匹配前的规范化：协议与主机小写，去除片段与末尾斜杠
+fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split('#').next().unwrap_or_default();
+    let (scheme, rest) = url.split_once("://").unwrap_or(("https", url));
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    format!(
+        "{}://{}{}",
+        scheme.to_ascii_lowercase(),
+        authority.to_ascii_lowercase(),
+        path.trim_end_matches('/')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_extraction() {
+        assert_eq!(host("https://User@Mail.Example.com:8443/inbox?x=1").as_deref(), Some("mail.example.com"));
+        assert_eq!(host("example.com/login").as_deref(), Some("example.com"));
+        assert_eq!(host("http://[::1]:8080/").as_deref(), Some("::1"));
+        assert_eq!(host("https://"), None);
+    }
+
+    #[test]
+    fn test_base_domain() {
+        assert_eq!(base_domain("login.github.com"), "github.com");
+        assert_eq!(base_domain("www.bbc.co.uk"), "bbc.co.uk");
+        assert_eq!(base_domain("pan.baidu.com.cn"), "baidu.com.cn");
+        assert_eq!(base_domain("localhost"), "localhost");
+        assert_eq!(base_domain("192.168.1.1"), "192.168.1.1");
+    }
+
+    #[test]
+    fn test_match_rules() {
+        let saved = "https://accounts.example.com/login/";
+        assert!(matches(UrlMatchRule::Exact, saved, "HTTPS://accounts.example.com/login"));
+        assert!(!matches(UrlMatchRule::Exact, saved, "https://accounts.example.com/signup"));
+        assert!(matches(UrlMatchRule::Host, saved, "https://accounts.example.com/signup"));
+        assert!(!matches(UrlMatchRule::Host, saved, "https://www.example.com/"));
+        assert!(matches(UrlMatchRule::BaseDomain, saved, "https://www.example.com/"));
+        assert!(!matches(UrlMatchRule::BaseDomain, saved, "https://example.org/"));
+    }
+}
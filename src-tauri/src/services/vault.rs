@@ -0,0 +1,1044 @@
+//! 保险库密钥服务
+//!
+//! 密钥层级：
+//! - 数据密钥（随机 32 字节）加密所有敏感字段
+//! - 主密码经 KDF 派生包装密钥，用于包装数据密钥（kind = master）
+//! - 未要求主密码时，数据密钥另以应用级固定密钥包装（kind = device），启动即可解锁
+//!
+//! 旧版数据直接使用应用级固定密钥加密，首次建立数据密钥时一次性迁移。
+
+use crate::models::{SecretString, VaultKey, VaultRekey};
+use crate::services::database::DatabaseService;
+use crate::services::encryption::{CipherFormat, EncryptionService};
+use crate::services::password_hashing::HashParams;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rand::RngCore;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::ops::Deref;
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::time::Duration;
+use zeroize::{Zeroize, Zeroizing};
+
+pub const KEY_KIND_MASTER: &str = "master";
+pub const KEY_KIND_DEVICE: &str = "device";
+pub const KEY_KIND_RECOVERY: &str = "recovery";
+
+const KDF_ARGON2ID: &str = "argon2id";
+const KDF_PBKDF2_SHA256: &str = "pbkdf2-sha256";
+const SALT_LEN: usize = 16;
+
+/// 恢复密钥长度（160 位随机数，Base32 编码后 32 个字符）
+const RECOVERY_KEY_BYTES: usize = 20;
+/// 恢复密钥本身熵足够，只需少量迭代
+const RECOVERY_KDF_ITERATIONS: u32 = 10_000;
+
+/// 以数据密钥加密存储的列：(表名, 主键列, 密文列)
+pub const ENCRYPTED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("passwords", "id", "password"),
+    ("password_history", "id", "old_password"),
+    ("password_totp", "password_id", "secret"),
+    ("password_custom_fields", "id", "secret_value"),
+    ("password_accounts", "id", "password"),
+    ("attachments", "id", "file_key"),
+    ("vault_item_fields", "id", "secret_value"),
+    ("secure_records", "id", "content"),
+    ("revisions", "id", "snapshot"),
+];
+
+/// 全部密文已升级为 AEAD 格式后写入该配置，此后不再接受旧版 CBC 密文
+pub const CIPHER_FORMAT_SETTING: &str = "security.cipher_format";
+const CIPHER_FORMAT_AEAD: &str = "2";
+
+/// 以密文存储的敏感配置项
+pub const SENSITIVE_SETTING_KEYS: &[&str] = &[
+    "backup.cloud.secret_id",
+    "backup.cloud.secret_key",
+    "backup.auto_export_password",
+];
+
+/// 包装密钥派生参数（以 JSON 存于 vault_keys.kdf_params）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    pub iterations: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_kib: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallelism: Option<u32>,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self::argon2id(&HashParams::default())
+    }
+}
+
+impl KdfParams {
+    /// 与主密码哈希相同成本的 Argon2id 参数
+    pub fn argon2id(params: &HashParams) -> Self {
+        Self {
+            algorithm: KDF_ARGON2ID.to_string(),
+            iterations: params.iterations,
+            memory_kib: Some(params.memory_kib),
+            parallelism: Some(params.parallelism),
+        }
+    }
+}
+
+/// 生成新的随机数据密钥
+pub fn generate_data_key() -> EncryptionService {
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    let service = EncryptionService::from_key(key);
+    key.zeroize();
+    service
+}
+
+fn derive_wrapping_key(
+    password: &str,
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<Zeroizing<[u8; 32]>, String> {
+    match params.algorithm.as_str() {
+        KDF_ARGON2ID => {
+            let hash_params = HashParams {
+                memory_kib: params.memory_kib.ok_or("缺少 Argon2 内存参数")?,
+                iterations: params.iterations,
+                parallelism: params.parallelism.unwrap_or(1),
+            };
+            let mut key = Zeroizing::new([0u8; 32]);
+            hash_params
+                .argon2()?
+                .hash_password_into(password.as_bytes(), salt, key.as_mut())
+                .map_err(|e| format!("派生包装密钥失败: {}", e))?;
+            Ok(key)
+        }
+        KDF_PBKDF2_SHA256 => {
+            let mut key = Zeroizing::new([0u8; 32]);
+            pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, params.iterations.max(1), key.as_mut());
+            Ok(key)
+        }
+        other => Err(format!("不支持的密钥派生算法: {}", other)),
+    }
+}
+
+pub(crate) fn wrap_data_key(wrapping_key: &EncryptionService, data_key: &EncryptionService) -> Result<String, String> {
+    let encoded = Zeroizing::new(BASE64.encode(data_key.key_bytes()));
+    wrapping_key.encrypt(&encoded)
+}
+
+pub(crate) fn unwrap_data_key(
+    wrapping_key: &EncryptionService,
+    wrapped: &str,
+) -> Result<(EncryptionService, CipherFormat), String> {
+    let (encoded, format) = wrapping_key
+        .decrypt_with_format(wrapped)
+        .map_err(|_| "数据密钥解包失败".to_string())?;
+    let bytes = Zeroizing::new(
+        BASE64
+            .decode(encoded.expose())
+            .map_err(|_| "数据密钥解包失败".to_string())?,
+    );
+    let mut key: [u8; 32] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| "数据密钥解包失败".to_string())?;
+    let service = EncryptionService::from_key(key);
+    key.zeroize();
+    Ok((service, format))
+}
+
+/// 使用主密码包装数据密钥
+pub fn wrap_with_password(
+    data_key: &EncryptionService,
+    password: &str,
+    params: &KdfParams,
+) -> Result<VaultKey, String> {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let wrapping_key = EncryptionService::from_key(*derive_wrapping_key(password, &salt, params)?);
+
+    Ok(VaultKey {
+        kind: KEY_KIND_MASTER.to_string(),
+        wrapped_key: wrap_data_key(&wrapping_key, data_key)?,
+        kdf_salt: Some(BASE64.encode(salt)),
+        kdf_params: Some(serde_json::to_string(params).map_err(|e| e.to_string())?),
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+/// 使用应用级固定密钥包装数据密钥（未要求主密码时使用）
+pub fn wrap_with_device_key(data_key: &EncryptionService) -> Result<VaultKey, String> {
+    Ok(VaultKey {
+        kind: KEY_KIND_DEVICE.to_string(),
+        wrapped_key: wrap_data_key(&EncryptionService::new_with_app_key(), data_key)?,
+        kdf_salt: None,
+        kdf_params: None,
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+/// 生成新的恢复密钥，格式如 `ABCD-EFGH-...`（8 组，每组 4 个字符）
+pub fn generate_recovery_key() -> SecretString {
+    let mut bytes = Zeroizing::new([0u8; RECOVERY_KEY_BYTES]);
+    rand::thread_rng().fill_bytes(bytes.as_mut());
+    let encoded = Zeroizing::new(data_encoding::BASE32_NOPAD.encode(bytes.as_ref()));
+    let grouped: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    SecretString::new(grouped.join("-"))
+}
+
+/// 规范化用户输入的恢复密钥：忽略分隔符、空白与大小写
+fn normalize_recovery_key(input: &str) -> Result<Zeroizing<String>, String> {
+    let normalized = Zeroizing::new(
+        input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect::<String>()
+            .to_ascii_uppercase(),
+    );
+    match data_encoding::BASE32_NOPAD.decode(normalized.as_bytes()) {
+        Ok(mut bytes) if bytes.len() == RECOVERY_KEY_BYTES => {
+            bytes.zeroize();
+            Ok(normalized)
+        }
+        _ => Err("恢复密钥格式无效".to_string()),
+    }
+}
+
+fn recovery_kdf_params() -> KdfParams {
+    KdfParams {
+        algorithm: KDF_PBKDF2_SHA256.to_string(),
+        iterations: RECOVERY_KDF_ITERATIONS,
+        memory_kib: None,
+        parallelism: None,
+    }
+}
+
+/// 使用恢复密钥包装数据密钥
+pub fn wrap_with_recovery_key(data_key: &EncryptionService, recovery_key: &str) -> Result<VaultKey, String> {
+    let normalized = normalize_recovery_key(recovery_key)?;
+    let mut key = wrap_with_password(data_key, &normalized, &recovery_kdf_params())?;
+    key.kind = KEY_KIND_RECOVERY.to_string();
+    Ok(key)
+}
+
+/// 使用恢复密钥解包数据密钥
+pub fn unlock_with_recovery_key(db: &DatabaseService, recovery_key: &str) -> Result<EncryptionService, String> {
+    let wrapped = db.get_vault_key(KEY_KIND_RECOVERY)?.ok_or("未设置恢复密钥")?;
+    let normalized = normalize_recovery_key(recovery_key)?;
+    let (data_key, _) = unwrap_master_key(&wrapped, &normalized).map_err(|_| "恢复密钥错误".to_string())?;
+    apply_cipher_policy(db, data_key)
+}
+
+/// 使用主密码解包数据密钥
+pub fn unwrap_with_password(key: &VaultKey, password: &str) -> Result<EncryptionService, String> {
+    unwrap_master_key(key, password).map(|(data_key, _)| data_key)
+}
+
+fn unwrap_master_key(key: &VaultKey, password: &str) -> Result<(EncryptionService, CipherFormat), String> {
+    let salt = BASE64
+        .decode(key.kdf_salt.as_deref().ok_or("缺少密钥派生盐值")?)
+        .map_err(|e| format!("密钥派生盐值无效: {}", e))?;
+    let params: KdfParams = serde_json::from_str(key.kdf_params.as_deref().ok_or("缺少密钥派生参数")?)
+        .map_err(|e| format!("密钥派生参数无效: {}", e))?;
+    let wrapping_key = EncryptionService::from_key(*derive_wrapping_key(password, &salt, &params)?);
+    unwrap_data_key(&wrapping_key, &key.wrapped_key)
+}
+
+/// 使用应用级固定密钥解包数据密钥
+pub fn unwrap_with_device_key(key: &VaultKey) -> Result<EncryptionService, String> {
+    unwrap_data_key(&EncryptionService::new_with_app_key(), &key.wrapped_key).map(|(data_key, _)| data_key)
+}
+
+/// 启动时打开保险库
+///
+/// 存在设备级包装时直接解锁；尚未建立数据密钥且无需主密码时，
+/// 立即生成数据密钥并迁移旧数据；否则保持锁定，等待主密码解锁。
+pub fn open_on_startup(db: &DatabaseService) -> Result<Option<EncryptionService>, String> {
+    if let Some(device_key) = db.get_vault_key(KEY_KIND_DEVICE)? {
+        let (data_key, format) = unwrap_data_key(&EncryptionService::new_with_app_key(), &device_key.wrapped_key)?;
+        if format == CipherFormat::LegacyCbc {
+            db.replace_vault_keys(&[wrap_with_device_key(&data_key)?], &[])?;
+        }
+        let data_key = apply_cipher_policy(db, data_key)?;
+        return match resume_rekey(db, &data_key) {
+            Ok(new_key) => Ok(Some(new_key.unwrap_or(data_key))),
+            Err(e) => {
+                log::error!("Failed to resume vault rekey: {}", e);
+                Ok(Some(data_key))
+            }
+        };
+    }
+    if db.has_vault_keys()? {
+        return Ok(None);
+    }
+
+    let (has_master, _hint, require_password) = db.get_master_password_config()?;
+    if has_master && require_password {
+        // 旧版保险库要求主密码：等首次解锁时再迁移，届时可直接写入主密码包装
+        return Ok(None);
+    }
+
+    let data_key = generate_data_key();
+    let device_key = wrap_with_device_key(&data_key)?;
+    let migrated = migrate_legacy_vault(db, &data_key, &[device_key])?;
+    log::info!("Vault data key created, {} legacy values re-encrypted", migrated);
+    Ok(Some(data_key))
+}
+
+/// 主密码校验通过后解包数据密钥
+///
+/// 尚无主密码包装时（旧版保险库或主密码早于密钥层级设置），
+/// 使用现有数据密钥或新生成的数据密钥补建主密码包装。
+pub fn unlock_with_password(
+    db: &DatabaseService,
+    password: &str,
+    params: &KdfParams,
+) -> Result<EncryptionService, String> {
+    if let Some(master_key) = db.get_vault_key(KEY_KIND_MASTER)? {
+        let (data_key, format) = unwrap_master_key(&master_key, password)?;
+        if format == CipherFormat::LegacyCbc {
+            // 旧格式包装无认证：沿用原 KDF 参数重新包装
+            let kdf_params = master_key
+                .kdf_params
+                .as_deref()
+                .and_then(|p| serde_json::from_str::<KdfParams>(p).ok())
+                .unwrap_or_else(|| params.clone());
+            db.replace_vault_keys(&[wrap_with_password(&data_key, password, &kdf_params)?], &[])?;
+        }
+        return apply_cipher_policy(db, data_key);
+    }
+
+    let (_has_master, _hint, require_password) = db.get_master_password_config()?;
+    match db.get_vault_key(KEY_KIND_DEVICE)? {
+        Some(device_key) => {
+            let data_key = unwrap_with_device_key(&device_key)?;
+            let master_key = wrap_with_password(&data_key, password, params)?;
+            let deletes: &[&str] = if require_password { &[KEY_KIND_DEVICE] } else { &[] };
+            db.replace_vault_keys(&[master_key], deletes)?;
+            apply_cipher_policy(db, data_key)
+        }
+        None => {
+            let data_key = generate_data_key();
+            let mut wraps = vec![wrap_with_password(&data_key, password, params)?];
+            if !require_password {
+                wraps.push(wrap_with_device_key(&data_key)?);
+            }
+            let migrated = migrate_legacy_vault(db, &data_key, &wraps)?;
+            log::info!("Vault data key created on unlock, {} legacy values re-encrypted", migrated);
+            Ok(data_key)
+        }
+    }
+}
+
+/// 全部密文升级完成后，数据密钥不再接受旧版 CBC 密文
+fn apply_cipher_policy(db: &DatabaseService, data_key: EncryptionService) -> Result<EncryptionService, String> {
+    let upgraded = db
+        .get_user_setting(CIPHER_FORMAT_SETTING)
+        .map_err(|e| e.to_string())?
+        .is_some_and(|s| s.value == CIPHER_FORMAT_AEAD);
+    Ok(if upgraded { data_key.without_legacy() } else { data_key })
+}
+
+/// 密文格式升级结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CipherUpgradeReport {
+    /// 本次改写为 AEAD 格式的字段数
+    pub upgraded: usize,
+    /// 无法解密、保持原样的字段数
+    pub failed: usize,
+}
+
+/// 将所有旧版 CBC 密文逐条改写为 AEAD 格式
+///
+/// 每条记录单独更新，且仅在密文未被并发修改时写入；
+/// 全部完成且没有失败时记录 [`CIPHER_FORMAT_SETTING`]。
+pub fn upgrade_ciphertexts(db: &DatabaseService, data_key: &EncryptionService) -> Result<CipherUpgradeReport, String> {
+    let conn = db.get_connection().map_err(|e| e.to_string())?;
+    let mut report = CipherUpgradeReport::default();
+
+    let mut upgrade = |value: &str, write: &dyn Fn(&str) -> rusqlite::Result<usize>| -> Result<(), String> {
+        match data_key.decrypt_with_format(value) {
+            Ok((plain, CipherFormat::LegacyCbc)) => {
+                write(&data_key.encrypt(plain.expose())?).map_err(|e| e.to_string())?;
+                report.upgraded += 1;
+            }
+            Ok(_) => {}
+            Err(_) => report.failed += 1,
+        }
+        Ok(())
+    };
+
+    for (table, id_column, column) in ENCRYPTED_COLUMNS {
+        let select_sql = format!(
+            "SELECT {id_column}, {column} FROM {table} WHERE {column} IS NOT NULL AND {column} != ''"
+        );
+        let update_sql =
+            format!("UPDATE {table} SET {column} = ?1 WHERE {id_column} = ?2 AND {column} = ?3");
+        let rows: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare(&select_sql).map_err(|e| e.to_string())?;
+            let iter = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?;
+            iter.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+        };
+        for (id, cipher) in rows {
+            upgrade(&cipher, &|new_cipher| conn.execute(&update_sql, (new_cipher, id, &cipher)))?;
+        }
+    }
+
+    for key in SENSITIVE_SETTING_KEYS {
+        let cipher: Option<String> = conn
+            .query_row("SELECT value FROM user_settings WHERE key = ?1", [key], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?
+            .flatten();
+        let Some(cipher) = cipher.filter(|v| !v.trim().is_empty()) else {
+            continue;
+        };
+        upgrade(&cipher, &|new_cipher| {
+            conn.execute(
+                "UPDATE user_settings SET value = ?1 WHERE key = ?2 AND value = ?3",
+                (new_cipher, key, &cipher),
+            )
+        })?;
+    }
+
+    if report.failed == 0 {
+        conn.execute(
+            "INSERT INTO user_settings (key, value, type, category, description, created_at, updated_at)
+             VALUES (?1, ?2, 'string', 'security', '密文格式版本', datetime('now'), datetime('now'))
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = datetime('now')",
+            (CIPHER_FORMAT_SETTING, CIPHER_FORMAT_AEAD),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(report)
+}
+
+/// 数据密钥副本的租约
+///
+/// 命令从取出数据密钥到写完密文之间持有租约。修改主密码时先停止发放新租约，
+/// 等已有租约全部归还后再轮换，避免轮换提交后仍有命令用已丢弃的旧密钥写入密文。
+#[derive(Debug, Default)]
+pub struct KeyLeases {
+    counts: Mutex<LeaseCounts>,
+    released: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct LeaseCounts {
+    active: usize,
+    rotating: bool,
+}
+
+/// 持有租约期间可用的数据密钥（或基于它的服务），离开作用域时归还租约
+pub struct Leased<'a, T> {
+    value: T,
+    leases: &'a KeyLeases,
+}
+
+impl<T> Deref for Leased<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Drop for Leased<'_, T> {
+    fn drop(&mut self) {
+        self.leases.release();
+    }
+}
+
+/// 数据密钥轮换期间持有，离开作用域时恢复发放租约
+pub struct KeyRotation<'a> {
+    leases: &'a KeyLeases,
+}
+
+impl Drop for KeyRotation<'_> {
+    fn drop(&mut self) {
+        self.leases.counts.lock().unwrap_or_else(PoisonError::into_inner).rotating = false;
+    }
+}
+
+impl KeyLeases {
+    /// 取得租约后调用 `load` 取出数据密钥
+    ///
+    /// 轮换进行中时直接返回错误而不等待：持有租约的命令可能再次取密钥，等待会与轮换互相卡死。
+    pub fn lease<T>(&self, load: impl FnOnce() -> Result<T, String>) -> Result<Leased<'_, T>, String> {
+        {
+            let mut counts = self.counts.lock().map_err(|e| e.to_string())?;
+            if counts.rotating {
+                return Err("正在修改主密码，请稍后重试".to_string());
+            }
+            counts.active += 1;
+        }
+        match load() {
+            Ok(value) => Ok(Leased { value, leases: self }),
+            Err(e) => {
+                self.release();
+                Err(e)
+            }
+        }
+    }
+
+    fn release(&self) {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner).active -= 1;
+        self.released.notify_all();
+    }
+
+    /// 停止发放新租约并等待已有租约归还；超过 `timeout` 仍未归还时放弃轮换
+    pub fn begin_rotation(&self, timeout: Duration) -> Result<KeyRotation<'_>, String> {
+        let mut counts = self.counts.lock().map_err(|e| e.to_string())?;
+        if counts.rotating {
+            return Err("正在修改主密码，请稍后重试".to_string());
+        }
+        counts.rotating = true;
+        let (mut counts, wait) = self
+            .released
+            .wait_timeout_while(counts, timeout, |c| c.active > 0)
+            .map_err(|e| e.to_string())?;
+        if wait.timed_out() {
+            counts.rotating = false;
+            return Err("仍有操作正在使用数据密钥，请稍后重试".to_string());
+        }
+        Ok(KeyRotation { leases: self })
+    }
+}
+
+/// 修改主密码：轮换数据密钥并重新加密全部数据
+///
+/// 恢复包装只能用恢复密钥本身建立，旧的恢复密钥因此随数据密钥一同失效；传入 `recovery_key` 时
+/// 为新数据密钥建立恢复包装，调用方须在报告成功前向用户展示它。崩溃后由 [`resume_rekey`] 完成时
+/// 该恢复密钥从未展示过，不再写入。
+///
+/// 先持久化任务记录（新数据密钥由当前数据密钥包装），再在单个事务中重新加密所有字段、
+/// 替换密钥包装并更新主密码哈希。事务中途崩溃时数据库回滚为旧状态，
+/// 下次解锁后由 [`resume_rekey`] 继续完成；事务返回错误时删除任务记录，主密码保持不变。
+pub fn rekey_vault(
+    db: &DatabaseService,
+    current: &EncryptionService,
+    new_password: &str,
+    password_hash: &str,
+    hint: Option<&str>,
+    params: &KdfParams,
+    recovery_key: Option<&str>,
+) -> Result<EncryptionService, String> {
+    let new_key = generate_data_key();
+    let mut new_keys = vec![wrap_with_password(&new_key, new_password, params)?];
+    if db.get_vault_key(KEY_KIND_DEVICE)?.is_some() {
+        new_keys.push(wrap_with_device_key(&new_key)?);
+    }
+    if let Some(recovery_key) = recovery_key {
+        new_keys.push(wrap_with_recovery_key(&new_key, recovery_key)?);
+    }
+    let rekey = VaultRekey {
+        wrapped_new_key: wrap_data_key(current, &new_key)?,
+        new_keys,
+        password_hash: Some(password_hash.to_string()),
+        hint: hint.map(str::to_string),
+        started_at: None,
+    };
+    db.save_vault_rekey(&rekey)?;
+    // 任务记录只留给崩溃后继续；已告知用户修改失败时不能在下次解锁时悄悄改用新密码
+    finish_rekey(db, current, new_key, &rekey).inspect_err(|_| {
+        if let Err(e) = db.delete_vault_rekey() {
+            log::error!("Failed to discard vault rekey journal: {}", e);
+        }
+    })
+}
+
+/// 存在未完成的重新加密任务时用当前数据密钥继续完成
+///
+/// 完成后返回新的数据密钥，此时主密码已变为任务中的新密码；没有任务时返回 None。
+pub fn resume_rekey(
+    db: &DatabaseService,
+    current: &EncryptionService,
+) -> Result<Option<EncryptionService>, String> {
+    let Some(rekey) = db.get_vault_rekey()? else {
+        return Ok(None);
+    };
+    let Ok((new_key, _)) = unwrap_data_key(current, &rekey.wrapped_new_key) else {
+        // 任务不属于当前数据密钥（已被后续操作取代），直接丢弃
+        log::warn!("Discarding vault rekey journal that does not match the current data key");
+        db.delete_vault_rekey()?;
+        return Ok(None);
+    };
+    // 崩溃前签发的恢复密钥未能展示给用户，保留其包装只会留下无人知道的恢复密钥
+    let mut rekey = rekey;
+    rekey.new_keys.retain(|key| key.kind != KEY_KIND_RECOVERY);
+    let new_key = finish_rekey(db, current, new_key, &rekey)?;
+    log::info!("Resumed interrupted vault rekey started at {:?}", rekey.started_at);
+    Ok(Some(new_key))
+}
+
+fn finish_rekey(
+    db: &DatabaseService,
+    current: &EncryptionService,
+    new_key: EncryptionService,
+    rekey: &VaultRekey,
+) -> Result<EncryptionService, String> {
+    let mut conn = db.get_connection().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let count = reencrypt_all(&tx, current, &new_key, true)?;
+    DatabaseService::finish_vault_rekey(&tx, rekey).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    log::info!("Vault data key rotated, {} values re-encrypted", count);
+    apply_cipher_policy(db, new_key)
+}
+
+/// 将旧版固定密钥加密的数据迁移到新数据密钥，并在同一事务中写入密钥包装
+fn migrate_legacy_vault(
+    db: &DatabaseService,
+    data_key: &EncryptionService,
+    wraps: &[VaultKey],
+) -> Result<usize, String> {
+    let legacy = EncryptionService::new_with_app_key();
+    let mut conn = db.get_connection().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    // 旧版数据可能夹杂无法解密的值，跳过而不阻止迁移
+    let migrated = reencrypt_all(&tx, &legacy, data_key, false)?;
+    for wrap in wraps {
+        DatabaseService::write_vault_key(&tx, wrap).map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(migrated)
+}
+
+/// 把所有加密字段从 `from` 密钥重新加密到 `to` 密钥
+///
+/// `strict` 为 true 时遇到无法用 `from` 解密的值立即返回错误，由调用方回滚事务——
+/// 轮换后旧密钥即被丢弃，保留原值等于永久丢失数据；否则保持原样并记录警告。
+pub(crate) fn reencrypt_all(
+    conn: &Connection,
+    from: &EncryptionService,
+    to: &EncryptionService,
+    strict: bool,
+) -> Result<usize, String> {
+    let mut count = 0;
+    // 按字段加密策略加密的列同样随数据密钥轮换
+    let policy = crate::services::field_encryption::load_policy_conn(conn)?;
+    let policy_columns = policy
+        .encrypted_fields()
+        .into_iter()
+        .flat_map(|field| field.columns().iter().copied());
+    for (table, id_column, column) in ENCRYPTED_COLUMNS.iter().copied().chain(policy_columns) {
+        let select_sql = format!(
+            "SELECT {id_column}, {column} FROM {table} WHERE {column} IS NOT NULL AND {column} != ''"
+        );
+        let update_sql = format!("UPDATE {table} SET {column} = ?1 WHERE {id_column} = ?2");
+        let rows: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare(&select_sql).map_err(|e| e.to_string())?;
+            let iter = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?;
+            iter.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+        };
+        for (id, cipher) in rows {
+            match from.decrypt(&cipher) {
+                Ok(plain) => {
+                    conn.execute(&update_sql, (to.encrypt(plain.expose())?, id))
+                        .map_err(|e| e.to_string())?;
+                    count += 1;
+                }
+                Err(e) if strict => return Err(format!("无法解密 {}.{} (id={}): {}", table, column, id, e)),
+                Err(e) => log::warn!("重新加密 {}.{} (id={}) 失败，保留原值: {}", table, column, id, e),
+            }
+        }
+    }
+
+    for key in SENSITIVE_SETTING_KEYS {
+        let cipher: Option<String> = conn
+            .query_row("SELECT value FROM user_settings WHERE key = ?1", [key], |row| row.get(0))
+            .ok()
+            .flatten();
+        let Some(cipher) = cipher.filter(|v| !v.trim().is_empty()) else {
+            continue;
+        };
+        match from.decrypt(&cipher) {
+            Ok(plain) => {
+                conn.execute(
+                    "UPDATE user_settings SET value = ?1 WHERE key = ?2",
+                    (to.encrypt(plain.expose())?, key),
+                )
+                .map_err(|e| e.to_string())?;
+                count += 1;
+            }
+            Err(e) if strict => return Err(format!("无法解密配置 {}: {}", key, e)),
+            Err(e) => log::warn!("重新加密配置 {} 失败，保留原值: {}", key, e),
+        }
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn fast_params() -> KdfParams {
+        KdfParams {
+            algorithm: KDF_PBKDF2_SHA256.to_string(),
+            iterations: 10,
+            memory_kib: None,
+            parallelism: None,
+        }
+    }
+
+    fn open_db(dir: &tempfile::TempDir) -> DatabaseService {
+        let db = DatabaseService::new(dir.path().join("vault.db").to_str().unwrap());
+        db.initialize().unwrap();
+        db
+    }
+
+    #[test]
+    fn test_wrap_roundtrip_with_password() {
+        let data_key = generate_data_key();
+        let wrapped = wrap_with_password(&data_key, "correct horse", &fast_params()).unwrap();
+        assert_eq!(wrapped.kind, KEY_KIND_MASTER);
+
+        let unwrapped = unwrap_with_password(&wrapped, "correct horse").unwrap();
+        assert_eq!(unwrapped.key_bytes(), data_key.key_bytes());
+        assert!(unwrap_with_password(&wrapped, "wrong horse")
+            .map(|k| k.key_bytes() != data_key.key_bytes())
+            .unwrap_or(true));
+    }
+
+    #[test]
+    fn test_wrap_roundtrip_with_argon2id() {
+        let data_key = generate_data_key();
+        let params = KdfParams::argon2id(&HashParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        });
+        let wrapped = wrap_with_password(&data_key, "pw", &params).unwrap();
+        assert!(wrapped.kdf_params.as_deref().unwrap().contains("argon2id"));
+        let unwrapped = unwrap_with_password(&wrapped, "pw").unwrap();
+        assert_eq!(unwrapped.key_bytes(), data_key.key_bytes());
+    }
+
+    #[test]
+    fn test_upgrade_ciphertexts_rewrites_legacy_cbc() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let data_key = open_on_startup(&db).unwrap().unwrap();
+        let conn = db.get_connection().unwrap();
+        conn.execute(
+            "INSERT INTO passwords (title, password) VALUES ('old', ?1), ('new', ?2)",
+            [data_key.encrypt_legacy_cbc("old-secret"), data_key.encrypt("new-secret").unwrap()],
+        )
+        .unwrap();
+
+        let report = upgrade_ciphertexts(&db, &data_key).unwrap();
+        assert_eq!(report, CipherUpgradeReport { upgraded: 1, failed: 0 });
+
+        let stored: String = conn
+            .query_row("SELECT password FROM passwords WHERE title = 'old'", [], |row| row.get(0))
+            .unwrap();
+        assert!(!data_key.needs_upgrade(&stored));
+
+        // 升级完成后重新打开的数据密钥拒绝旧格式
+        let reopened = open_on_startup(&db).unwrap().unwrap();
+        assert_eq!(reopened.decrypt(&stored).unwrap().expose(), "old-secret");
+        assert!(reopened.decrypt(&data_key.encrypt_legacy_cbc("x")).is_err());
+        assert_eq!(upgrade_ciphertexts(&db, &reopened).unwrap(), CipherUpgradeReport::default());
+    }
+
+    #[test]
+    fn test_startup_migrates_legacy_rows_without_master_password() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let legacy = EncryptionService::new_with_app_key();
+        let conn = db.get_connection().unwrap();
+        conn.execute(
+            "INSERT INTO passwords (title, password) VALUES ('legacy', ?1)",
+            [legacy.encrypt("s3cret").unwrap()],
+        )
+        .unwrap();
+
+        let data_key = open_on_startup(&db).unwrap().expect("vault should be unlocked");
+        let stored: String = conn
+            .query_row("SELECT password FROM passwords WHERE title = 'legacy'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(data_key.decrypt(&stored).unwrap().expose(), "s3cret");
+        assert!(legacy.decrypt(&stored).map(|p| p.expose() != "s3cret").unwrap_or(true));
+
+        // 再次启动复用同一数据密钥
+        let reopened = open_on_startup(&db).unwrap().unwrap();
+        assert_eq!(reopened.key_bytes(), data_key.key_bytes());
+    }
+
+    #[test]
+    fn test_legacy_vault_with_required_password_stays_locked_until_unlock() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        db.set_master_password("hash", None).unwrap();
+        let legacy = EncryptionService::new_with_app_key();
+        let conn = db.get_connection().unwrap();
+        conn.execute(
+            "INSERT INTO secure_records (title, content) VALUES ('note', ?1)",
+            [legacy.encrypt("body").unwrap()],
+        )
+        .unwrap();
+
+        assert!(open_on_startup(&db).unwrap().is_none());
+
+        let data_key = unlock_with_password(&db, "master-pass", &fast_params()).unwrap();
+        let stored: String = conn
+            .query_row("SELECT content FROM secure_records", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(data_key.decrypt(&stored).unwrap().expose(), "body");
+        assert!(db.get_vault_key(KEY_KIND_DEVICE).unwrap().is_none());
+        assert!(open_on_startup(&db).unwrap().is_none());
+
+        let again = unlock_with_password(&db, "master-pass", &fast_params()).unwrap();
+        assert_eq!(again.key_bytes(), data_key.key_bytes());
+    }
+
+    /// 建立一个已设置主密码且含各类密文的保险库
+    fn vault_with_master_password(db: &DatabaseService) -> EncryptionService {
+        db.set_master_password("old-hash", Some("hint")).unwrap();
+        let data_key = unlock_with_password(db, "old-pass", &fast_params()).unwrap();
+        let conn = db.get_connection().unwrap();
+        conn.execute(
+            "INSERT INTO passwords (title, password) VALUES ('mail', ?1)",
+            [data_key.encrypt("s3cret").unwrap()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO password_history (password_id, old_password) VALUES (1, ?1)",
+            [data_key.encrypt("older").unwrap()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO user_settings (key, value) VALUES (?1, ?2)",
+            [SENSITIVE_SETTING_KEYS[0].to_string(), data_key.encrypt("ak").unwrap()],
+        )
+        .unwrap();
+        data_key
+    }
+
+    fn stored_password(db: &DatabaseService) -> String {
+        db.get_connection()
+            .unwrap()
+            .query_row("SELECT password FROM passwords WHERE title = 'mail'", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rekey_rotates_data_key_and_reencrypts_rows() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let old_key = vault_with_master_password(&db);
+
+        let new_key = rekey_vault(&db, &old_key, "new-pass", "new-hash", Some("new hint"), &fast_params(), None).unwrap();
+        assert_ne!(new_key.key_bytes(), old_key.key_bytes());
+        assert!(db.get_vault_rekey().unwrap().is_none());
+        assert_eq!(db.get_master_password_hash().unwrap().as_deref(), Some("new-hash"));
+
+        let stored = stored_password(&db);
+        assert!(old_key.decrypt(&stored).is_err());
+        assert_eq!(new_key.decrypt(&stored).unwrap().expose(), "s3cret");
+        let conn = db.get_connection().unwrap();
+        let history: String = conn
+            .query_row("SELECT old_password FROM password_history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(new_key.decrypt(&history).unwrap().expose(), "older");
+        let setting: String = conn
+            .query_row("SELECT value FROM user_settings WHERE key = ?1", [SENSITIVE_SETTING_KEYS[0]], |row| row.get(0))
+            .unwrap();
+        assert_eq!(new_key.decrypt(&setting).unwrap().expose(), "ak");
+
+        let master = db.get_vault_key(KEY_KIND_MASTER).unwrap().unwrap();
+        assert_eq!(unwrap_with_password(&master, "new-pass").unwrap().key_bytes(), new_key.key_bytes());
+    }
+
+    #[test]
+    fn test_rekey_refuses_undecryptable_values() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let old_key = vault_with_master_password(&db);
+        db.get_connection()
+            .unwrap()
+            .execute(
+                "INSERT INTO secure_records (title, content) VALUES ('foreign', ?1)",
+                [generate_data_key().encrypt("lost").unwrap()],
+            )
+            .unwrap();
+
+        let err = rekey_vault(&db, &old_key, "new-pass", "new-hash", None, &fast_params(), None)
+            .err()
+            .expect("rekey should be refused");
+        assert!(err.contains("secure_records.content"), "{}", err);
+
+        // 事务回滚：数据、密钥包装与主密码哈希均保持原样
+        assert_eq!(db.get_master_password_hash().unwrap().as_deref(), Some("old-hash"));
+        assert_eq!(old_key.decrypt(&stored_password(&db)).unwrap().expose(), "s3cret");
+        let unlocked = unlock_with_password(&db, "old-pass", &fast_params()).unwrap();
+        assert_eq!(unlocked.key_bytes(), old_key.key_bytes());
+    }
+
+    #[test]
+    fn test_failed_rekey_is_not_resumed_on_next_unlock() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let old_key = vault_with_master_password(&db);
+        db.get_connection()
+            .unwrap()
+            .execute(
+                "INSERT INTO password_history (password_id, old_password) VALUES (1, ?1)",
+                [generate_data_key().encrypt("lost").unwrap()],
+            )
+            .unwrap();
+        assert!(rekey_vault(&db, &old_key, "new-pass", "new-hash", None, &fast_params(), None).is_err());
+        assert!(db.get_vault_rekey().unwrap().is_none());
+
+        // 下次用旧主密码解锁不会继续这次已失败的修改
+        let unlocked = unlock_with_password(&db, "old-pass", &fast_params()).unwrap();
+        assert!(resume_rekey(&db, &unlocked).unwrap().is_none());
+        assert_eq!(unlocked.key_bytes(), old_key.key_bytes());
+        assert_eq!(db.get_master_password_hash().unwrap().as_deref(), Some("old-hash"));
+        let master = db.get_vault_key(KEY_KIND_MASTER).unwrap().unwrap();
+        assert!(unwrap_with_password(&master, "new-pass")
+            .map(|k| k.key_bytes() != old_key.key_bytes())
+            .unwrap_or(true));
+    }
+
+    #[test]
+    fn test_interrupted_rekey_is_resumed_with_old_key() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let old_key = vault_with_master_password(&db);
+
+        // 模拟任务记录已写入、重新加密事务尚未提交时崩溃
+        let new_key = generate_data_key();
+        db.save_vault_rekey(&VaultRekey {
+            wrapped_new_key: wrap_data_key(&old_key, &new_key).unwrap(),
+            new_keys: vec![
+                wrap_with_password(&new_key, "new-pass", &fast_params()).unwrap(),
+                wrap_with_recovery_key(&new_key, generate_recovery_key().expose()).unwrap(),
+            ],
+            password_hash: Some("new-hash".to_string()),
+            hint: None,
+            started_at: None,
+        })
+        .unwrap();
+        assert_eq!(old_key.decrypt(&stored_password(&db)).unwrap().expose(), "s3cret");
+
+        // 旧主密码仍可解锁，随后完成轮换
+        let unlocked = unlock_with_password(&db, "old-pass", &fast_params()).unwrap();
+        let resumed = resume_rekey(&db, &unlocked).unwrap().expect("rekey should resume");
+        assert_eq!(resumed.key_bytes(), new_key.key_bytes());
+        assert_eq!(resumed.decrypt(&stored_password(&db)).unwrap().expose(), "s3cret");
+        assert_eq!(db.get_master_password_hash().unwrap().as_deref(), Some("new-hash"));
+        assert!(db.get_vault_key(KEY_KIND_RECOVERY).unwrap().is_none());
+        assert!(resume_rekey(&db, &resumed).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_stale_rekey_journal_is_discarded() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let data_key = vault_with_master_password(&db);
+        let other = generate_data_key();
+        db.save_vault_rekey(&VaultRekey {
+            wrapped_new_key: wrap_data_key(&other, &generate_data_key()).unwrap(),
+            new_keys: Vec::new(),
+            password_hash: None,
+            hint: None,
+            started_at: None,
+        })
+        .unwrap();
+
+        assert!(resume_rekey(&db, &data_key).unwrap().is_none());
+        assert!(db.get_vault_rekey().unwrap().is_none());
+        assert_eq!(data_key.decrypt(&stored_password(&db)).unwrap().expose(), "s3cret");
+    }
+
+    #[test]
+    fn test_key_rotation_waits_for_leases() {
+        let leases = KeyLeases::default();
+        let lease = leases.lease(|| Ok(1)).unwrap();
+        assert_eq!(*lease, 1);
+        assert!(leases.lease(|| Err::<(), _>("locked".to_string())).is_err());
+
+        // 有租约未归还时轮换超时放弃，之后仍可发放租约
+        assert!(leases.begin_rotation(Duration::from_millis(10)).is_err());
+        assert!(leases.lease(|| Ok(())).is_ok());
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                drop(lease);
+            });
+            let rotation = leases.begin_rotation(Duration::from_secs(5)).unwrap();
+            // 轮换期间拒绝新租约而不是等待
+            assert!(leases.lease(|| Ok(())).is_err());
+            drop(rotation);
+        });
+        assert!(leases.lease(|| Ok(())).is_ok());
+    }
+
+    #[test]
+    fn test_recovery_key_format_and_unlock() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let data_key = vault_with_master_password(&db);
+
+        let recovery_key = generate_recovery_key();
+        let groups: Vec<&str> = recovery_key.expose().split('-').collect();
+        assert_eq!(groups.len(), 8);
+        assert!(groups.iter().all(|g| g.len() == 4));
+        assert_ne!(recovery_key.expose(), generate_recovery_key().expose());
+
+        db.replace_vault_keys(&[wrap_with_recovery_key(&data_key, recovery_key.expose()).unwrap()], &[])
+            .unwrap();
+        // 输入时允许小写与空白
+        let typed = recovery_key.expose().to_lowercase().replace('-', " ");
+        let unlocked = unlock_with_recovery_key(&db, &typed).unwrap();
+        assert_eq!(unlocked.key_bytes(), data_key.key_bytes());
+
+        let other = generate_recovery_key();
+        assert_eq!(unlock_with_recovery_key(&db, other.expose()).err().as_deref(), Some("恢复密钥错误"));
+        assert_eq!(unlock_with_recovery_key(&db, "not-a-key").err().as_deref(), Some("恢复密钥格式无效"));
+    }
+
+    #[test]
+    fn test_rekey_replaces_recovery_key() {
+        let dir = tempdir().unwrap();
+        let db = open_db(&dir);
+        let old_key = vault_with_master_password(&db);
+        let old_recovery = generate_recovery_key();
+        db.replace_vault_keys(&[wrap_with_recovery_key(&old_key, old_recovery.expose()).unwrap()], &[])
+            .unwrap();
+
+        let new_recovery = generate_recovery_key();
+        let new_key = rekey_vault(
+            &db,
+            &old_key,
+            "new-pass",
+            "new-hash",
+            None,
+            &fast_params(),
+            Some(new_recovery.expose()),
+        )
+        .unwrap();
+        assert!(unlock_with_recovery_key(&db, old_recovery.expose()).is_err());
+        assert_eq!(
+            unlock_with_recovery_key(&db, new_recovery.expose()).unwrap().key_bytes(),
+            new_key.key_bytes()
+        );
+    }
+}
@@ -35,6 +35,7 @@ import './styles/global.css';
 import { buildPasswordColumns } from './columns/passwordColumns';
 import { buildHistoryColumns } from './columns/historyColumns';
 import MasterPasswordGate from './components/MasterPasswordGate';
+import { showRecoveryKey } from './components/RecoveryKeyNotice';
 
 // 从preload导入类型
 import type { Group, MasterPasswordState } from '../shared/types';
@@ -191,6 +192,17 @@ const App: React.FC = () => {
     }
   }, [locked, selectedGroupId, loadGroups, loadPasswords, loadRecentPasswords]);
 
+  useEffect(() => {
+    if (!window.electronAPI?.onVaultLocked) return;
+    let unlisten: (() => void) | undefined;
+    window.electronAPI
+      .onVaultLocked(() => setLocked(true))
+      .then((fn) => {
+        unlisten = fn;
+      });
+    return () => unlisten?.();
+  }, []);
+
   useEffect(() => {
     if (locked || checkingSecurity) return;
     if (selectedGroupId) {
@@ -278,6 +290,9 @@ const App: React.FC = () => {
         message.error(msg);
         throw new Error(msg);
       }
+      if (res.recoveryKey) {
+        await showRecoveryKey(res.recoveryKey);
+      }
       setSecurityState(res.state || securityState);
       message.success('主密码已设置并启用');
       setLocked(false);
@@ -288,6 +303,30 @@ const App: React.FC = () => {
     [loadGroups, loadRecentPasswords, resetAutoLockTimer, securityState]
   );
 
+  const handleRecover = useCallback(
+    async (recoveryKey: string, newPassword: string, hint?: string) => {
+      setSecurityLoading(true);
+      const res = await securityService.unlockWithRecoveryKey(recoveryKey, newPassword, hint);
+      setSecurityLoading(false);
+      if (!res.success) {
+        const msg = res.error || '恢复密钥不正确';
+        message.error(msg);
+        throw new Error(msg);
+      }
+      // 恢复密钥只能使用一次，必须先让用户保存新签发的恢复密钥
+      if (res.recoveryKey) {
+        await showRecoveryKey(res.recoveryKey);
+      }
+      setSecurityState(res.state || securityState);
+      message.success('已使用恢复密钥解锁，主密码已重置');
+      setLocked(false);
+      resetAutoLockTimer();
+      await loadGroups();
+      await loadRecentPasswords();
+    },
+    [loadGroups, loadRecentPasswords, resetAutoLockTimer, securityState]
+  );
+
   useEffect(() => {
     const refreshState = async () => {
       try {
@@ -1230,6 +1269,7 @@ const App: React.FC = () => {
         loading={securityLoading}
         onUnlock={handleUnlock}
         onSetup={handleSetupMaster}
+        onRecover={handleRecover}
       />
     </>
   );
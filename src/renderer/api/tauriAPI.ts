@@ -50,6 +50,8 @@ interface SecurityCommandResult {
   error?: string;
   errorCode?: string;
   cooldownSeconds?: number;
+  /** 新签发的恢复密钥，只在签发时返回一次 */
+  recoveryKey?: string;
 }
 
 /**
@@ -87,7 +89,7 @@ export interface TauriAPI {
   ): Promise<{ success: boolean; count: number; error?: string }>;
 
   // 分组管理
-  getGroups(): Promise<Group[]>;
+  getGroups(keyword?: string): Promise<Group[]>;
   getGroupTree(parentId?: number): Promise<GroupWithChildren[]>;
   getGroupById(id: number): Promise<Group | undefined>;
   getGroupByName(name: string, parentId?: number): Promise<Group | undefined>;
@@ -133,12 +135,26 @@ export interface TauriAPI {
 
   // 密码生成
   generatePassword(options: {
+    mode?: 'random' | 'passphrase';
     length?: number;
     includeUppercase?: boolean;
     includeLowercase?: boolean;
     includeNumbers?: boolean;
     includeSymbols?: boolean;
-  }): Promise<string>;
+    minUppercase?: number;
+    minLowercase?: number;
+    minNumbers?: number;
+    minSymbols?: number;
+    excludeChars?: string;
+    excludeAmbiguous?: boolean;
+    customSymbols?: string;
+    noRepeats?: boolean;
+    noSequences?: boolean;
+    wordCount?: number;
+    separator?: string;
+    capitalize?: boolean;
+    includeNumber?: boolean;
+  }): Promise<{ password: string; entropyBits: number }>;
 
   // 系统相关
   getVersion(): Promise<string>;
@@ -213,6 +229,9 @@ export interface TauriAPI {
       error?: string;
     }) => void
   ) => Promise<() => void>;
+  onVaultLocked?: (
+    handler: (payload: { reason: string }) => void
+  ) => Promise<() => void>;
 
   getBackupConfig(): Promise<BackupConfig>;
   saveBackupConfig(
@@ -280,6 +299,17 @@ export interface TauriAPI {
     hint?: string,
     currentPassword?: string
   ): Promise<SecurityCommandResult>;
+  unlockWithRecoveryKey(
+    recoveryKey: string,
+    newPassword: string,
+    hint?: string
+  ): Promise<SecurityCommandResult>;
+  regenerateRecoveryKey(
+    currentPassword: string
+  ): Promise<SecurityCommandResult>;
+  revokeRecoveryKey(
+    currentPassword: string
+  ): Promise<SecurityCommandResult>;
 }
 
 /**
@@ -307,7 +337,7 @@ const realTauriAPI: TauriAPI = {
   cleanOldHistory: (daysToKeep) => invoke('clean_old_history', { daysToKeep }),
 
   // 分组管理
-  getGroups: () => invoke('get_groups', {}),
+  getGroups: (keyword) => invoke('get_groups', { keyword }),
   getGroupTree: (parentId) => invoke('get_group_tree', { parentId }),
   getGroupById: (id) => invoke('get_group_by_id', { id }),
   getGroupByName: (name, parentId) =>
@@ -374,6 +404,11 @@ const realTauriAPI: TauriAPI = {
       )
     );
   },
+  onVaultLocked: (handler) => {
+    return listen('vault-locked', (event) =>
+      handler(event.payload as { reason: string })
+    );
+  },
 
   // 笔记相关
   getNoteGroups: () => invoke('get_note_groups', {}),
@@ -412,6 +447,16 @@ const realTauriAPI: TauriAPI = {
       hint, 
       currentPassword 
     }),
+  unlockWithRecoveryKey: (recoveryKey, newPassword, hint) =>
+    invoke('security_unlock_with_recovery_key', {
+      recoveryKey,
+      newPassword,
+      hint,
+    }),
+  regenerateRecoveryKey: (currentPassword) =>
+    invoke('security_regenerate_recovery_key', { currentPassword }),
+  revokeRecoveryKey: (currentPassword) =>
+    invoke('security_revoke_recovery_key', { currentPassword }),
 };
 
 /**
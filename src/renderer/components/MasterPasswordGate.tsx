@@ -9,6 +9,7 @@ interface MasterPasswordGateProps {
   loading?: boolean;
   onUnlock: (password: string) => Promise<void> | void;
   onSetup: (password: string, hint?: string) => Promise<void> | void;
+  onRecover: (recoveryKey: string, newPassword: string, hint?: string) => Promise<void> | void;
 }
 
 const MasterPasswordGate: React.FC<MasterPasswordGateProps> = ({
@@ -17,32 +18,47 @@ const MasterPasswordGate: React.FC<MasterPasswordGateProps> = ({
   loading,
   onUnlock,
   onSetup,
+  onRecover,
 }) => {
   const [form] = Form.useForm();
   const [error, setError] = useState<string>('');
+  const [recovering, setRecovering] = useState(false);
   const hasMaster = state?.hasMasterPassword;
+  // 使用恢复密钥时与首次设置一样需要输入并确认新主密码
+  const choosingPassword = !hasMaster || recovering;
 
   const title = useMemo(() => {
     if (!hasMaster) return '设置主密码';
+    if (recovering) return '使用恢复密钥解锁';
     return '输入主密码解锁';
-  }, [hasMaster]);
+  }, [hasMaster, recovering]);
 
   const description = useMemo(() => {
     if (!hasMaster) return '主密码用于锁定应用和保护数据，请务必牢记。';
+    if (recovering) return '恢复密钥只能使用一次：解锁后需要设置新的主密码，并保存新签发的恢复密钥。';
     return '已启用主密码访问控制，输入后解锁应用。';
-  }, [hasMaster]);
+  }, [hasMaster, recovering]);
+
+  const toggleRecovering = () => {
+    setRecovering((value) => !value);
+    setError('');
+    form.resetFields();
+  };
 
   const handleFinish = async (values: any) => {
     try {
       setError('');
-      if (hasMaster) {
-        await onUnlock(values.password);
-      } else {
-        if (values.password !== values.confirmPassword) {
-          setError('两次输入的主密码不一致');
-          return;
-        }
+      if (choosingPassword && values.password !== values.confirmPassword) {
+        setError('两次输入的主密码不一致');
+        return;
+      }
+      if (!hasMaster) {
         await onSetup(values.password, values.hint);
+      } else if (recovering) {
+        await onRecover(values.recoveryKey, values.password, values.hint);
+        setRecovering(false);
+      } else {
+        await onUnlock(values.password);
       }
       form.resetFields();
     } catch (err) {
@@ -66,19 +82,31 @@ const MasterPasswordGate: React.FC<MasterPasswordGateProps> = ({
         extra={<SafetyCertificateOutlined />}
       >
         <Typography.Paragraph style={{ marginBottom: 12 }}>{description}</Typography.Paragraph>
-        {state?.hint && hasMaster && (
+        {state?.hint && hasMaster && !recovering && (
           <Alert type="info" message={`提示：${state.hint}`} style={{ marginBottom: 12 }} />
         )}
         {error && <Alert type="error" message={error} style={{ marginBottom: 12 }} />}
         <Form form={form} layout="vertical" onFinish={handleFinish}>
+          {recovering && (
+            <Form.Item
+              label="恢复密钥"
+              name="recoveryKey"
+              rules={[{ required: true, message: '请输入恢复密钥' }]}
+            >
+              <Input.Password placeholder="输入保存的恢复密钥" autoFocus />
+            </Form.Item>
+          )}
           <Form.Item
-            label="主密码"
+            label={recovering ? '新主密码' : '主密码'}
             name="password"
             rules={[{ required: true, message: '请输入主密码' }, { min: 6, message: '至少6位字符' }]}
           >
-            <Input.Password placeholder={hasMaster ? '输入主密码以解锁' : '设置主密码'} autoFocus />
+            <Input.Password
+              placeholder={choosingPassword ? '设置主密码' : '输入主密码以解锁'}
+              autoFocus={!recovering}
+            />
           </Form.Item>
-          {!hasMaster && (
+          {choosingPassword && (
             <>
               <Form.Item
                 label="确认主密码"
@@ -94,10 +122,15 @@ const MasterPasswordGate: React.FC<MasterPasswordGateProps> = ({
           )}
           <Form.Item style={{ marginBottom: 0 }}>
             <Button type="primary" htmlType="submit" block loading={loading}>
-              {hasMaster ? '解锁' : '保存主密码并解锁'}
+              {choosingPassword ? '保存主密码并解锁' : '解锁'}
             </Button>
           </Form.Item>
         </Form>
+        {hasMaster && state?.hasRecoveryKey && (
+          <Button type="link" block onClick={toggleRecovering} style={{ marginTop: 8 }}>
+            {recovering ? '返回主密码解锁' : '忘记主密码？使用恢复密钥'}
+          </Button>
+        )}
         {state?.requireMasterPassword && (
           <Typography.Text type="secondary" style={{ fontSize: 12 }}>
             自动锁定：{state.autoLockMinutes} 分钟无操作后需要重新输入主密码
@@ -15,6 +15,7 @@ const PasswordGenerator: React.FC<PasswordGeneratorProps> = ({
 }) => {
   const [form] = Form.useForm();
   const [generatedPassword, setGeneratedPassword] = useState('');
+  const [entropyBits, setEntropyBits] = useState(0);
   const [loading, setLoading] = useState(false);
 
   useEffect(() => {
@@ -54,8 +55,9 @@ const PasswordGenerator: React.FC<PasswordGeneratorProps> = ({
   }) => {
     setLoading(true);
     try {
-      const password = await window.electronAPI.generatePassword(values);
-      setGeneratedPassword(password);
+      const result = await window.electronAPI.generatePassword(values);
+      setGeneratedPassword(result.password);
+      setEntropyBits(result.entropyBits);
     } catch (error) {
       message.error('密码生成失败');
       reportError('PASSWORD_GENERATE_FAILED', 'Generate password error', error, values);
@@ -153,7 +155,7 @@ const PasswordGenerator: React.FC<PasswordGeneratorProps> = ({
         </Form.Item>
 
         {generatedPassword && (
-          <Form.Item label="生成的密码">
+          <Form.Item label="生成的密码" extra={`熵约 ${Math.round(entropyBits)} 位`}>
             <Input.Password
               value={generatedPassword}
               readOnly
@@ -0,0 +1,36 @@
+import React from 'react';
+import { Alert, Modal, Typography } from 'antd';
+
+/**
+ * 展示新签发的恢复密钥，用户确认已保存后 resolve
+ *
+ * 恢复密钥只在签发时返回一次，关闭后无法再次查看，因此弹窗只能通过确认按钮关闭。
+ */
+export function showRecoveryKey(recoveryKey: string): Promise<void> {
+  return new Promise((resolve) => {
+    Modal.info({
+      title: '请保存新的恢复密钥',
+      width: 520,
+      keyboard: false,
+      okText: '我已妥善保存',
+      content: (
+        <>
+          <Alert
+            type="warning"
+            showIcon
+            style={{ margin: '12px 0' }}
+            message="恢复密钥只显示这一次，忘记主密码时需要用它解锁。之前的恢复密钥（如有）已失效。"
+          />
+          <Typography.Paragraph
+            code
+            copyable={{ text: recoveryKey }}
+            style={{ fontSize: 16, wordBreak: 'break-all' }}
+          >
+            {recoveryKey}
+          </Typography.Paragraph>
+        </>
+      ),
+      onOk: () => resolve(),
+    });
+  });
+}
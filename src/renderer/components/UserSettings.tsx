@@ -42,6 +42,7 @@ import { useIntegrity } from '../hooks/useIntegrity';
 import { reportError } from '../utils/logging';
 import * as securityService from '../services/security';
 import * as backupService from '../services/backup';
+import { showRecoveryKey } from './RecoveryKeyNotice';
 
 const { Title } = Typography;
 const { Option } = Select;
@@ -74,9 +75,9 @@ const UserSettings: React.FC<UserSettingsProps> = ({ onClose }) => {
   const [securityState, setSecurityState] =
     useState<MasterPasswordState | null>(null);
   const [masterModalVisible, setMasterModalVisible] = useState(false);
-  const [masterMode, setMasterMode] = useState<'set' | 'update' | 'disable'>(
-    'set'
-  );
+  const [masterMode, setMasterMode] = useState<
+    'set' | 'update' | 'disable' | 'regenerateRecovery' | 'revokeRecovery'
+  >('set');
   const [masterSaving, setMasterSaving] = useState(false);
   const [masterForm] = Form.useForm();
   const [selectingExportDirectory, setSelectingExportDirectory] =
@@ -666,14 +667,33 @@ const UserSettings: React.FC<UserSettingsProps> = ({ onClose }) => {
           values.newPassword,
           values.hint
         );
+        // 修改主密码会轮换数据密钥，旧恢复密钥随之失效，须先展示新的再提示成功
+        if (res.success && res.recoveryKey) {
+          await showRecoveryKey(res.recoveryKey);
+        }
         if (res.success && res.state) {
           message.success('主密码已更新');
         }
+      } else {
+      } else if (masterMode === 'regenerateRecovery') {
+        res = await securityService.regenerateRecoveryKey(values.currentPassword);
+        if (res.success && res.recoveryKey) {
+          await showRecoveryKey(res.recoveryKey);
+          message.success('恢复密钥已重新生成');
+        }
+      } else if (masterMode === 'revokeRecovery') {
+        res = await securityService.revokeRecoveryKey(values.currentPassword);
+        if (res.success && res.state) {
+          message.success('恢复密钥已撤销');
+        }
       } else {
         res = await window.electronAPI.setMasterPassword(
           values.newPassword,
           values.hint
         );
+        if (res.success && res.recoveryKey) {
+          await showRecoveryKey(res.recoveryKey);
+        }
         if (res.success && res.state) {
           message.success('主密码已设置');
         }
@@ -726,6 +746,9 @@ const UserSettings: React.FC<UserSettingsProps> = ({ onClose }) => {
       try {
         const res = await securityService.setRequireMasterPassword(true);
         if (!res.success) throw new Error(res.error || '启用失败');
+        if (res.recoveryKey) {
+          await showRecoveryKey(res.recoveryKey);
+        }
         setSecurityState(res.state || null);
         form.setFieldsValue({
           requireMasterPassword: res.state?.requireMasterPassword ?? true,
@@ -830,6 +853,29 @@ const UserSettings: React.FC<UserSettingsProps> = ({ onClose }) => {
                       >
                         {securityState?.hasMasterPassword ? '修改主密码' : '设置主密码'}
                       </Button>
+                      {securityState?.hasMasterPassword && (
+                        <Button
+                          onClick={() => {
+                            setMasterMode('regenerateRecovery');
+                            masterForm.resetFields();
+                            setMasterModalVisible(true);
+                          }}
+                        >
+                          {securityState.hasRecoveryKey ? '重新生成恢复密钥' : '生成恢复密钥'}
+                        </Button>
+                      )}
+                      {securityState?.hasRecoveryKey && (
+                        <Button
+                          danger
+                          onClick={() => {
+                            setMasterMode('revokeRecovery');
+                            masterForm.resetFields();
+                            setMasterModalVisible(true);
+                          }}
+                        >
+                          撤销恢复密钥
+                        </Button>
+                      )}
                     </Space>
                   </Space>
                 </div>
@@ -1581,7 +1627,11 @@ const UserSettings: React.FC<UserSettingsProps> = ({ onClose }) => {
             ? '关闭主密码解锁要求'
             : masterMode === 'update'
               ? '修改主密码'
-              : '设置主密码'
+              : masterMode === 'regenerateRecovery'
+                ? '重新生成恢复密钥'
+                : masterMode === 'revokeRecovery'
+                  ? '撤销恢复密钥'
+                  : '设置主密码'
         }
         open={masterModalVisible}
         onCancel={() => {
@@ -1600,8 +1650,8 @@ const UserSettings: React.FC<UserSettingsProps> = ({ onClose }) => {
         destroyOnClose
       >
         <Form layout="vertical" form={masterForm}>
-          {/* 需要输入当前密码的模式: disable, update */}
-          {(masterMode === 'disable' || masterMode === 'update') && (
+          {/* 需要输入当前密码的模式: 除 set 以外全部 */}
+          {masterMode !== 'set' && (
             <Form.Item
               label="当前主密码"
               name="currentPassword"
@@ -1654,6 +1704,18 @@ const UserSettings: React.FC<UserSettingsProps> = ({ onClose }) => {
               message="关闭后将不再要求输入主密码解锁，但主密码仍会保留，可随时重新开启。"
             />
           )}
+          {masterMode === 'regenerateRecovery' && (
+            <Alert
+              type="warning"
+              message="生成后旧的恢复密钥立即失效，新的恢复密钥只显示一次。"
+            />
+          )}
+          {masterMode === 'revokeRecovery' && (
+            <Alert
+              type="warning"
+              message="撤销后忘记主密码将无法恢复数据。"
+            />
+          )}
         </Form>
       </Modal>
     </div>
@@ -13,7 +13,8 @@ const store = {
     requireMasterPassword: false,
     hint: '',
     autoLockMinutes: 5,
-    lastUnlockAt: ''
+    lastUnlockAt: '',
+    hasRecoveryKey: false
   },
   backupConfig: {
     targetMode: 'local',
@@ -174,7 +175,7 @@ const electronAPI = {
     for (let i = 0; i < length; i++) {
       password += charset.charAt(Math.floor(Math.random() * charset.length));
     }
-    return Promise.resolve(password);
+    return Promise.resolve({ password, entropyBits: length * Math.log2(charset.length) });
   },
   
   // 系统相关
@@ -239,7 +240,8 @@ const electronAPI = {
   setMasterPassword: (password: string, _hint?: string) => {
     store.security.hasMasterPassword = true;
     store.security.requireMasterPassword = true;
-    return Promise.resolve({ success: true, state: store.security });
+    store.security.hasRecoveryKey = true;
+    return Promise.resolve({ success: true, state: store.security, recoveryKey: 'MOCK-RECOVERY-KEY' });
   },
   verifyMasterPassword: (_password: string) =>
     Promise.resolve({ success: true, state: store.security }),
@@ -261,6 +263,16 @@ const electronAPI = {
     store.security.requireMasterPassword = require;
     return Promise.resolve({ success: true, state: { ...store.security } });
   },
+  unlockWithRecoveryKey: (_recoveryKey: string, _newPassword: string, _hint?: string) =>
+    Promise.resolve({ success: true, state: { ...store.security }, recoveryKey: 'MOCK-RECOVERY-KEY' }),
+  regenerateRecoveryKey: (_currentPassword: string) => {
+    store.security.hasRecoveryKey = true;
+    return Promise.resolve({ success: true, state: { ...store.security }, recoveryKey: 'MOCK-RECOVERY-KEY' });
+  },
+  revokeRecoveryKey: (_currentPassword: string) => {
+    store.security.hasRecoveryKey = false;
+    return Promise.resolve({ success: true, state: { ...store.security } });
+  },
 
   // Note management
   getNoteGroups: () => Promise.resolve([...store.noteGroups]),
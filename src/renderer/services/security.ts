@@ -43,3 +43,15 @@ export async function setRequireMasterPassword(
     options?.currentPassword
   );
 }
+
+export async function unlockWithRecoveryKey(recoveryKey: string, newPassword: string, hint?: string) {
+  return window.electronAPI.unlockWithRecoveryKey(recoveryKey, newPassword, hint);
+}
+
+export async function regenerateRecoveryKey(currentPassword: string) {
+  return window.electronAPI.regenerateRecoveryKey(currentPassword);
+}
+
+export async function revokeRecoveryKey(currentPassword: string) {
+  return window.electronAPI.revokeRecoveryKey(currentPassword);
+}
@@ -115,6 +115,7 @@ export interface MasterPasswordState {
   hint?: string;
   autoLockMinutes: number;
   lastUnlockAt?: string;
+  hasRecoveryKey?: boolean;
 }
 
 export type AutoExportFrequency =